@@ -1,3 +1,5 @@
 fn main() {
+    // Sin la interfaz (school_roster-cli) no hay nada que preparar
+    #[cfg(feature = "gui")]
     tauri_build::build()
 }
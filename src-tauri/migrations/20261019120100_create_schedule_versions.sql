@@ -0,0 +1,20 @@
+-- Versiones guardadas del horario (copias completas de `assignments`)
+CREATE TABLE IF NOT EXISTS schedule_versions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    note TEXT,
+    created_at INTEGER NOT NULL
+);
+
+-- Sin llaves foraneas a grupos/materias/profesores: la version se conserva aunque se borren
+CREATE TABLE IF NOT EXISTS schedule_version_assignments (
+    version_id INTEGER NOT NULL,
+    group_id INTEGER NOT NULL,
+    day TEXT NOT NULL,
+    module_index INTEGER NOT NULL,
+    subject_id INTEGER NOT NULL,
+    teacher_id INTEGER NOT NULL,
+    classroom_id INTEGER,
+    FOREIGN KEY (version_id) REFERENCES schedule_versions(id) ON DELETE CASCADE,
+    UNIQUE (version_id, group_id, day, module_index)
+);
@@ -0,0 +1,24 @@
+-- Historial de cambios al horario para deshacer/rehacer
+-- Cada operacion es un paso de deshacer y puede cambiar varias celdas
+CREATE TABLE IF NOT EXISTS operation_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    label TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    undone INTEGER NOT NULL DEFAULT 0
+);
+
+-- Estado de cada celda (grupo, dia, modulo) antes y despues de la operacion
+-- Materia en NULL significa que la celda estaba o quedo vacia
+CREATE TABLE IF NOT EXISTS operation_changes (
+    operation_id INTEGER NOT NULL,
+    group_id INTEGER NOT NULL,
+    day TEXT NOT NULL,
+    module_index INTEGER NOT NULL,
+    before_subject_id INTEGER,
+    before_teacher_id INTEGER,
+    before_classroom_id INTEGER,
+    after_subject_id INTEGER,
+    after_teacher_id INTEGER,
+    after_classroom_id INTEGER,
+    FOREIGN KEY (operation_id) REFERENCES operation_log(id) ON DELETE CASCADE
+);
@@ -0,0 +1,31 @@
+-- Ausencias de profesores (fechas "AAAA-MM-DD", las dos incluidas)
+CREATE TABLE IF NOT EXISTS absences (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    teacher_id INTEGER NOT NULL,
+    start_date TEXT NOT NULL,
+    end_date TEXT NOT NULL,
+    reason TEXT,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (teacher_id) REFERENCES teachers(id) ON DELETE CASCADE
+);
+
+-- Cambios al horario que solo aplican en una fecha (ej. un suplente)
+-- Reemplazan la clase del grupo en ese modulo sin tocar `assignments`
+-- Sin materia o sin profesor la clase no se da ese dia
+CREATE TABLE IF NOT EXISTS schedule_overrides (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    date TEXT NOT NULL,
+    group_id INTEGER NOT NULL,
+    module_index INTEGER NOT NULL,
+    subject_id INTEGER,
+    teacher_id INTEGER,
+    classroom_id INTEGER,
+    kind TEXT NOT NULL DEFAULT 'substitution',
+    absence_id INTEGER,
+    note TEXT,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (absence_id) REFERENCES absences(id) ON DELETE CASCADE,
+    UNIQUE (date, group_id, module_index)
+);
+
+CREATE INDEX IF NOT EXISTS idx_absences_dates ON absences (start_date, end_date);
@@ -0,0 +1,19 @@
+-- Periodos escolares (semestre, trimestre...) con sus fechas ("AAAA-MM-DD", las dos incluidas)
+CREATE TABLE IF NOT EXISTS terms (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    start_date TEXT NOT NULL,
+    end_date TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+-- Dias festivos y cierres de la escuela, no hay clases entre las dos fechas
+CREATE TABLE IF NOT EXISTS holidays (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    start_date TEXT NOT NULL,
+    end_date TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_holidays_dates ON holidays (start_date, end_date);
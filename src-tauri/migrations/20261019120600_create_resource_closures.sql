@@ -0,0 +1,19 @@
+-- Cierres de un grupo o de un aula entre dos fechas ("AAAA-MM-DD", las dos incluidas)
+-- `resource` es "group" (ej. salida de campo, el grupo no tiene clases)
+-- o "classroom" (ej. aula en reparacion, las clases se quedan sin aula)
+-- Sin `start_module` / `end_module` el cierre es de todo el dia
+CREATE TABLE IF NOT EXISTS resource_closures (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    resource TEXT NOT NULL CHECK (resource IN ('group', 'classroom')),
+    resource_id INTEGER NOT NULL,
+    start_date TEXT NOT NULL,
+    end_date TEXT NOT NULL,
+    start_module INTEGER,
+    end_module INTEGER,
+    kind TEXT NOT NULL DEFAULT 'closure',
+    note TEXT,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_resource_closures_dates ON resource_closures (start_date, end_date);
+CREATE INDEX IF NOT EXISTS idx_schedule_overrides_date ON schedule_overrides (date);
@@ -0,0 +1,52 @@
+-- Cada periodo guarda sus propios grupos, alumnos, horario y configuracion
+-- Los datos del periodo activo viven en las tablas normales (groups, assignments...)
+-- y al cambiar de periodo se guardan en estas tablas
+ALTER TABLE terms ADD COLUMN active INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE terms ADD COLUMN previous_term_id INTEGER;
+-- Fecha en que se guardaron los datos del periodo, NULL si nunca se han guardado
+ALTER TABLE terms ADD COLUMN archived_at INTEGER;
+ALTER TABLE terms ADD COLUMN schedule_config TEXT;
+
+CREATE TABLE IF NOT EXISTS term_groups (
+    term_id INTEGER NOT NULL,
+    group_id INTEGER NOT NULL,
+    grade INTEGER NOT NULL,
+    "group" TEXT NOT NULL,
+    career TEXT,
+    students INTEGER,
+    max_modules_per_day INTEGER,
+    PRIMARY KEY (term_id, group_id),
+    FOREIGN KEY (term_id) REFERENCES terms(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS term_groups_subjects (
+    term_id INTEGER NOT NULL,
+    group_id INTEGER NOT NULL,
+    subject_id INTEGER NOT NULL,
+    PRIMARY KEY (term_id, group_id, subject_id),
+    FOREIGN KEY (term_id) REFERENCES terms(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS term_students (
+    term_id INTEGER NOT NULL,
+    student_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    father_lastname TEXT NOT NULL,
+    mother_lastname TEXT,
+    group_id INTEGER,
+    PRIMARY KEY (term_id, student_id),
+    FOREIGN KEY (term_id) REFERENCES terms(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS term_assignments (
+    term_id INTEGER NOT NULL,
+    group_id INTEGER NOT NULL,
+    day TEXT NOT NULL,
+    module_index INTEGER NOT NULL,
+    subject_id INTEGER NOT NULL,
+    teacher_id INTEGER NOT NULL,
+    classroom_id INTEGER,
+    FOREIGN KEY (term_id) REFERENCES terms(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_term_assignments_term ON term_assignments (term_id);
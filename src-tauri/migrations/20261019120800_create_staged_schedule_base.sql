@@ -0,0 +1,7 @@
+-- Huella del horario sobre el que se genero el horario en prueba
+-- Si `assignments` cambia despues, el horario en prueba ya no se puede aplicar
+CREATE TABLE IF NOT EXISTS staged_schedule_base (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    fingerprint TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
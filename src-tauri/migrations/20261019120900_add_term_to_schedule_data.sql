@@ -0,0 +1,56 @@
+-- Las versiones del horario, los cambios por fecha, los cierres y las ausencias son del
+-- periodo en que se crearon: los ids de los grupos se repiten entre periodos
+-- `term_id` es 0 mientras no se ha elegido ningun periodo
+ALTER TABLE schedule_versions ADD COLUMN term_id INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE resource_closures ADD COLUMN term_id INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE absences ADD COLUMN term_id INTEGER NOT NULL DEFAULT 0;
+
+-- Los cambios por fecha se rehacen para que la celda sea unica dentro de cada periodo
+CREATE TABLE IF NOT EXISTS schedule_overrides_by_term (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    term_id INTEGER NOT NULL DEFAULT 0,
+    date TEXT NOT NULL,
+    group_id INTEGER NOT NULL,
+    module_index INTEGER NOT NULL,
+    subject_id INTEGER,
+    teacher_id INTEGER,
+    classroom_id INTEGER,
+    kind TEXT NOT NULL DEFAULT 'substitution',
+    absence_id INTEGER,
+    note TEXT,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (absence_id) REFERENCES absences(id) ON DELETE CASCADE,
+    UNIQUE (term_id, date, group_id, module_index)
+);
+
+INSERT INTO schedule_overrides_by_term
+    (id, date, group_id, module_index, subject_id, teacher_id, classroom_id, kind, absence_id,
+     note, created_at)
+SELECT id, date, group_id, module_index, subject_id, teacher_id, classroom_id, kind, absence_id,
+       note, created_at
+FROM schedule_overrides;
+
+DROP TABLE schedule_overrides;
+ALTER TABLE schedule_overrides_by_term RENAME TO schedule_overrides;
+CREATE INDEX IF NOT EXISTS idx_schedule_overrides_date ON schedule_overrides (date);
+
+-- Lo que ya existia es del periodo activo
+UPDATE schedule_versions SET term_id = COALESCE((SELECT id FROM terms WHERE active = 1), 0);
+UPDATE schedule_overrides SET term_id = COALESCE((SELECT id FROM terms WHERE active = 1), 0);
+UPDATE resource_closures SET term_id = COALESCE((SELECT id FROM terms WHERE active = 1), 0);
+UPDATE absences SET term_id = COALESCE((SELECT id FROM terms WHERE active = 1), 0);
+
+CREATE INDEX IF NOT EXISTS idx_schedule_versions_term ON schedule_versions (term_id);
+CREATE INDEX IF NOT EXISTS idx_schedule_overrides_term ON schedule_overrides (term_id);
+CREATE INDEX IF NOT EXISTS idx_resource_closures_term ON resource_closures (term_id);
+CREATE INDEX IF NOT EXISTS idx_absences_term ON absences (term_id);
+
+-- Al borrar un periodo se borran sus datos (las clases de las versiones y las suplencias
+-- se borran en cascada)
+CREATE TRIGGER IF NOT EXISTS delete_term_schedule_data AFTER DELETE ON terms
+BEGIN
+    DELETE FROM schedule_versions WHERE term_id = OLD.id;
+    DELETE FROM schedule_overrides WHERE term_id = OLD.id;
+    DELETE FROM resource_closures WHERE term_id = OLD.id;
+    DELETE FROM absences WHERE term_id = OLD.id;
+END;
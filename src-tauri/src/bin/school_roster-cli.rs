@@ -0,0 +1,374 @@
+/*
+    Linea de comandos para generar horarios sin abrir la interfaz.
+    Lee un archivo .roster (o .json / .yaml), ejecuta el motor y escribe el horario en .roster,
+    .json, .yaml o .csv; con --report guarda tambien el reporte de la generacion en JSON
+    Con --compare no genera nada: compara dos archivos e imprime la hoja de cambios
+    La contraseña de los .roster cifrados se toma de ROSTER_PASSWORD o de la entrada estandar
+    (--password-stdin) para que no quede en el historial ni en la lista de procesos
+
+    Uso:
+        school_roster-cli <entrada.roster> [--seed N] [--restarts N] [--threads N] [--time-limit SEGUNDOS] [--output RUTA] [--report RUTA]
+        school_roster-cli <antes.roster> --compare <despues.roster> [--output RUTA]
+*/
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use school_roster::engine::{
+    self, compare_schedules, format_change_sheet, GenerateOptions, GenerationReport, Snapshot,
+};
+use school_roster::schedule_file::{
+    read_schedule_file, write_schedule_file, RosterOptions, ScheduleData,
+};
+
+// Variable de entorno con la contraseña de los .roster cifrados
+const PASSWORD_VAR: &str = "ROSTER_PASSWORD";
+
+const USAGE: &str =
+    "Usage: school_roster-cli <input.roster> [--seed N] [--restarts N] [--threads N] [--time-limit SECONDS] [--output PATH] [--report PATH] [--password-stdin]
+       school_roster-cli <before.roster> --compare <after.roster> [--output PATH] [--password-stdin]
+
+Options:
+  <input>               The school data: .roster, or a .json / .yaml text file
+  --seed N              Seed for group/day order and teacher ties (same input + seed = same timetable)
+  --restarts N          Run N attempts with derived seeds and keep the best scoring one
+  --threads N           Worker threads for the attempts (defaults to all cores)
+  --time-limit SECONDS  Stop placing lessons after this many seconds (more than 0)
+  --output PATH         Where to write the timetable (.roster, .yaml, .json or .csv).
+                        Defaults to saving it into the input file, in its own format
+  --report PATH         Also write the generation report (score, missing subjects,
+                        warnings and violations) as JSON
+  --compare PATH        Don't generate: print the change sheet from the input to PATH.
+                        --output then writes the sheet (.txt) or the full diff (.json)
+  --password-stdin      Read the password for encrypted .roster files from the first
+                        line of stdin. A .roster output is encrypted with it too
+  --password PASS       Same, from the command line. It shows up in the shell history
+                        and the process list: prefer --password-stdin or ROSTER_PASSWORD
+
+Environment:
+  ROSTER_PASSWORD       Password used when neither --password nor --password-stdin is given";
+
+struct Args {
+    input: PathBuf,
+    output: Option<PathBuf>,
+    report: Option<PathBuf>,
+    compare: Option<PathBuf>,
+    password: Option<String>,
+    options: GenerateOptions,
+}
+
+fn main() {
+    let args = match parse_args(std::env::args().skip(1).collect()) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(&args) {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn parse_args(raw: Vec<String>) -> Result<Args, String> {
+    let mut input: Option<PathBuf> = None;
+    let mut output: Option<PathBuf> = None;
+    let mut report: Option<PathBuf> = None;
+    let mut compare: Option<PathBuf> = None;
+    let mut password: Option<String> = None;
+    let mut password_stdin = false;
+    let mut options = GenerateOptions::default();
+
+    let mut iter = raw.into_iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "--seed" => {
+                let value = iter.next().ok_or("--seed needs a value")?;
+                options.seed = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("Invalid seed: {}", value))?,
+                );
+            }
+            "--restarts" => {
+                let value = iter.next().ok_or("--restarts needs a value")?;
+                options.restarts = value
+                    .parse()
+                    .map_err(|_| format!("Invalid number of restarts: {}", value))?;
+            }
+            "--threads" => {
+                let value = iter.next().ok_or("--threads needs a value")?;
+                options.threads = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("Invalid number of threads: {}", value))?,
+                );
+            }
+            "--time-limit" => {
+                let value = iter.next().ok_or("--time-limit needs a value")?;
+                let seconds = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|s| s.is_finite() && *s > 0.0)
+                    .ok_or_else(|| format!("Invalid time limit: {}", value))?;
+                options.time_budget_ms = Some((seconds * 1000.0) as u64);
+            }
+            "--compare" => {
+                let value = iter.next().ok_or("--compare needs a value")?;
+                compare = Some(PathBuf::from(value));
+            }
+            "--password" => {
+                password = Some(iter.next().ok_or("--password needs a value")?);
+            }
+            "--password-stdin" => password_stdin = true,
+            "-o" | "--output" => {
+                let value = iter.next().ok_or("--output needs a value")?;
+                output = Some(PathBuf::from(value));
+            }
+            "--report" => {
+                let value = iter.next().ok_or("--report needs a value")?;
+                report = Some(PathBuf::from(value));
+            }
+            other if other.starts_with('-') => return Err(format!("Unknown option: {}", other)),
+            other => {
+                if input.is_some() {
+                    return Err(format!("Unexpected argument: {}", other));
+                }
+                input = Some(PathBuf::from(other));
+            }
+        }
+    }
+
+    let input = input.ok_or("Missing input file")?;
+    if compare.is_some() && report.is_some() {
+        return Err("--report can't be used with --compare".to_string());
+    }
+    if password.is_some() && password_stdin {
+        return Err("Use either --password or --password-stdin".to_string());
+    }
+    if password_stdin {
+        password = Some(read_password_line()?);
+    }
+    let password = password.or_else(|| std::env::var(PASSWORD_VAR).ok().filter(|p| !p.is_empty()));
+
+    Ok(Args {
+        input,
+        output,
+        report,
+        compare,
+        password,
+        options,
+    })
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    if let Some(other) = &args.compare {
+        return run_compare(
+            &args.input,
+            other,
+            args.output.as_deref(),
+            args.password.as_deref(),
+        );
+    }
+
+    let output = args.output.as_ref().unwrap_or(&args.input);
+    let mut data = read_roster(&args.input, args.password.as_deref())?;
+
+    let snapshot = Snapshot::from_schedule_data(&data);
+    if snapshot.group_subjects.is_empty() {
+        eprintln!("Warning: the file has no subjects assigned to any group");
+    }
+
+    let report = engine::generate(&snapshot, &args.options);
+    print!("{}", format_report(&snapshot, &report));
+
+    // El reporte se escribe antes porque el horario se mueve a `data`
+    if let Some(path) = &args.report {
+        let json = serde_json::to_string_pretty(&report)
+            .map_err(|e| format!("Failed to serialize report: {}", e))?;
+        std::fs::write(path, json).map_err(|e| e.to_string())?;
+        println!("Report written to {}", path.display());
+    }
+
+    match extension(output).as_str() {
+        "csv" => {
+            std::fs::write(output, format_csv(&snapshot, &report)).map_err(|e| e.to_string())?;
+        }
+        ext @ ("roster" | "" | "json" | "yaml" | "yml") => {
+            data.assignments = report.assignments;
+            // Los archivos de texto no se cifran
+            let options = RosterOptions {
+                password: args
+                    .password
+                    .clone()
+                    .filter(|_| matches!(ext, "roster" | "")),
+                ..RosterOptions::default()
+            };
+            write_schedule_file(output, &data, &options).map_err(|e| e.to_string())?;
+        }
+        other => return Err(format!("Unsupported output format: .{}", other)),
+    }
+
+    println!("Written to {}", output.display());
+    Ok(())
+}
+
+// Compara dos archivos; los nombres se toman del archivo de despues
+fn run_compare(
+    before: &Path,
+    after: &Path,
+    output: Option<&Path>,
+    password: Option<&str>,
+) -> Result<(), String> {
+    let before_data = read_roster(before, password)?;
+    let after_data = read_roster(after, password)?;
+
+    let snapshot = Snapshot::from_schedule_data(&after_data);
+    let comparison =
+        compare_schedules(&snapshot, &before_data.assignments, &after_data.assignments);
+    let title = format!(
+        "Cambios de horario: {} -> {}",
+        file_name(before),
+        file_name(after)
+    );
+    let sheet = format_change_sheet(&comparison, &title);
+    print!("{}", sheet);
+
+    let Some(output) = output else {
+        return Ok(());
+    };
+
+    match extension(output).as_str() {
+        "json" => {
+            let json = serde_json::to_string_pretty(&comparison)
+                .map_err(|e| format!("Failed to serialize comparison: {}", e))?;
+            std::fs::write(output, json).map_err(|e| e.to_string())?;
+        }
+        "txt" | "" => std::fs::write(output, sheet).map_err(|e| e.to_string())?,
+        other => return Err(format!("Unsupported output format: .{}", other)),
+    }
+
+    println!("Written to {}", output.display());
+    Ok(())
+}
+
+// Primera linea de la entrada estandar, sin el salto de linea
+fn read_password_line() -> Result<String, String> {
+    let mut line = String::new();
+    std::io::stdin()
+        .read_line(&mut line)
+        .map_err(|e| format!("Failed to read the password from stdin: {}", e))?;
+    let password = line.trim_end_matches(['\r', '\n']).to_string();
+    if password.is_empty() {
+        return Err("--password-stdin got an empty password".to_string());
+    }
+    Ok(password)
+}
+
+fn read_roster(path: &Path, password: Option<&str>) -> Result<ScheduleData, String> {
+    read_schedule_file(path, password)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn format_report(snapshot: &Snapshot, report: &GenerationReport) -> String {
+    let mut out = String::new();
+    let score = &report.score;
+
+    let _ = writeln!(out, "Generation report");
+    let _ = writeln!(
+        out,
+        "  Modules placed: {} / {}",
+        score.placed_modules, score.required_modules
+    );
+    let _ = writeln!(out, "  Missing modules: {}", score.missing_modules);
+    let _ = writeln!(out, "  Group gaps: {}", score.group_gaps);
+    let _ = writeln!(out, "  Teacher gaps: {}", score.teacher_gaps);
+    let _ = writeln!(out, "  Score: {}", score.total);
+    if report.attempts > 1 {
+        let _ = writeln!(out, "  Best of {} attempts", report.attempts);
+    }
+    if let Some(seed) = report.seed {
+        let _ = writeln!(out, "  Seed: {}", seed);
+    }
+    if report.timed_out {
+        let _ = writeln!(out, "  Stopped early: time limit reached");
+    }
+
+    if !report.missing.is_empty() {
+        let _ = writeln!(out, "Incomplete subjects:");
+        for m in &report.missing {
+            let _ = writeln!(
+                out,
+                "  {} - {}: {} of {} modules",
+                snapshot.group_label(m.group_id),
+                snapshot.subject_label(m.subject_id),
+                m.assigned,
+                m.required
+            );
+        }
+    }
+
+    if !report.warnings.is_empty() {
+        let _ = writeln!(out, "Warnings:");
+        for w in &report.warnings {
+            let _ = writeln!(out, "  {}", w);
+        }
+    }
+
+    if !report.violations.is_empty() {
+        let _ = writeln!(out, "Violations:");
+        for v in &report.violations {
+            let _ = writeln!(out, "  [{:?}] {}", v.severity, v.message);
+        }
+    }
+
+    out
+}
+
+fn format_csv(snapshot: &Snapshot, report: &GenerationReport) -> String {
+    let mut out = String::from("group,day,module,subject,teacher,classroom\n");
+
+    for a in &report.assignments {
+        let classroom = snapshot.classroom_label(a.classroom_id);
+
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{}",
+            csv_field(&snapshot.group_label(a.group_id)),
+            csv_field(&a.day),
+            a.module_index + 1,
+            csv_field(&a.subject_name),
+            csv_field(&snapshot.teacher_label(a.teacher_id)),
+            csv_field(&classroom)
+        );
+    }
+
+    out
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains(',') || value.contains('"') || value.contains('\n') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
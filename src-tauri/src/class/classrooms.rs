@@ -1,37 +1,8 @@
 use crate::db::AppState;
+pub use crate::model::Classroom;
 use crate::util::assignments::Assignment;
-use serde::{Deserialize, Serialize};
-use sqlx::error::Error as SqlxError;
-use sqlx::{sqlite::SqliteRow, FromRow, Row};
-
-/// Estructural salon
-/// Se utiliza para mapear los datos de la base de datos a un objeto en Rust
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Classroom {
-    pub id: Option<i16>,
-    pub building_id: Option<String>, // Puede ser una letra o numero entonces lo dejaremos como String
-    pub building_number: i16, // Numero de aula, lo que sigue despues del building_id (ejemplo: 303)
-    pub building_type: Option<String>,
-    pub capacity: Option<i16>,
-    pub availability: Option<Vec<(String, i16)>>, // Lista con (dias, modulos)
-}
-
-impl<'r> FromRow<'r, SqliteRow> for Classroom {
-    fn from_row(row: &'r SqliteRow) -> Result<Self, SqlxError> {
-        let availability_str: String = row.try_get("availability")?;
-        let availability: Vec<(String, i16)> =
-            serde_json::from_str(&availability_str).unwrap_or_default();
-
-        Ok(Classroom {
-            id: row.try_get("id")?,
-            building_id: row.try_get("building_id")?,
-            building_number: row.try_get("building_number")?,
-            building_type: row.try_get("building_type")?,
-            capacity: row.try_get("capacity")?,
-            availability: Some(availability),
-        })
-    }
-}
+use crate::util::audit;
+use crate::util::history;
 
 /// Funcion para crear un nuevo elemento
 /// # Argumentos
@@ -44,9 +15,15 @@ pub async fn create_classroom(
     pool: tauri::State<'_, AppState>,
     cr: Classroom,
 ) -> Result<(), String> {
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
     let availability_json = serde_json::to_string(&cr.availability)
         .map_err(|e| format!("Failed to serialize availability: {}", e))?;
-    sqlx::query(
+    let classroom_id = sqlx::query(
         "
         INSERT INTO classroom (
             building_number,
@@ -61,9 +38,25 @@ pub async fn create_classroom(
     .bind(cr.building_type)
     .bind(cr.capacity)
     .bind(availability_json)
-    .execute(&pool.db)
+    .execute(&mut tx)
     .await
-    .map_err(|e| format!("Failed to create building_id, error: {}", e))?;
+    .map_err(|e| format!("Failed to create building_id, error: {}", e))?
+    .last_insert_rowid();
+
+    let new_value = audit::row_json(&mut tx, "classroom", classroom_id).await?;
+    audit::record(
+        &mut tx,
+        audit::CLASSROOM,
+        Some(classroom_id),
+        "create",
+        None,
+        new_value.as_ref(),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
 
     Ok(())
 }
@@ -88,7 +81,7 @@ pub async fn create_classrooms(
         println!("Aula: {:?}", c);
         let availability_json = serde_json::to_string(&c.availability)
             .map_err(|e| format!("Failed to serialize availability: {}", e))?;
-        sqlx::query(
+        let classroom_id = sqlx::query(
             r#"INSERT INTO classroom (building_id, building_number, building_type, capacity, availability) VALUES (?1, ?2, ?3, ?4, ?5)"#,
         )
         .bind(c.building_id)
@@ -98,7 +91,19 @@ pub async fn create_classrooms(
         .bind(availability_json)
         .execute(&mut tx)
         .await
-        .map_err(|e| format!("Error creating the classroom, error: {}", e))?;
+        .map_err(|e| format!("Error creating the classroom, error: {}", e))?
+        .last_insert_rowid();
+
+        let new_value = audit::row_json(&mut tx, "classroom", classroom_id).await?;
+        audit::record(
+            &mut tx,
+            audit::CLASSROOM,
+            Some(classroom_id),
+            "create",
+            None,
+            new_value.as_ref(),
+        )
+        .await?;
     }
 
     tx.commit()
@@ -133,12 +138,36 @@ pub async fn get_classrooms(pool: tauri::State<'_, AppState>) -> Result<Vec<Clas
 #[allow(dead_code, unused)]
 #[tauri::command]
 pub async fn delete_classroom(pool: tauri::State<'_, AppState>, id: i16) -> Result<(), String> {
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let old_value = audit::row_json(&mut tx, "classroom", id.into()).await?;
+
     sqlx::query("DELETE FROM classroom WHERE id = ?1")
         .bind(id)
-        .execute(&pool.db)
+        .execute(&mut tx)
         .await
         .map_err(|e| format!("Failed to delete classroom: {}", e))?;
 
+    if old_value.is_some() {
+        audit::record(
+            &mut tx,
+            audit::CLASSROOM,
+            Some(id.into()),
+            "delete",
+            old_value.as_ref(),
+            None,
+        )
+        .await?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
     Ok(())
 }
 
@@ -172,9 +201,19 @@ pub async fn update_classroom(
     pool: tauri::State<'_, AppState>,
     classroom: Classroom,
 ) -> Result<(), String> {
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
     let availability_json = serde_json::to_string(&classroom.availability)
         .map_err(|e| format!("Failed to serialize availability: {}", e))?;
     println!("{:?}", classroom);
+
+    let classroom_id = classroom.id.unwrap_or_default() as i64;
+    let old_value = audit::row_json(&mut tx, "classroom", classroom_id).await?;
+
     sqlx::query(
         "UPDATE classroom SET
             building_number = ?1,
@@ -190,10 +229,25 @@ pub async fn update_classroom(
     .bind(classroom.capacity)
     .bind(availability_json)
     .bind(classroom.id)
-    .execute(&pool.db)
+    .execute(&mut tx)
     .await
     .map_err(|e| format!("Failed to update classroom: {}", e))?;
 
+    let new_value = audit::row_json(&mut tx, "classroom", classroom_id).await?;
+    audit::record(
+        &mut tx,
+        audit::CLASSROOM,
+        Some(classroom_id),
+        "update",
+        old_value.as_ref(),
+        new_value.as_ref(),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
     Ok(())
 }
 
@@ -204,13 +258,20 @@ pub async fn assign_classroom_to_assignment(
     classroom_id: i32,
     pool: tauri::State<'_, AppState>,
 ) -> Result<(), String> {
-    sqlx::query("UPDATE assignments SET classroom_id = ?1 WHERE id = ?2")
-        .bind(classroom_id)
-        .bind(assignment_id)
-        .execute(&pool.db)
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    history::set_classroom(&mut tx, assignment_id, classroom_id as i16, "Asignar aula")
         .await
         .map_err(|e| format!("Error asignando aula: {}", e))?;
 
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
     Ok(())
 }
 
@@ -262,11 +323,19 @@ pub async fn remove_classroom_assignment(
     assignment_id: i32,
     pool: tauri::State<'_, AppState>,
 ) -> Result<(), String> {
-    sqlx::query("UPDATE assignments SET classroom_id = NULL WHERE id = ?1")
-        .bind(assignment_id)
-        .execute(&pool.db)
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    history::set_classroom(&mut tx, assignment_id, 0, "Quitar aula")
         .await
         .map_err(|e| format!("Error removiendo aula: {}", e))?;
 
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
     Ok(())
 }
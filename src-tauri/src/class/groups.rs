@@ -1,74 +1,13 @@
 use crate::db::AppState;
+pub use crate::model::{Group, GroupSubjects, Student};
+use crate::util::audit;
+use crate::util::history::Tx;
 use futures::TryStreamExt; // Para poder usar try_next() en los streams
-use serde::{Deserialize, Serialize};
-use sqlx::error::Error as SqlxError;
-use sqlx::{sqlite::SqliteRow, FromRow, Row};
+use sqlx::Row;
 use tauri::api::dialog::blocking::FileDialogBuilder;
 
 use crate::class::subjects::SubjectWithTeacher;
 
-/// Estructura de un grupo
-/// Se utiliza para mapear los datos del grupo de la base de datos a un objeto en Rust
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct Group {
-    pub id: Option<i16>,
-    pub grade: i16,
-    pub group: String,
-    pub career: Option<String>,
-    pub students: Option<i16>,
-    pub max_modules_per_day: Option<i16>,
-}
-
-impl<'r> FromRow<'r, SqliteRow> for Group {
-    fn from_row(row: &'r SqliteRow) -> Result<Self, SqlxError> {
-        Ok(Group {
-            id: row.try_get("id")?,
-            grade: row.try_get("grade")?,
-            group: row.try_get("group")?,
-            career: row.try_get("career")?,
-            students: row.try_get("students")?,
-            max_modules_per_day: row.try_get("max_modules_per_day")?,
-        })
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct GroupSubjects {
-    pub group_id: i16,
-    pub subject_id: i16,
-}
-
-impl<'r> FromRow<'r, SqliteRow> for GroupSubjects {
-    fn from_row(row: &'r SqliteRow) -> Result<Self, SqlxError> {
-        Ok(GroupSubjects {
-            group_id: row.try_get("group_id")?,
-            subject_id: row.try_get("subject_id")?,
-        })
-    }
-}
-
-/// Estructura de un estudiante
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct Student {
-    pub id: Option<i16>,
-    pub name: String,
-    pub father_lastname: String,
-    pub mother_lastname: Option<String>,
-    pub group_id: Option<i16>,
-}
-
-impl<'r> FromRow<'r, SqliteRow> for Student {
-    fn from_row(row: &'r SqliteRow) -> Result<Self, SqlxError> {
-        Ok(Student {
-            id: row.try_get("id")?,
-            name: row.try_get("name")?,
-            father_lastname: row.try_get("father_lastname")?,
-            mother_lastname: row.try_get("mother_lastname")?,
-            group_id: row.try_get("group_id")?,
-        })
-    }
-}
-
 /// Funcion para crear un grupo
 /// # Argumentos
 /// * `pool` - Conexion a la base de datos
@@ -81,6 +20,12 @@ pub async fn create_group(
     g: Group,
     subjects: Option<Vec<SubjectWithTeacher>>,
 ) -> Result<(), String> {
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
     let group_id: i16 = sqlx::query_scalar(
         r#"
         INSERT INTO groups (grade, "group", career, students, max_modules_per_day)
@@ -93,7 +38,7 @@ pub async fn create_group(
     .bind(g.career)
     .bind(g.students)
     .bind(g.max_modules_per_day)
-    .fetch_one(&pool.db)
+    .fetch_one(&mut tx)
     .await
     .map_err(|e| format!("Failed to create group, error: {}", e))?;
 
@@ -108,7 +53,7 @@ pub async fn create_group(
             )
             .bind(group_id)
             .bind(subject.id)
-            .fetch_optional(&pool.db)
+            .fetch_optional(&mut tx)
             .await
             .map_err(|e| format!("Error checking if subject exists on group table: {}", e))?;
 
@@ -117,13 +62,28 @@ pub async fn create_group(
                 sqlx::query("INSERT INTO groups_subjects (group_id, subject_id) VALUES (?1, ?2)")
                     .bind(group_id)
                     .bind(subject.id)
-                    .fetch_optional(&pool.db)
+                    .fetch_optional(&mut tx)
                     .await
                     .map_err(|e| format!("Error assigning subject to group: {}", e))?;
             }
         }
     }
 
+    let new_value = audit_value(&mut tx, group_id.into()).await?;
+    audit::record(
+        &mut tx,
+        audit::GROUP,
+        Some(group_id.into()),
+        "create",
+        None,
+        new_value.as_ref(),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
     Ok(())
 }
 
@@ -145,7 +105,7 @@ pub async fn create_groups(
         .map_err(|e| format!("Failed to start transaction! {}", e))?;
 
     for g in groups {
-        sqlx::query(
+        let group_id = sqlx::query(
             r#"INSERT INTO groups(grade, "group", career, students, max_modules_per_day) VALUES (?1, ?2, ?3, ?4, ?5)"#,
         )
         .bind(g.grade)
@@ -155,7 +115,19 @@ pub async fn create_groups(
         .bind(g.max_modules_per_day)
         .execute(&mut tx)
         .await
-        .map_err(|e| format!("Error creating the group, error: {}", e))?;
+        .map_err(|e| format!("Error creating the group, error: {}", e))?
+        .last_insert_rowid();
+
+        let new_value = audit::row_json(&mut tx, "groups", group_id).await?;
+        audit::record(
+            &mut tx,
+            audit::GROUP,
+            Some(group_id),
+            "create",
+            None,
+            new_value.as_ref(),
+        )
+        .await?;
     }
 
     tx.commit()
@@ -326,19 +298,43 @@ pub async fn get_group_by_id(
 #[allow(dead_code, unused)]
 #[tauri::command]
 pub async fn delete_group(pool: tauri::State<'_, AppState>, id: i16) -> Result<(), String> {
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let old_value = audit_value(&mut tx, id.into()).await?;
+
     sqlx::query("DELETE FROM groups WHERE id = ?1")
         .bind(id)
-        .execute(&pool.db)
+        .execute(&mut tx)
         .await
         .map_err(|e| format!("Failed to delete group: {}", e))?;
 
     // Borrar asignaciones de horario ligadas al grupo
     sqlx::query("DELETE FROM assignments WHERE group_id = ?1")
         .bind(id)
-        .execute(&pool.db)
+        .execute(&mut tx)
         .await
         .map_err(|e| format!("Failed to delete group assignment: {}", e))?;
 
+    if old_value.is_some() {
+        audit::record(
+            &mut tx,
+            audit::GROUP,
+            Some(id.into()),
+            "delete",
+            old_value.as_ref(),
+            None,
+        )
+        .await?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
     Ok(())
 }
 
@@ -371,6 +367,15 @@ pub async fn update_group(
     g: Group,
     subjects: Option<Vec<SubjectWithTeacher>>,
 ) -> Result<(), String> {
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let group_id = g.id.unwrap_or_default() as i64;
+    let old_value = audit_value(&mut tx, group_id).await?;
+
     sqlx::query(
         r#"UPDATE groups SET grade = ?1, "group" = ?2, career = ?3, students = ?4, max_modules_per_day = ?5 WHERE id = ?6"#,
     )
@@ -380,7 +385,7 @@ pub async fn update_group(
     .bind(g.students)
     .bind(g.max_modules_per_day)
     .bind(g.id)
-    .execute(&pool.db)
+    .execute(&mut tx)
     .await
     .map_err(|e| format!("Failed to update group: {}", e))?;
 
@@ -388,7 +393,7 @@ pub async fn update_group(
         // Eliminar las materias del grupo si existian
         sqlx::query("DELETE FROM groups_subjects WHERE group_id = ?1")
             .bind(g.id)
-            .execute(&pool.db)
+            .execute(&mut tx)
             .await
             .map_err(|e| format!("Failed to delete group subject: {}", e))?;
         for subject in subjects {
@@ -396,12 +401,27 @@ pub async fn update_group(
             sqlx::query("INSERT INTO groups_subjects (group_id, subject_id) VALUES (?1, ?2)")
                 .bind(g.id)
                 .bind(subject.id)
-                .fetch_optional(&pool.db)
+                .fetch_optional(&mut tx)
                 .await
                 .map_err(|e| format!("Failed to assign the subject to existed group: {}", e))?;
         }
     }
 
+    let new_value = audit_value(&mut tx, group_id).await?;
+    audit::record(
+        &mut tx,
+        audit::GROUP,
+        Some(group_id),
+        "update",
+        old_value.as_ref(),
+        new_value.as_ref(),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
     Ok(())
 }
 
@@ -447,13 +467,25 @@ pub async fn create_students(
         .execute(&mut tx)
         .await;
 
-        match result {
-            Ok(_) => count += 1,
+        let student_id = match result {
+            Ok(result) => result.last_insert_rowid(),
             Err(e) => {
                 tx.rollback().await.ok();
                 return Err(format!("Error creating student: {}", e));
             }
-        }
+        };
+        count += 1;
+
+        let new_value = audit::row_json(&mut tx, "students", student_id).await?;
+        audit::record(
+            &mut tx,
+            audit::STUDENT,
+            Some(student_id),
+            "create",
+            None,
+            new_value.as_ref(),
+        )
+        .await?;
     }
 
     tx.commit()
@@ -499,3 +531,16 @@ pub async fn save_excel_file(default_name: String) -> Result<Option<String>, Str
         None => Ok(None),
     }
 }
+
+// Datos del grupo para la bitacora, junto con sus materias
+async fn audit_value(tx: &mut Tx<'_>, group_id: i64) -> Result<Option<serde_json::Value>, String> {
+    let value = audit::row_json(&mut *tx, "groups", group_id).await?;
+    audit::with_links(
+        &mut *tx,
+        value,
+        "subjects",
+        "SELECT subject_id FROM groups_subjects WHERE group_id = ?1 ORDER BY subject_id",
+        group_id,
+    )
+    .await
+}
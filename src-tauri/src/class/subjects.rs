@@ -1,64 +1,8 @@
 use crate::class::teachers::SimpleTeacher;
 use crate::db::AppState;
-use serde::{Deserialize, Serialize};
-use sqlx::error::Error as SqlxError;
-use sqlx::{sqlite::SqliteRow, FromRow, Row};
-
-/// Estructura de una materia
-/// Se utiliza para mapear los datos de una materia de la base de datos a un objeto en Rust
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Subject {
-    pub id: Option<i16>, // ID opcional cuando se crea por medio de la clase
-    pub name: String,
-    pub shorten: String,
-    pub color: String,
-    pub spec: Option<String>,
-    pub required_modules: Option<i16>,
-    pub priority: Option<i16>,
-}
-
-impl<'r> FromRow<'r, SqliteRow> for Subject {
-    fn from_row(row: &'r SqliteRow) -> Result<Self, SqlxError> {
-        Ok(Subject {
-            id: row.try_get("id")?,
-            name: row.try_get("name")?,
-            shorten: row.try_get("shorten")?,
-            color: row.try_get("color")?,
-            spec: row.try_get("spec")?,
-            required_modules: row.try_get("required_modules")?,
-            priority: row.try_get("priority")?,
-        })
-    }
-}
-
-/// Estructura de una materia con profesor asignado
-/// Se utiliza para mapear los datos de una materia de la base de datos a un objeto en Rust
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct SubjectWithTeacher {
-    pub id: i16,
-    pub name: String,
-    pub shorten: String,
-    pub color: String,
-    pub spec: String,
-    pub required_modules: Option<i16>,
-    pub priority: Option<i16>,
-    pub assigned_teacher: Option<SimpleTeacher>,
-}
-
-impl<'r> FromRow<'r, SqliteRow> for SubjectWithTeacher {
-    fn from_row(row: &'r SqliteRow) -> Result<Self, SqlxError> {
-        Ok(SubjectWithTeacher {
-            id: row.try_get("id")?,
-            name: row.try_get("name")?,
-            shorten: row.try_get("shorten")?,
-            color: row.try_get("color")?,
-            spec: row.try_get("spec")?,
-            required_modules: row.try_get("required_modules")?,
-            priority: row.try_get("priority")?,
-            assigned_teacher: None,
-        })
-    }
-}
+pub use crate::model::{Subject, SubjectWithTeacher};
+use crate::util::audit;
+use sqlx::Row;
 
 /// Funcion para crear una materia
 /// # Argumentos
@@ -71,7 +15,13 @@ pub async fn create_subject(
     pool: tauri::State<'_, AppState>,
     subject: Subject,
 ) -> Result<(), String> {
-    sqlx::query(
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let subject_id = sqlx::query(
         "
         INSERT INTO subjects (name, shorten, color, spec, required_modules, priority)
         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
@@ -83,12 +33,28 @@ pub async fn create_subject(
     .bind(subject.spec)
     .bind(subject.required_modules)
     .bind(subject.priority)
-    .execute(&pool.db)
+    .execute(&mut tx)
     .await
-    .map_err(|e| format!("Failed to create subject: {}", e))?;
+    .map_err(|e| format!("Failed to create subject: {}", e))?
+    .last_insert_rowid();
+
+    let new_value = audit::row_json(&mut tx, "subjects", subject_id).await?;
+    audit::record(
+        &mut tx,
+        audit::SUBJECT,
+        Some(subject_id),
+        "create",
+        None,
+        new_value.as_ref(),
+    )
+    .await?;
 
     println!("Subject created successfully");
 
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
     Ok(())
 }
 
@@ -102,8 +68,14 @@ pub async fn create_subjects(
     pool: tauri::State<'_, AppState>,
     subject: Vec<Subject>,
 ) -> Result<(), String> {
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
     for i in subject {
-        sqlx::query("INSERT INTO subjects (shorten, name, color, spec, required_modules, priority) VALUES (?1, ?2, ?3, ?4, ?5, ?6)")
+        let subject_id = sqlx::query("INSERT INTO subjects (shorten, name, color, spec, required_modules, priority) VALUES (?1, ?2, ?3, ?4, ?5, ?6)")
             .bind(if i.shorten.len() <= 0 {
                 i.name.to_uppercase().chars().take(3).collect()
             } else {
@@ -114,11 +86,27 @@ pub async fn create_subjects(
             .bind(i.spec)
             .bind(i.required_modules)
             .bind(i.priority)
-            .execute(&pool.db)
+            .execute(&mut tx)
             .await
-            .map_err(|e| format!("Error creating the classroom, error: {}", e))?;
+            .map_err(|e| format!("Error creating the classroom, error: {}", e))?
+            .last_insert_rowid();
+
+        let new_value = audit::row_json(&mut tx, "subjects", subject_id).await?;
+        audit::record(
+            &mut tx,
+            audit::SUBJECT,
+            Some(subject_id),
+            "create",
+            None,
+            new_value.as_ref(),
+        )
+        .await?;
     }
 
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
     Ok(())
 }
 
@@ -161,18 +149,42 @@ pub async fn get_subject_by_id(
 #[allow(dead_code, unused)]
 #[tauri::command]
 pub async fn delete_subject(pool: tauri::State<'_, AppState>, id: i16) -> Result<(), String> {
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let old_value = audit::row_json(&mut tx, "subjects", id.into()).await?;
+
     sqlx::query("DELETE FROM subjects WHERE id = ?1")
         .bind(id)
-        .execute(&pool.db)
+        .execute(&mut tx)
         .await
         .map_err(|e| format!("Failed to delete subject: {}", e))?;
 
     sqlx::query("DELETE FROM groups_subjects WHERE subject_id = ?1")
         .bind(id)
-        .execute(&pool.db)
+        .execute(&mut tx)
         .await
         .map_err(|e| format!("Failed to delete subject: {}", e))?;
 
+    if old_value.is_some() {
+        audit::record(
+            &mut tx,
+            audit::SUBJECT,
+            Some(id.into()),
+            "delete",
+            old_value.as_ref(),
+            None,
+        )
+        .await?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
     Ok(())
 }
 
@@ -206,6 +218,15 @@ pub async fn update_subject(
     pool: tauri::State<'_, AppState>,
     subject: Subject,
 ) -> Result<(), String> {
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let subject_id = subject.id.unwrap_or_default() as i64;
+    let old_value = audit::row_json(&mut tx, "subjects", subject_id).await?;
+
     sqlx::query(
         "
         UPDATE subjects SET
@@ -225,10 +246,25 @@ pub async fn update_subject(
     .bind(subject.required_modules)
     .bind(subject.priority)
     .bind(Some(subject.id))
-    .execute(&pool.db)
+    .execute(&mut tx)
     .await
     .map_err(|e| format!("Failed to update subject: {}", e))?;
 
+    let new_value = audit::row_json(&mut tx, "subjects", subject_id).await?;
+    audit::record(
+        &mut tx,
+        audit::SUBJECT,
+        Some(subject_id),
+        "update",
+        old_value.as_ref(),
+        new_value.as_ref(),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
     Ok(())
 }
 
@@ -294,24 +330,3 @@ pub async fn get_subjects_with_teachers(
 
     Ok(subjects_with_teachers)
 }
-
-/// Funcion para vincular materias con profesores
-pub async fn link_subject_to_teacher(
-    pool: tauri::State<'_, AppState>,
-    data: Vec<SubjectWithTeacher>,
-) -> Result<(), String> {
-    for subject in data {
-        let teacher: SimpleTeacher = match &subject.assigned_teacher {
-            Some(teacher) => teacher.clone(),
-            None => return Err(String::from("Cannot find teacher module")),
-        };
-
-        sqlx::query("INSERT into teacher_subjects (teacher_id, subject_id) VALUES (?1, ?2)")
-            .bind(teacher.id)
-            .bind(subject.id)
-            .execute(&pool.db)
-            .await
-            .map_err(|e| format!("Error linking to table teacer_subjects: {}", e));
-    }
-    Ok(())
-}
@@ -1,63 +1,9 @@
 use crate::db::AppState;
+pub use crate::model::{SimpleTeacher, Teacher, TeacherSubjects};
+use crate::util::audit;
+use crate::util::history::Tx;
 use futures::TryStreamExt;
-use serde::{Deserialize, Serialize};
-use sqlx::error::Error as SqlxError;
-use sqlx::{sqlite::SqliteRow, FromRow, Row};
-
-/// Estructura simple de un profesor, solo contiene el ID, el nombre y el primer apellido
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct SimpleTeacher {
-    pub id: Option<i16>,
-    pub name: String,
-    pub father_lastname: String,
-}
-
-/// Estructura de un profesor
-/// Se utiliza para mapear los datos de un profesor de la base de datos a un objeto en Rust
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct Teacher {
-    pub id: Option<i16>,
-    pub name: String,
-    pub father_lastname: String,
-    pub mother_lastname: Option<String>,
-    pub email: Option<String>,
-    pub phone: Option<String>,
-    pub degree: Option<String>,
-    pub commisioned_hours: Option<i16>,      // Total de horas
-    pub active_hours: Option<i16>,           // Horas activas en el programa
-    pub performance: Option<i16>,            // Desempeño
-    pub preferred_days: Option<Vec<String>>, // Dias preferidos del profesor
-    pub preferred_modules: Option<Vec<i16>>, // Modulos preferidos del profesor (eg: |1, 2, 3])
-}
-
-// Implement FromRow for Teacher
-impl<'r> FromRow<'r, SqliteRow> for Teacher {
-    fn from_row(row: &'r SqliteRow) -> Result<Self, SqlxError> {
-        // For the Vec fields, get them as strings and deserialize
-        let preferred_days_str: String = row.try_get("preferred_days")?;
-        let preferred_days: Vec<String> =
-            serde_json::from_str(&preferred_days_str).unwrap_or_default();
-
-        let preferred_modules_str: String = row.try_get("preferred_modules")?;
-        let preferred_modules: Vec<i16> =
-            serde_json::from_str(&preferred_modules_str).unwrap_or_default();
-
-        Ok(Teacher {
-            id: row.try_get("id")?,
-            name: row.try_get("name")?,
-            father_lastname: row.try_get("father_lastname")?,
-            mother_lastname: row.try_get("mother_lastname")?,
-            email: row.try_get("email")?,
-            phone: row.try_get("phone")?,
-            degree: row.try_get("degree")?,
-            commisioned_hours: row.try_get("commisioned_hours")?,
-            active_hours: row.try_get("active_hours")?,
-            performance: row.try_get("performance")?,
-            preferred_days: Some(preferred_days),
-            preferred_modules: Some(preferred_modules),
-        })
-    }
-}
+use sqlx::Row;
 
 /// Funcion para agregar un profesor
 /// # Argumentos
@@ -71,6 +17,12 @@ pub async fn add_teacher(
     teacher: Teacher,
     subjects: Option<Vec<i16>>,
 ) -> Result<(), String> {
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
     let preferred_days = serde_json::to_string(&teacher.preferred_days)
         .map_err(|e| format!("Failed to serialize teacher preferred days: {:?}", e))?;
 
@@ -107,7 +59,7 @@ pub async fn add_teacher(
     .bind(teacher.performance)
     .bind(preferred_days)
     .bind(preferred_modules)
-    .fetch_one(&pool.db)
+    .fetch_one(&mut tx)
     .await
     .map_err(|e| format!("Failed to create teacher: {}", e))?;
 
@@ -123,7 +75,7 @@ pub async fn add_teacher(
             )
             .bind(teacher_id)
             .bind(subject_id)
-            .fetch_optional(&pool.db)
+            .fetch_optional(&mut tx)
             .await
             .map_err(|e| {
                 format!(
@@ -140,7 +92,7 @@ pub async fn add_teacher(
                 )
                 .bind(teacher_id)
                 .bind(subject_id)
-                .execute(&pool.db)
+                .execute(&mut tx)
                 .await
                 .map_err(|e| format!("Failed to attach subject to teacher: {}", e))?;
             } else {
@@ -149,6 +101,21 @@ pub async fn add_teacher(
         }
     }
 
+    let new_value = audit_value(&mut tx, teacher_id).await?;
+    audit::record(
+        &mut tx,
+        audit::TEACHER,
+        Some(teacher_id),
+        "create",
+        None,
+        new_value.as_ref(),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
     Ok(())
 }
 
@@ -164,13 +131,19 @@ pub async fn create_teachers(
     pool: tauri::State<'_, AppState>,
     teacher: Vec<Teacher>,
 ) -> Result<(), String> {
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
     for i in teacher {
         let preferred_days = serde_json::to_string(&i.preferred_days)
             .map_err(|e| format!("Failed to serialize preferred_days: {}", e))?;
         let preferred_modules = serde_json::to_string(&i.preferred_modules)
             .map_err(|e| format!("Failed to serialize teacher preferred modules: {}", e))?;
 
-        sqlx::query(
+        let teacher_id = sqlx::query(
             "
         INSERT INTO teachers (
             name, father_lastname, mother_lastname,
@@ -189,11 +162,27 @@ pub async fn create_teachers(
         .bind(i.performance)
         .bind(preferred_days)
         .bind(preferred_modules)
-        .execute(&pool.db)
+        .execute(&mut tx)
         .await
-        .map_err(|e| format!("Error creating the teacher, error: {}", e))?;
+        .map_err(|e| format!("Error creating the teacher, error: {}", e))?
+        .last_insert_rowid();
+
+        let new_value = audit_value(&mut tx, teacher_id).await?;
+        audit::record(
+            &mut tx,
+            audit::TEACHER,
+            Some(teacher_id),
+            "create",
+            None,
+            new_value.as_ref(),
+        )
+        .await?;
     }
 
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
     Ok(())
 }
 
@@ -210,6 +199,12 @@ pub async fn edit_teacher(
     teacher: Teacher,
     subjects: Option<Vec<i16>>,
 ) -> Result<(), String> {
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
     let preferred_days = serde_json::to_string(&teacher.preferred_days)
         .map_err(|e| format!("Failed to serialize teacher preferred days: {:?}", e))?;
     let preferred_modules = serde_json::to_string(&teacher.preferred_modules)
@@ -217,6 +212,9 @@ pub async fn edit_teacher(
 
     println!("{:?}", teacher);
 
+    let teacher_id = teacher.id.unwrap_or_default() as i64;
+    let old_value = audit_value(&mut tx, teacher_id).await?;
+
     // Actualizar los datos del profesor
     sqlx::query(
         "
@@ -248,7 +246,7 @@ pub async fn edit_teacher(
     .bind(preferred_days)
     .bind(preferred_modules)
     .bind(teacher.id)
-    .execute(&pool.db)
+    .execute(&mut tx)
     .await
     .map_err(|e| format!("Failed to update teacher: {}", e))?;
 
@@ -256,7 +254,7 @@ pub async fn edit_teacher(
         // Eliminar las materias del profesor
         sqlx::query("DELETE FROM teacher_subjects WHERE teacher_id = ?1")
             .bind(teacher.id)
-            .execute(&pool.db)
+            .execute(&mut tx)
             .await
             .map_err(|e| format!("Failed to delete teacher subjects: {}", e))?;
 
@@ -270,12 +268,27 @@ pub async fn edit_teacher(
             )
             .bind(teacher.id)
             .bind(subject_id)
-            .execute(&pool.db)
+            .execute(&mut tx)
             .await
             .map_err(|e| format!("Failed to attach subject to teacher: {}", e))?;
         }
     }
 
+    let new_value = audit_value(&mut tx, teacher_id).await?;
+    audit::record(
+        &mut tx,
+        audit::TEACHER,
+        Some(teacher_id),
+        "update",
+        old_value.as_ref(),
+        new_value.as_ref(),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
     Ok(())
 }
 
@@ -352,18 +365,42 @@ pub async fn delete_teacher(
     pool: tauri::State<'_, AppState>,
     teacher_id: i16,
 ) -> Result<(), String> {
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let old_value = audit_value(&mut tx, teacher_id.into()).await?;
+
     sqlx::query("DELETE FROM teachers WHERE id = ?1")
         .bind(teacher_id)
-        .execute(&pool.db)
+        .execute(&mut tx)
         .await
         .map_err(|e| format!("Failed to delete teacher: {}", e))?;
 
     sqlx::query("DELETE FROM assignments WHERE teacher_id = ?1")
         .bind(teacher_id)
-        .execute(&pool.db)
+        .execute(&mut tx)
         .await
         .map_err(|e| format!("Failed to delete teacher: {}", e))?;
 
+    if old_value.is_some() {
+        audit::record(
+            &mut tx,
+            audit::TEACHER,
+            Some(teacher_id.into()),
+            "delete",
+            old_value.as_ref(),
+            None,
+        )
+        .await?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
     Ok(())
 }
 
@@ -423,3 +460,19 @@ pub async fn check_teacher_availability(
 
     Ok(row.is_none())
 }
+
+// Datos del profesor para la bitacora, junto con las materias que imparte
+async fn audit_value(
+    tx: &mut Tx<'_>,
+    teacher_id: i64,
+) -> Result<Option<serde_json::Value>, String> {
+    let value = audit::row_json(&mut *tx, "teachers", teacher_id).await?;
+    audit::with_links(
+        &mut *tx,
+        value,
+        "subjects",
+        "SELECT subject_id FROM teacher_subjects WHERE teacher_id = ?1 ORDER BY subject_id",
+        teacher_id,
+    )
+    .await
+}
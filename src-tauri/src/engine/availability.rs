@@ -0,0 +1,229 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::Assignment;
+
+use super::snapshot::Snapshot;
+
+/// Profesor libre en un rango de modulos
+/// `new_gaps` son los modulos libres que se le agregan (o quitan, si es negativo) entre
+/// su primera y ultima clase del dia al darle ese rango
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FreeTeacher {
+    pub teacher_id: i16,
+    pub name: String,
+    pub new_gaps: i64,
+    pub teaches_that_day: bool,
+    pub load: i64,
+}
+
+/// Aula libre en un rango de modulos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FreeClassroom {
+    pub classroom_id: i16,
+    pub name: String,
+    pub building_type: Option<String>,
+    pub capacity: Option<i16>,
+}
+
+/// Ventana en la que todos los profesores (y el aula, si se pidio) estan libres
+/// `adjacent_teachers` son los que tienen clase justo antes o justo despues de la ventana
+/// y `teachers_on_campus` los que tienen alguna clase ese dia
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeetingSlot {
+    pub day: String,
+    pub module_index: i16,
+    pub modules: i16,
+    pub adjacent_teachers: usize,
+    pub teachers_on_campus: usize,
+}
+
+/// Filtro para buscar aulas libres
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClassroomFilter {
+    pub building_type: Option<String>,
+    pub min_capacity: Option<i16>,
+}
+
+/// Funcion para buscar los profesores sin clase en `day` desde `start` durante `len` modulos
+/// Si se da `subject_id` solo se regresan los profesores registrados para esa materia
+/// Se ordenan por lo poco que se altera su dia: primero los que no ganan huecos,
+/// despues los que ya van ese dia y al final por carga total
+pub fn free_teachers(
+    snapshot: &Snapshot,
+    schedule: &[Assignment],
+    day: &str,
+    start: i16,
+    len: i16,
+    subject_id: Option<i16>,
+) -> Vec<FreeTeacher> {
+    let mut free: Vec<FreeTeacher> = snapshot
+        .teachers
+        .iter()
+        .filter_map(|teacher| {
+            let teacher_id = teacher.id?;
+            if subject_id.is_some_and(|s| !snapshot.is_qualified(teacher_id, s)) {
+                return None;
+            }
+
+            let today: Vec<i16> = schedule
+                .iter()
+                .filter(|a| a.teacher_id == teacher_id && a.day == day)
+                .map(|a| a.module_index)
+                .collect();
+            if today.iter().any(|m| (start..start + len).contains(m)) {
+                return None;
+            }
+
+            let mut with_range = today.clone();
+            with_range.extend(start..start + len);
+
+            Some(FreeTeacher {
+                teacher_id,
+                name: snapshot.teacher_label(teacher_id),
+                new_gaps: gaps(&with_range) - gaps(&today),
+                teaches_that_day: !today.is_empty(),
+                load: schedule
+                    .iter()
+                    .filter(|a| a.teacher_id == teacher_id)
+                    .count() as i64,
+            })
+        })
+        .collect();
+
+    free.sort_by_key(|t| (t.new_gaps, !t.teaches_that_day, t.load, t.teacher_id));
+    free
+}
+
+/// Funcion para buscar las aulas sin clase en `day` desde `start` durante `len` modulos
+/// Se ordenan de menor a mayor capacidad, asi la primera es la que mejor se ajusta
+pub fn free_classrooms(
+    snapshot: &Snapshot,
+    schedule: &[Assignment],
+    day: &str,
+    start: i16,
+    len: i16,
+    filter: &ClassroomFilter,
+) -> Vec<FreeClassroom> {
+    let mut free: Vec<FreeClassroom> = snapshot
+        .classrooms
+        .iter()
+        .filter_map(|classroom| {
+            let classroom_id = classroom.id?;
+
+            if let Some(wanted) = &filter.building_type {
+                let matches = classroom
+                    .building_type
+                    .as_ref()
+                    .is_some_and(|t| t.trim().eq_ignore_ascii_case(wanted.trim()));
+                if !matches {
+                    return None;
+                }
+            }
+            if let Some(min) = filter.min_capacity {
+                if classroom.capacity.map_or(true, |c| c < min) {
+                    return None;
+                }
+            }
+
+            let busy = schedule.iter().any(|a| {
+                a.classroom_id == classroom_id
+                    && a.day == day
+                    && (start..start + len).contains(&a.module_index)
+            });
+            if busy {
+                return None;
+            }
+
+            Some(FreeClassroom {
+                classroom_id,
+                name: snapshot.classroom_label(classroom_id),
+                building_type: classroom.building_type.clone(),
+                capacity: classroom.capacity,
+            })
+        })
+        .collect();
+
+    free.sort_by_key(|c| (c.capacity.unwrap_or(i16::MAX), c.classroom_id));
+    free
+}
+
+/// Funcion para buscar ventanas de `len` modulos en las que todos los profesores estan libres
+/// Si se da `classroom_id` el aula tambien tiene que estar libre
+/// Se ordenan por cuantos profesores tienen clase pegada a la ventana (mas es mejor),
+/// despues por cuantos ya van ese dia y al final por dia y modulo
+pub fn common_free_slots(
+    snapshot: &Snapshot,
+    schedule: &[Assignment],
+    teacher_ids: &[i16],
+    len: i16,
+    classroom_id: Option<i16>,
+) -> Vec<MeetingSlot> {
+    let mut slots = Vec::new();
+
+    for (day_index, day) in snapshot.days.iter().enumerate() {
+        let today: Vec<&Assignment> = schedule.iter().filter(|a| a.day == *day).collect();
+        let teaches = |teacher_id: i16, module: i16| {
+            today
+                .iter()
+                .any(|a| a.teacher_id == teacher_id && a.module_index == module)
+        };
+
+        for start in 0..=(snapshot.modules_per_day - len) {
+            let range = start..start + len;
+            let teachers_free = teacher_ids
+                .iter()
+                .all(|&t| !range.clone().any(|m| teaches(t, m)));
+            let room_free = classroom_id.map_or(true, |c| {
+                !today
+                    .iter()
+                    .any(|a| a.classroom_id == c && range.contains(&a.module_index))
+            });
+            if !teachers_free || !room_free {
+                continue;
+            }
+
+            let adjacent_teachers = teacher_ids
+                .iter()
+                .filter(|&&t| teaches(t, start - 1) || teaches(t, start + len))
+                .count();
+            let teachers_on_campus = teacher_ids
+                .iter()
+                .filter(|&&t| today.iter().any(|a| a.teacher_id == t))
+                .count();
+
+            slots.push((
+                day_index,
+                MeetingSlot {
+                    day: day.clone(),
+                    module_index: start,
+                    modules: len,
+                    adjacent_teachers,
+                    teachers_on_campus,
+                },
+            ));
+        }
+    }
+
+    slots.sort_by_key(|(day_index, s)| {
+        (
+            std::cmp::Reverse(s.adjacent_teachers),
+            std::cmp::Reverse(s.teachers_on_campus),
+            *day_index,
+            s.module_index,
+        )
+    });
+    slots.into_iter().map(|(_, s)| s).collect()
+}
+
+// Modulos libres entre la primera y la ultima clase
+fn gaps(modules: &[i16]) -> i64 {
+    let (Some(first), Some(last)) = (modules.iter().min(), modules.iter().max()) else {
+        return 0;
+    };
+    let mut distinct = modules.to_vec();
+    distinct.sort_unstable();
+    distinct.dedup();
+
+    (last - first + 1) as i64 - distinct.len() as i64
+}
@@ -0,0 +1,96 @@
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+// Nombres de los dias de la semana como vienen en la configuracion por defecto
+const WEEKDAYS: [&str; 7] = [
+    "Lunes",
+    "Martes",
+    "Miércoles",
+    "Jueves",
+    "Viernes",
+    "Sábado",
+    "Domingo",
+];
+
+/// Periodo escolar (semestre, trimestre...), fechas "AAAA-MM-DD" incluidas
+/// `active` es el periodo cuyos grupos y horario estan en uso; `archived_at` es cuando
+/// se guardaron sus datos por ultima vez (`None` si nunca se han guardado)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Term {
+    pub id: i64,
+    pub name: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub active: bool,
+    pub previous_term_id: Option<i64>,
+    pub archived_at: Option<i64>,
+}
+
+/// Dia festivo o cierre de la escuela, fechas "AAAA-MM-DD" incluidas
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Holiday {
+    pub id: i64,
+    pub name: String,
+    pub start_date: String,
+    pub end_date: String,
+}
+
+impl Holiday {
+    /// Funcion que dice si la escuela esta cerrada en una fecha
+    pub fn covers(&self, date: &str) -> bool {
+        self.start_date.as_str() <= date && date <= self.end_date.as_str()
+    }
+}
+
+/// Funcion para leer una fecha con formato "AAAA-MM-DD"
+pub fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD", value))
+}
+
+/// Funcion que da formato "AAAA-MM-DD" a una fecha
+pub fn format_date(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+/// Funcion que regresa el dia del horario que corresponde a una fecha
+/// Se busca por nombre ("Lunes", "Miercoles"...); si la configuracion usa otros nombres
+/// se toman en orden a partir del lunes
+/// Retorna `None` si ese dia de la semana no hay clases
+pub fn school_day(days: &[String], date: NaiveDate) -> Option<String> {
+    let index = date.weekday().num_days_from_monday() as usize;
+
+    let named = days
+        .iter()
+        .any(|d| WEEKDAYS.iter().any(|w| same_name(d, w)));
+    if named {
+        days.iter().find(|d| same_name(d, WEEKDAYS[index])).cloned()
+    } else {
+        days.get(index).cloned()
+    }
+}
+
+/// Funcion que regresa las fechas desde `start` hasta `end` (las dos incluidas)
+pub fn dates_between(start: NaiveDate, end: NaiveDate) -> Vec<NaiveDate> {
+    start.iter_days().take_while(|d| *d <= end).collect()
+}
+
+// Compara nombres de dias sin importar mayusculas ni acentos
+fn same_name(a: &str, b: &str) -> bool {
+    let normalize = |s: &str| -> String {
+        s.trim()
+            .to_lowercase()
+            .chars()
+            .map(|c| match c {
+                'á' => 'a',
+                'é' => 'e',
+                'í' => 'i',
+                'ó' => 'o',
+                'ú' => 'u',
+                other => other,
+            })
+            .collect()
+    };
+    normalize(a) == normalize(b)
+}
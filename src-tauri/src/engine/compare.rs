@@ -0,0 +1,220 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::Assignment;
+
+use super::diff::{diff_lessons, ChangeKind, Lesson, LessonChange, ScheduleDiff};
+use super::snapshot::Snapshot;
+
+/// Un cambio visto desde un profesor o un grupo
+/// `from` es `None` si la clase es nueva y `to` es `None` si se quito
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SheetEntry {
+    pub kinds: Vec<ChangeKind>,
+    pub from: Option<Lesson>,
+    pub to: Option<Lesson>,
+}
+
+/// Hoja de cambios de un profesor o de un grupo
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeSheet {
+    pub id: i16,
+    pub name: String,
+    pub entries: Vec<SheetEntry>,
+}
+
+/// Comparacion completa entre dos horarios
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleComparison {
+    pub diff: ScheduleDiff,
+    pub teachers: Vec<ChangeSheet>,
+    pub groups: Vec<ChangeSheet>,
+}
+
+/// Funcion para comparar dos horarios y agrupar los cambios por profesor y por grupo
+/// Un cambio de profesor aparece en la hoja de los dos profesores
+/// Los nombres se toman del snapshot
+pub fn compare_schedules(
+    snapshot: &Snapshot,
+    before: &[Assignment],
+    after: &[Assignment],
+) -> ScheduleComparison {
+    let diff = diff_lessons(snapshot, before, after);
+
+    let mut entries: Vec<SheetEntry> = Vec::new();
+    for lesson in &diff.removed {
+        entries.push(SheetEntry {
+            kinds: Vec::new(),
+            from: Some(lesson.clone()),
+            to: None,
+        });
+    }
+    for lesson in &diff.added {
+        entries.push(SheetEntry {
+            kinds: Vec::new(),
+            from: None,
+            to: Some(lesson.clone()),
+        });
+    }
+    for LessonChange { kinds, from, to } in &diff.changed {
+        entries.push(SheetEntry {
+            kinds: kinds.clone(),
+            from: Some(from.clone()),
+            to: Some(to.clone()),
+        });
+    }
+
+    let mut teachers: BTreeMap<i16, ChangeSheet> = BTreeMap::new();
+    let mut groups: BTreeMap<i16, ChangeSheet> = BTreeMap::new();
+
+    for entry in entries {
+        let lessons = [entry.from.as_ref(), entry.to.as_ref()];
+
+        let mut teacher_ids: Vec<(i16, &str)> = lessons
+            .iter()
+            .flatten()
+            .map(|l| (l.teacher_id, l.teacher.as_str()))
+            .collect();
+        teacher_ids.dedup_by_key(|(id, _)| *id);
+        for (id, name) in teacher_ids {
+            sheet(&mut teachers, id, name).entries.push(entry.clone());
+        }
+
+        if let Some(lesson) = lessons.iter().flatten().next() {
+            sheet(&mut groups, lesson.group_id, &lesson.group)
+                .entries
+                .push(entry.clone());
+        }
+    }
+
+    let sort = |sheets: BTreeMap<i16, ChangeSheet>| {
+        let mut sheets: Vec<ChangeSheet> = sheets.into_values().collect();
+        // Por dia de la semana (segun la configuracion), modulo y grupo
+        for s in &mut sheets {
+            s.entries.sort_by_key(|e| {
+                let l = e
+                    .from
+                    .as_ref()
+                    .or(e.to.as_ref())
+                    .expect("entry has a lesson");
+                let day = snapshot.days.iter().position(|d| *d == l.day);
+                (day, l.module_index, l.group_id)
+            });
+        }
+        sheets
+    };
+
+    ScheduleComparison {
+        diff,
+        teachers: sort(teachers),
+        groups: sort(groups),
+    }
+}
+
+fn sheet<'m>(
+    sheets: &'m mut BTreeMap<i16, ChangeSheet>,
+    id: i16,
+    name: &str,
+) -> &'m mut ChangeSheet {
+    sheets.entry(id).or_insert_with(|| ChangeSheet {
+        id,
+        name: name.to_string(),
+        entries: Vec::new(),
+    })
+}
+
+/// Funcion para armar la hoja de cambios imprimible (texto plano)
+/// Primero una seccion por profesor y despues una por grupo
+pub fn format_change_sheet(comparison: &ScheduleComparison, title: &str) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{}", title);
+    let _ = writeln!(out, "{}", "=".repeat(title.chars().count()));
+
+    if comparison.diff.is_empty() {
+        let _ = writeln!(out, "\nSin cambios.");
+        return out;
+    }
+
+    let _ = writeln!(
+        out,
+        "{} clases nuevas, {} quitadas, {} cambiadas",
+        comparison.diff.added.len(),
+        comparison.diff.removed.len(),
+        comparison.diff.changed.len()
+    );
+
+    for (heading, sheets) in [
+        ("Profesor", &comparison.teachers),
+        ("Grupo", &comparison.groups),
+    ] {
+        for sheet in sheets {
+            let _ = writeln!(out, "\n{}: {}", heading, sheet.name);
+            for entry in &sheet.entries {
+                let _ = writeln!(out, "  - {}", describe(entry));
+            }
+        }
+    }
+
+    out
+}
+
+fn describe(entry: &SheetEntry) -> String {
+    match (&entry.from, &entry.to) {
+        (None, Some(to)) => format!("Nueva: {}, {}{}", lesson_name(to), slot(to), room(to)),
+        (Some(from), None) => format!(
+            "Quitada: {}, {}{}",
+            lesson_name(from),
+            slot(from),
+            room(from)
+        ),
+        (Some(from), Some(to)) => {
+            let mut parts = Vec::new();
+            for kind in &entry.kinds {
+                parts.push(match kind {
+                    ChangeKind::Moved => format!("se mueve a {}", slot(to)),
+                    ChangeKind::TeacherChanged => {
+                        format!("profesor {} -> {}", from.teacher, to.teacher)
+                    }
+                    ChangeKind::RoomChanged => format!(
+                        "aula {} -> {}",
+                        or_none(&from.classroom),
+                        or_none(&to.classroom)
+                    ),
+                });
+            }
+            format!(
+                "{}, {}: {}",
+                lesson_name(from),
+                slot(from),
+                parts.join(", ")
+            )
+        }
+        (None, None) => String::new(),
+    }
+}
+
+fn lesson_name(lesson: &Lesson) -> String {
+    format!("{} {} ({})", lesson.group, lesson.subject, lesson.teacher)
+}
+
+fn slot(lesson: &Lesson) -> String {
+    format!("{} modulo {}", lesson.day, lesson.module_index + 1)
+}
+
+fn room(lesson: &Lesson) -> String {
+    if lesson.classroom.is_empty() {
+        String::new()
+    } else {
+        format!(", aula {}", lesson.classroom)
+    }
+}
+
+fn or_none(classroom: &str) -> &str {
+    if classroom.is_empty() {
+        "(ninguna)"
+    } else {
+        classroom
+    }
+}
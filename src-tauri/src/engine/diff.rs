@@ -0,0 +1,226 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::Assignment;
+
+use super::snapshot::Snapshot;
+
+/// Contenido de una celda del horario (grupo, dia, modulo)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CellValue {
+    pub subject_id: i16,
+    pub teacher_id: i16,
+    pub classroom_id: i16,
+}
+
+impl From<&Assignment> for CellValue {
+    fn from(a: &Assignment) -> Self {
+        CellValue {
+            subject_id: a.subject_id,
+            teacher_id: a.teacher_id,
+            classroom_id: a.classroom_id,
+        }
+    }
+}
+
+/// Celda que cambio entre dos horarios
+/// `before` o `after` en `None` significa que la celda estaba o quedo vacia
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CellChange {
+    pub group_id: i16,
+    pub day: String,
+    pub module_index: i16,
+    pub before: Option<CellValue>,
+    pub after: Option<CellValue>,
+}
+
+/// Funcion para comparar dos horarios celda por celda
+/// Retorna solo las celdas distintas, ordenadas por grupo, dia y modulo
+pub fn diff_schedules(before: &[Assignment], after: &[Assignment]) -> Vec<CellChange> {
+    let mut cells: BTreeMap<(i16, &str, i16), (Option<CellValue>, Option<CellValue>)> =
+        BTreeMap::new();
+
+    for a in before {
+        cells
+            .entry((a.group_id, a.day.as_str(), a.module_index))
+            .or_default()
+            .0 = Some(a.into());
+    }
+    for a in after {
+        cells
+            .entry((a.group_id, a.day.as_str(), a.module_index))
+            .or_default()
+            .1 = Some(a.into());
+    }
+
+    cells
+        .into_iter()
+        .filter(|(_, (before, after))| before != after)
+        .map(
+            |((group_id, day, module_index), (before, after))| CellChange {
+                group_id,
+                day: day.to_string(),
+                module_index,
+                before,
+                after,
+            },
+        )
+        .collect()
+}
+
+/// Clase (un modulo) con los nombres ya resueltos para mostrarla
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lesson {
+    pub group_id: i16,
+    pub group: String,
+    pub day: String,
+    pub module_index: i16,
+    pub subject_id: i16,
+    pub subject: String,
+    pub teacher_id: i16,
+    pub teacher: String,
+    pub classroom_id: i16,
+    pub classroom: String,
+}
+
+impl Lesson {
+    pub fn new(snapshot: &Snapshot, a: &Assignment) -> Self {
+        Lesson {
+            group_id: a.group_id,
+            group: snapshot.group_label(a.group_id),
+            day: a.day.clone(),
+            module_index: a.module_index,
+            subject_id: a.subject_id,
+            subject: snapshot.subject_label(a.subject_id),
+            teacher_id: a.teacher_id,
+            teacher: snapshot.teacher_label(a.teacher_id),
+            classroom_id: a.classroom_id,
+            classroom: snapshot.classroom_label(a.classroom_id),
+        }
+    }
+}
+
+/// Que cambio en una clase
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Moved,
+    TeacherChanged,
+    RoomChanged,
+}
+
+/// Clase que cambio de lugar, de profesor o de aula (o varias a la vez)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LessonChange {
+    pub kinds: Vec<ChangeKind>,
+    pub from: Lesson,
+    pub to: Lesson,
+}
+
+/// Diferencias entre dos horarios a nivel de clase
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScheduleDiff {
+    pub added: Vec<Lesson>,
+    pub removed: Vec<Lesson>,
+    pub changed: Vec<LessonChange>,
+}
+
+impl ScheduleDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Funcion para comparar dos horarios clase por clase
+/// Las clases identicas en ambos se ignoran. De lo que sobra se emparejan primero
+/// las clases del mismo grupo y materia en la misma celda (cambio de profesor o aula)
+/// y despues las del mismo grupo y materia en otra celda (movidas); el resto son
+/// clases agregadas o quitadas
+pub fn diff_lessons(
+    snapshot: &Snapshot,
+    before: &[Assignment],
+    after: &[Assignment],
+) -> ScheduleDiff {
+    let key = |a: &Assignment| {
+        (
+            a.group_id,
+            a.day.clone(),
+            a.module_index,
+            a.subject_id,
+            a.teacher_id,
+            a.classroom_id,
+        )
+    };
+    let before_keys: BTreeSet<_> = before.iter().map(key).collect();
+    let after_keys: BTreeSet<_> = after.iter().map(key).collect();
+
+    let mut removed: Vec<&Assignment> = before
+        .iter()
+        .filter(|a| !after_keys.contains(&key(a)))
+        .collect();
+    let mut added: Vec<&Assignment> = after
+        .iter()
+        .filter(|a| !before_keys.contains(&key(a)))
+        .collect();
+
+    // Se ordena para que el emparejamiento sea estable
+    removed.sort_by_key(|a| key(a));
+    added.sort_by_key(|a| key(a));
+
+    let mut diff = ScheduleDiff::default();
+    let same_slot = |from: &Assignment, to: &Assignment| {
+        from.group_id == to.group_id
+            && from.subject_id == to.subject_id
+            && from.day == to.day
+            && from.module_index == to.module_index
+    };
+    let same_lesson = |from: &Assignment, to: &Assignment| {
+        from.group_id == to.group_id && from.subject_id == to.subject_id
+    };
+
+    for matches in [
+        &same_slot as &dyn Fn(&Assignment, &Assignment) -> bool,
+        &same_lesson,
+    ] {
+        let mut unmatched: Vec<&Assignment> = Vec::new();
+        for from in removed {
+            match added.iter().position(|to| matches(from, to)) {
+                Some(i) => {
+                    let to = added.remove(i);
+                    diff.changed.push(LessonChange {
+                        kinds: change_kinds(from, to),
+                        from: Lesson::new(snapshot, from),
+                        to: Lesson::new(snapshot, to),
+                    });
+                }
+                None => unmatched.push(from),
+            }
+        }
+        removed = unmatched;
+    }
+
+    diff.removed = removed
+        .into_iter()
+        .map(|a| Lesson::new(snapshot, a))
+        .collect();
+    diff.added = added
+        .into_iter()
+        .map(|a| Lesson::new(snapshot, a))
+        .collect();
+    diff
+}
+
+fn change_kinds(from: &Assignment, to: &Assignment) -> Vec<ChangeKind> {
+    let mut kinds = Vec::new();
+    if from.day != to.day || from.module_index != to.module_index {
+        kinds.push(ChangeKind::Moved);
+    }
+    if from.teacher_id != to.teacher_id {
+        kinds.push(ChangeKind::TeacherChanged);
+    }
+    if from.classroom_id != to.classroom_id {
+        kinds.push(ChangeKind::RoomChanged);
+    }
+    kinds
+}
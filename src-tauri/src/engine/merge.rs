@@ -0,0 +1,719 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{
+    self, Assignment, Classroom, Group, GroupSubjects, Student, Subject, Teacher, TeacherSubjects,
+};
+use crate::schedule_file::ScheduleData;
+
+/// Que hacer cuando una fila del archivo ya existe en la base de datos
+/// `Skip` deja la fila actual, `Overwrite` la reemplaza con los datos del archivo
+/// (conservando su id, las materias de un profesor o grupo tambien se reemplazan por las
+/// del archivo) y `KeepBoth` agrega la del archivo como una fila nueva
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeAction {
+    Skip,
+    Overwrite,
+    KeepBoth,
+}
+
+/// Accion elegida para un conflicto en especifico
+/// `entity` es "subject", "teacher", "group" o "classroom" y `file_id` el id en el archivo
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeResolution {
+    pub entity: String,
+    pub file_id: i16,
+    pub action: MergeAction,
+}
+
+/// Opciones para combinar un archivo con los datos actuales
+/// `include` son las entidades que se traen del archivo ("subject", "teacher", "group",
+/// "student", "classroom", "assignment"); vacio trae todo
+/// `on_conflict` se usa para los conflictos que no tengan su propia resolucion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MergeOptions {
+    pub include: Vec<String>,
+    pub on_conflict: MergeAction,
+    pub resolutions: Vec<MergeResolution>,
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        MergeOptions {
+            include: Vec::new(),
+            on_conflict: MergeAction::Skip,
+            resolutions: Vec::new(),
+        }
+    }
+}
+
+impl MergeOptions {
+    fn includes(&self, entity: &str) -> bool {
+        self.include.is_empty() || self.include.iter().any(|e| e == entity)
+    }
+
+    fn action(&self, entity: &str, file_id: Option<i16>) -> MergeAction {
+        self.resolutions
+            .iter()
+            .find(|r| r.entity == entity && Some(r.file_id) == file_id)
+            .map_or(self.on_conflict, |r| r.action)
+    }
+}
+
+/// Fila del archivo que se parece a una que ya existe
+/// Materias con la misma abreviatura, profesores con el mismo nombre y correo, grupos con
+/// el mismo grado, letra y carrera y aulas con el mismo edificio y numero
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeConflict {
+    pub entity: String,
+    pub file_id: Option<i16>,
+    pub existing_id: i16,
+    pub label: String,
+    pub action: MergeAction,
+}
+
+/// Cuantas filas de una entidad se crearon, se sobrescribieron, se saltaron o se
+/// agregaron aunque ya existian
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MergeCount {
+    pub entity: String,
+    pub created: usize,
+    pub overwritten: usize,
+    pub skipped: usize,
+    pub kept_both: usize,
+}
+
+/// Resultado de combinar un archivo
+/// `skipped_lessons` dice por que no se trajo cada clase que se quedo fuera
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MergeReport {
+    pub counts: Vec<MergeCount>,
+    pub teacher_subjects: usize,
+    pub group_subjects: usize,
+    pub students: usize,
+    pub assignments: usize,
+    pub skipped_lessons: Vec<String>,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Cambios a guardar para combinar un archivo
+/// `insert` tiene las filas nuevas ya con sus ids en la base de datos y `overwrite` las
+/// filas existentes que se reemplazan (materias, profesores, grupos y aulas) junto con
+/// todas las materias de los profesores y grupos sobrescritos
+#[derive(Debug, Clone)]
+pub struct MergePlan {
+    pub insert: ScheduleData,
+    pub overwrite: ScheduleData,
+    pub report: MergeReport,
+}
+
+// Entidad que se puede encontrar repetida entre el archivo y la base de datos
+trait MergeRow: Clone {
+    const ENTITY: &'static str;
+
+    fn id(&self) -> Option<i16>;
+    fn with_id(&self, id: i16) -> Self;
+    // Llave para encontrar duplicados, ya normalizada
+    fn key(&self) -> String;
+    fn label(&self) -> String;
+}
+
+fn normalize(text: &str) -> String {
+    text.trim().to_lowercase()
+}
+
+impl MergeRow for Subject {
+    const ENTITY: &'static str = model::SUBJECT;
+
+    fn id(&self) -> Option<i16> {
+        self.id
+    }
+    fn with_id(&self, id: i16) -> Self {
+        Subject {
+            id: Some(id),
+            ..self.clone()
+        }
+    }
+    fn key(&self) -> String {
+        normalize(&self.shorten)
+    }
+    fn label(&self) -> String {
+        format!("{} ({})", self.name, self.shorten)
+    }
+}
+
+impl MergeRow for Teacher {
+    const ENTITY: &'static str = model::TEACHER;
+
+    fn id(&self) -> Option<i16> {
+        self.id
+    }
+    fn with_id(&self, id: i16) -> Self {
+        Teacher {
+            id: Some(id),
+            ..self.clone()
+        }
+    }
+    fn key(&self) -> String {
+        format!(
+            "{}|{}|{}|{}",
+            normalize(&self.name),
+            normalize(&self.father_lastname),
+            normalize(self.mother_lastname.as_deref().unwrap_or_default()),
+            normalize(self.email.as_deref().unwrap_or_default())
+        )
+    }
+    fn label(&self) -> String {
+        format!("{} {}", self.name, self.father_lastname)
+    }
+}
+
+impl MergeRow for Group {
+    const ENTITY: &'static str = model::GROUP;
+
+    fn id(&self) -> Option<i16> {
+        self.id
+    }
+    fn with_id(&self, id: i16) -> Self {
+        Group {
+            id: Some(id),
+            ..self.clone()
+        }
+    }
+    fn key(&self) -> String {
+        format!(
+            "{}|{}|{}",
+            self.grade,
+            normalize(&self.group),
+            normalize(self.career.as_deref().unwrap_or_default())
+        )
+    }
+    fn label(&self) -> String {
+        format!("{}{}", self.grade, self.group)
+    }
+}
+
+impl MergeRow for Classroom {
+    const ENTITY: &'static str = model::CLASSROOM;
+
+    fn id(&self) -> Option<i16> {
+        self.id
+    }
+    fn with_id(&self, id: i16) -> Self {
+        Classroom {
+            id: Some(id),
+            ..self.clone()
+        }
+    }
+    fn key(&self) -> String {
+        format!(
+            "{}|{}",
+            normalize(self.building_id.as_deref().unwrap_or_default()),
+            self.building_number
+        )
+    }
+    fn label(&self) -> String {
+        format!(
+            "{}{}",
+            self.building_id.as_deref().unwrap_or_default(),
+            self.building_number
+        )
+    }
+}
+
+// Resultado de combinar una entidad
+// `ids` va del id en el archivo al id en la base de datos (tambien para las filas que
+// se saltaron) y `written` son los ids del archivo cuyas filas se crearon o sobrescribieron
+struct Merged<T> {
+    ids: BTreeMap<i16, i16>,
+    written: BTreeSet<i16>,
+    created: BTreeSet<i16>,
+    insert: Vec<T>,
+    overwrite: Vec<T>,
+}
+
+fn merge_rows<T: MergeRow>(
+    current: &[T],
+    incoming: &[T],
+    options: &MergeOptions,
+    report: &mut MergeReport,
+) -> Merged<T> {
+    let included = options.includes(T::ENTITY);
+    let existing: BTreeMap<String, i16> = current
+        .iter()
+        .rev()
+        .filter_map(|row| Some((row.key(), row.id()?)))
+        .collect();
+    let mut next_id = current.iter().filter_map(|r| r.id()).max().unwrap_or(0) + 1;
+
+    let mut merged = Merged {
+        ids: BTreeMap::new(),
+        written: BTreeSet::new(),
+        created: BTreeSet::new(),
+        insert: Vec::new(),
+        overwrite: Vec::new(),
+    };
+    let mut count = MergeCount {
+        entity: T::ENTITY.to_string(),
+        ..MergeCount::default()
+    };
+
+    for row in incoming {
+        let duplicate = existing.get(&row.key()).copied();
+
+        // Las entidades que no se traen solo sirven para ligar las relaciones
+        if !included {
+            if let (Some(file_id), Some(existing_id)) = (row.id(), duplicate) {
+                merged.ids.insert(file_id, existing_id);
+            }
+            continue;
+        }
+
+        let action = match duplicate {
+            Some(existing_id) => {
+                let action = options.action(T::ENTITY, row.id());
+                report.conflicts.push(MergeConflict {
+                    entity: T::ENTITY.to_string(),
+                    file_id: row.id(),
+                    existing_id,
+                    label: row.label(),
+                    action,
+                });
+                Some((existing_id, action))
+            }
+            None => None,
+        };
+
+        let id = match action {
+            Some((existing_id, MergeAction::Skip)) => {
+                count.skipped += 1;
+                existing_id
+            }
+            Some((existing_id, MergeAction::Overwrite)) => {
+                count.overwritten += 1;
+                merged.overwrite.push(row.with_id(existing_id));
+                if let Some(file_id) = row.id() {
+                    merged.written.insert(file_id);
+                }
+                existing_id
+            }
+            Some((_, MergeAction::KeepBoth)) | None => {
+                if action.is_some() {
+                    count.kept_both += 1;
+                } else {
+                    count.created += 1;
+                }
+                let id = next_id;
+                next_id += 1;
+                merged.insert.push(row.with_id(id));
+                if let Some(file_id) = row.id() {
+                    merged.written.insert(file_id);
+                    merged.created.insert(file_id);
+                }
+                id
+            }
+        };
+
+        if let Some(file_id) = row.id() {
+            merged.ids.insert(file_id, id);
+        }
+    }
+
+    if included {
+        report.counts.push(count);
+    }
+    merged
+}
+
+/// Funcion para armar los cambios que combinan un archivo con los datos actuales
+/// # Argumentos
+/// * `current` - Datos actuales de la base de datos (con su configuracion)
+/// * `incoming` - Datos del archivo, ya validados
+/// * `options` - Entidades a traer y que hacer con cada conflicto
+/// Los ids del archivo se cambian por ids nuevos; las relaciones con filas que se saltaron
+/// apuntan a la fila que ya existia
+/// Las materias de un profesor o grupo solo se traen si se creo o sobrescribio (al
+/// sobrescribir reemplazan a las que tenia), y los alumnos y clases solo se traen para los
+/// grupos nuevos
+/// Una clase se queda fuera si su profesor o aula ya esta ocupado en ese modulo o si el
+/// profesor no puede dar la materia
+pub fn plan_merge(
+    current: &ScheduleData,
+    incoming: &ScheduleData,
+    options: &MergeOptions,
+) -> MergePlan {
+    let mut report = MergeReport::default();
+
+    let subjects = merge_rows(&current.subjects, &incoming.subjects, options, &mut report);
+    let teachers = merge_rows(&current.teachers, &incoming.teachers, options, &mut report);
+    let groups = merge_rows(&current.groups, &incoming.groups, options, &mut report);
+    let classrooms = merge_rows(
+        &current.classrooms,
+        &incoming.classrooms,
+        options,
+        &mut report,
+    );
+
+    // Materias de profesores y grupos, las de los sobrescritos se reemplazan
+    let overwritten_teachers: BTreeSet<i16> =
+        teachers.overwrite.iter().filter_map(|t| t.id).collect();
+    let mut qualified: BTreeSet<(i16, i16)> = current
+        .teacher_subjects
+        .iter()
+        .filter(|ts| !overwritten_teachers.contains(&ts.teacher_id))
+        .map(|ts| (ts.teacher_id, ts.subject_id))
+        .collect();
+    let mut teacher_subjects = Vec::new();
+    let mut overwritten_teacher_subjects = Vec::new();
+    for ts in &incoming.teacher_subjects {
+        let (Some(&teacher_id), Some(&subject_id)) = (
+            teachers.ids.get(&ts.teacher_id),
+            subjects.ids.get(&ts.subject_id),
+        ) else {
+            continue;
+        };
+        if !teachers.written.contains(&ts.teacher_id) || !qualified.insert((teacher_id, subject_id))
+        {
+            continue;
+        }
+        let link = TeacherSubjects {
+            teacher_id,
+            subject_id,
+        };
+        if overwritten_teachers.contains(&teacher_id) {
+            overwritten_teacher_subjects.push(link);
+        } else {
+            teacher_subjects.push(link);
+        }
+    }
+
+    let overwritten_groups: BTreeSet<i16> = groups.overwrite.iter().filter_map(|g| g.id).collect();
+    let mut linked: BTreeSet<(i16, i16)> = current
+        .group_subjects
+        .iter()
+        .filter(|gs| !overwritten_groups.contains(&gs.group_id))
+        .map(|gs| (gs.group_id, gs.subject_id))
+        .collect();
+    let mut group_subjects = Vec::new();
+    let mut overwritten_group_subjects = Vec::new();
+    for gs in &incoming.group_subjects {
+        let (Some(&group_id), Some(&subject_id)) = (
+            groups.ids.get(&gs.group_id),
+            subjects.ids.get(&gs.subject_id),
+        ) else {
+            continue;
+        };
+        if !groups.written.contains(&gs.group_id) || !linked.insert((group_id, subject_id)) {
+            continue;
+        }
+        let link = GroupSubjects {
+            group_id,
+            subject_id,
+        };
+        if overwritten_groups.contains(&group_id) {
+            overwritten_group_subjects.push(link);
+        } else {
+            group_subjects.push(link);
+        }
+    }
+
+    // Alumnos de los grupos nuevos (y los que no tienen grupo)
+    let students: Vec<Student> = if options.includes(model::STUDENT) {
+        incoming
+            .students
+            .iter()
+            .filter_map(|st| {
+                let group_id = match st.group_id {
+                    Some(id) if groups.created.contains(&id) => Some(*groups.ids.get(&id)?),
+                    Some(_) => return None,
+                    None => None,
+                };
+                Some(Student {
+                    id: None,
+                    group_id,
+                    ..st.clone()
+                })
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    // Clases de los grupos nuevos
+    let config = current.config.clone().unwrap_or_default();
+    let mut teacher_busy: BTreeSet<(i16, &str, i16)> = current
+        .assignments
+        .iter()
+        .map(|a| (a.teacher_id, a.day.as_str(), a.module_index))
+        .collect();
+    let mut room_busy: BTreeSet<(i16, &str, i16)> = current
+        .assignments
+        .iter()
+        .filter(|a| a.classroom_id != 0)
+        .map(|a| (a.classroom_id, a.day.as_str(), a.module_index))
+        .collect();
+
+    let mut assignments = Vec::new();
+    if options.includes(model::ASSIGNMENT) {
+        for a in &incoming.assignments {
+            let Some(&group_id) = groups
+                .ids
+                .get(&a.group_id)
+                .filter(|_| groups.created.contains(&a.group_id))
+            else {
+                continue;
+            };
+            let cell = format!(
+                "{} on {} module {}",
+                label_of(incoming.groups.iter().find(|g| g.id == Some(a.group_id))),
+                a.day,
+                a.module_index + 1
+            );
+
+            let (Some(&subject_id), Some(&teacher_id)) = (
+                subjects.ids.get(&a.subject_id),
+                teachers.ids.get(&a.teacher_id),
+            ) else {
+                report
+                    .skipped_lessons
+                    .push(format!("{}: its subject or teacher was not imported", cell));
+                continue;
+            };
+            let classroom_id = classrooms.ids.get(&a.classroom_id).copied().unwrap_or(0);
+
+            let reason = if !config.days.contains(&a.day)
+                || a.module_index as u32 >= config.modules_per_day
+            {
+                Some("it is outside of the current schedule")
+            } else if !qualified.contains(&(teacher_id, subject_id)) {
+                Some("the teacher can't teach that subject")
+            } else if teacher_busy.contains(&(teacher_id, a.day.as_str(), a.module_index)) {
+                Some("the teacher is busy")
+            } else if classroom_id != 0
+                && room_busy.contains(&(classroom_id, a.day.as_str(), a.module_index))
+            {
+                Some("the classroom is busy")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                report.skipped_lessons.push(format!("{}: {}", cell, reason));
+                continue;
+            }
+
+            teacher_busy.insert((teacher_id, a.day.as_str(), a.module_index));
+            if classroom_id != 0 {
+                room_busy.insert((classroom_id, a.day.as_str(), a.module_index));
+            }
+            assignments.push(Assignment {
+                id: None,
+                group_id,
+                subject_id,
+                teacher_id,
+                classroom_id,
+                ..a.clone()
+            });
+        }
+    }
+
+    report.teacher_subjects = teacher_subjects.len() + overwritten_teacher_subjects.len();
+    report.group_subjects = group_subjects.len() + overwritten_group_subjects.len();
+    report.students = students.len();
+    report.assignments = assignments.len();
+
+    MergePlan {
+        insert: ScheduleData {
+            subjects: subjects.insert,
+            teachers: teachers.insert,
+            teacher_subjects,
+            groups: groups.insert,
+            group_subjects,
+            students,
+            classrooms: classrooms.insert,
+            assignments,
+            config: None,
+            school: None,
+        },
+        overwrite: ScheduleData {
+            subjects: subjects.overwrite,
+            teachers: teachers.overwrite,
+            teacher_subjects: overwritten_teacher_subjects,
+            groups: groups.overwrite,
+            group_subjects: overwritten_group_subjects,
+            students: Vec::new(),
+            classrooms: classrooms.overwrite,
+            assignments: Vec::new(),
+            config: None,
+            school: None,
+        },
+        report,
+    }
+}
+
+fn label_of(group: Option<&Group>) -> String {
+    group.map_or_else(|| "Unknown group".to_string(), |g| g.label())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::testing::{group, lesson, subject, teacher};
+
+    fn data(
+        subjects: Vec<Subject>,
+        teachers: Vec<Teacher>,
+        teacher_subjects: &[(i16, i16)],
+        groups: Vec<Group>,
+        group_subjects: &[(i16, i16)],
+        assignments: Vec<Assignment>,
+    ) -> ScheduleData {
+        ScheduleData {
+            subjects,
+            teachers,
+            teacher_subjects: teacher_subjects
+                .iter()
+                .map(|&(teacher_id, subject_id)| TeacherSubjects {
+                    teacher_id,
+                    subject_id,
+                })
+                .collect(),
+            groups,
+            group_subjects: group_subjects
+                .iter()
+                .map(|&(group_id, subject_id)| GroupSubjects {
+                    group_id,
+                    subject_id,
+                })
+                .collect(),
+            students: Vec::new(),
+            classrooms: Vec::new(),
+            assignments,
+            config: None,
+            school: None,
+        }
+    }
+
+    fn teacher_links(data: &ScheduleData) -> Vec<(i16, i16)> {
+        data.teacher_subjects
+            .iter()
+            .map(|ts| (ts.teacher_id, ts.subject_id))
+            .collect()
+    }
+
+    fn group_links(data: &ScheduleData) -> Vec<(i16, i16)> {
+        data.group_subjects
+            .iter()
+            .map(|gs| (gs.group_id, gs.subject_id))
+            .collect()
+    }
+
+    fn count<'a>(report: &'a MergeReport, entity: &str) -> &'a MergeCount {
+        report.counts.iter().find(|c| c.entity == entity).unwrap()
+    }
+
+    #[test]
+    fn remaps_ids_and_applies_each_action() {
+        let current = data(
+            vec![subject(1, "Matematicas", 2), subject(2, "Español", 2)],
+            vec![teacher(1, "Ana"), teacher(2, "Luis")],
+            &[(1, 1), (1, 2), (2, 1)],
+            vec![group(1, 1, "A")],
+            &[(1, 1), (1, 2)],
+            vec![lesson(1, "Lunes", 0, 1, 1)],
+        );
+        // Los ids del archivo no coinciden con los de la base de datos
+        let incoming = data(
+            vec![subject(7, "Español", 2), subject(8, "Historia", 2)],
+            vec![teacher(5, "Ana"), teacher(6, "Luis"), teacher(9, "Eva")],
+            &[(5, 8), (6, 7), (9, 8)],
+            vec![group(4, 1, "A"), group(5, 2, "B")],
+            &[(4, 7), (5, 8)],
+            vec![lesson(5, "Lunes", 0, 8, 9), lesson(4, "Lunes", 1, 7, 6)],
+        );
+        let options = MergeOptions {
+            on_conflict: MergeAction::Skip,
+            resolutions: vec![
+                MergeResolution {
+                    entity: model::TEACHER.to_string(),
+                    file_id: 5,
+                    action: MergeAction::Overwrite,
+                },
+                MergeResolution {
+                    entity: model::GROUP.to_string(),
+                    file_id: 4,
+                    action: MergeAction::KeepBoth,
+                },
+            ],
+            ..MergeOptions::default()
+        };
+
+        let plan = plan_merge(&current, &incoming, &options);
+
+        // Español se salta y apunta a la materia 2, Historia se crea con el id 3
+        assert_eq!(plan.insert.subjects.len(), 1);
+        assert_eq!(plan.insert.subjects[0].id, Some(3));
+        assert_eq!(count(&plan.report, model::SUBJECT).skipped, 1);
+
+        // Ana se sobrescribe y sus materias se reemplazan por las del archivo,
+        // Luis se salta (sin traer sus materias) y Eva se crea
+        assert_eq!(plan.overwrite.teachers.len(), 1);
+        assert_eq!(plan.overwrite.teachers[0].id, Some(1));
+        assert_eq!(teacher_links(&plan.overwrite), vec![(1, 3)]);
+        assert_eq!(plan.insert.teachers.len(), 1);
+        assert_eq!(plan.insert.teachers[0].id, Some(3));
+        assert_eq!(teacher_links(&plan.insert), vec![(3, 3)]);
+        let teachers = count(&plan.report, model::TEACHER);
+        assert_eq!(
+            (teachers.created, teachers.overwritten, teachers.skipped),
+            (1, 1, 1)
+        );
+
+        // 1A se agrega aunque ya existe y 2B es nuevo
+        let group_ids: Vec<_> = plan.insert.groups.iter().map(|g| g.id).collect();
+        assert_eq!(group_ids, vec![Some(2), Some(3)]);
+        assert_eq!(group_links(&plan.insert), vec![(2, 2), (3, 3)]);
+        assert!(plan.overwrite.groups.is_empty());
+        assert_eq!(count(&plan.report, model::GROUP).kept_both, 1);
+
+        // La clase de 2B se trae con los ids nuevos; la de 1A no porque Luis no
+        // puede dar Español
+        assert_eq!(plan.insert.assignments.len(), 1);
+        let a = &plan.insert.assignments[0];
+        assert_eq!((a.group_id, a.subject_id, a.teacher_id), (3, 3, 3));
+        assert_eq!(plan.report.skipped_lessons.len(), 1);
+        assert!(plan.report.skipped_lessons[0].contains("can't teach"));
+    }
+
+    #[test]
+    fn overwritten_group_replaces_its_subjects() {
+        let current = data(
+            vec![subject(1, "Matematicas", 2), subject(2, "Español", 2)],
+            Vec::new(),
+            &[],
+            vec![group(1, 1, "A")],
+            &[(1, 1), (1, 2)],
+            Vec::new(),
+        );
+        let incoming = data(
+            vec![subject(1, "Matematicas", 2)],
+            Vec::new(),
+            &[],
+            vec![group(3, 1, "A")],
+            &[(3, 1)],
+            Vec::new(),
+        );
+        let options = MergeOptions {
+            on_conflict: MergeAction::Overwrite,
+            ..MergeOptions::default()
+        };
+
+        let plan = plan_merge(&current, &incoming, &options);
+
+        assert_eq!(plan.overwrite.groups[0].id, Some(1));
+        assert_eq!(group_links(&plan.overwrite), vec![(1, 1)]);
+        assert!(plan.insert.group_subjects.is_empty());
+    }
+}
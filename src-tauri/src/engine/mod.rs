@@ -0,0 +1,47 @@
+/*
+    Motor de generacion de horarios.
+    No depende de tauri: trabaja sobre un `Snapshot` en memoria que se puede leer de un
+    archivo o construir a mano, asi se puede usar desde pruebas, scripts o un servidor.
+    Los comandos de tauri (util::generate) cargan el snapshot de la base de datos
+    (util::snapshot), llaman al motor y guardan.
+*/
+
+pub mod availability;
+pub mod calendar;
+pub mod compare;
+pub mod diff;
+pub mod merge;
+pub mod overrides;
+pub mod rng;
+pub mod rollover;
+pub mod scope;
+pub mod score;
+pub mod sessions;
+pub mod snapshot;
+pub mod solver;
+pub mod substitutes;
+pub mod validate;
+
+#[cfg(test)]
+mod testing;
+
+pub use availability::{
+    common_free_slots, free_classrooms, free_teachers, ClassroomFilter, FreeClassroom, FreeTeacher,
+    MeetingSlot,
+};
+pub use calendar::{format_date, parse_date, school_day, Holiday, Term};
+pub use compare::{compare_schedules, format_change_sheet, ScheduleComparison};
+pub use diff::{diff_lessons, diff_schedules, CellChange, CellValue, ScheduleDiff};
+pub use merge::{plan_merge, MergeOptions, MergeReport};
+pub use overrides::{
+    apply_changes, DateChanges, ResourceClosure, ScheduleOverride, CLOSE_CLASSROOM, CLOSE_GROUP,
+};
+pub use rollover::{plan_rollover, RolloverOptions};
+pub use scope::{regenerate, GenerationScope, RegenerationReport};
+pub use sessions::{
+    count_sessions, effective_timetable, expand_sessions, DatedSession, EffectiveDay, SessionCount,
+};
+pub use snapshot::Snapshot;
+pub use solver::{generate, GenerateOptions, GenerationReport};
+pub use substitutes::{plan_cover, substitutes_for, Absence, CoverNeed};
+pub use validate::{introduced_violations, validate_schedule, Severity, Violation};
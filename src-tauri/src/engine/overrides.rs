@@ -0,0 +1,229 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::model::Assignment;
+
+use super::calendar::{format_date, school_day};
+use super::snapshot::Snapshot;
+
+// Recursos que se pueden cerrar por fecha
+pub const CLOSE_GROUP: &str = "group";
+pub const CLOSE_CLASSROOM: &str = "classroom";
+
+/// Cambio al horario que solo aplica en una fecha
+/// Reemplaza la clase del grupo en ese modulo; sin materia o sin profesor la clase no se da
+/// `kind` es "substitution", "cancelled", "exam" o "change"
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ScheduleOverride {
+    pub id: i64,
+    pub date: String,
+    pub group_id: i16,
+    pub module_index: i16,
+    pub subject_id: Option<i16>,
+    pub teacher_id: Option<i16>,
+    pub classroom_id: Option<i16>,
+    pub kind: String,
+    pub absence_id: Option<i64>,
+    pub note: Option<String>,
+}
+
+/// Cierre de un grupo o un aula entre dos fechas
+/// Un grupo cerrado no tiene clases (ej. salida de campo) y las clases de un aula
+/// cerrada se quedan sin aula (ej. reparaciones)
+/// Sin `start_module` / `end_module` el cierre es de todo el dia
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ResourceClosure {
+    pub id: i64,
+    pub resource: String,
+    pub resource_id: i16,
+    pub start_date: String,
+    pub end_date: String,
+    pub start_module: Option<i16>,
+    pub end_module: Option<i16>,
+    pub kind: String,
+    pub note: Option<String>,
+}
+
+impl ResourceClosure {
+    /// Funcion que dice si el cierre toca una fecha y modulo
+    pub fn covers(&self, date: &str, module_index: i16) -> bool {
+        self.start_date.as_str() <= date
+            && date <= self.end_date.as_str()
+            && self.start_module.map_or(true, |m| module_index >= m)
+            && self.end_module.map_or(true, |m| module_index <= m)
+    }
+}
+
+/// Cambios por fecha que se aplican sobre el horario semanal
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DateChanges {
+    pub overrides: Vec<ScheduleOverride>,
+    pub closures: Vec<ResourceClosure>,
+}
+
+impl DateChanges {
+    /// Funcion que retorna el cambio guardado para una celda en una fecha
+    pub fn cell(&self, date: &str, group_id: i16, module_index: i16) -> Option<&ScheduleOverride> {
+        self.overrides
+            .iter()
+            .find(|o| o.date == date && o.group_id == group_id && o.module_index == module_index)
+    }
+
+    /// Funcion que retorna una copia sin el cambio de una celda
+    /// Sirve para buscar opciones para esa celda como si no tuviera cambio
+    pub fn without_cell(&self, date: &str, group_id: i16, module_index: i16) -> DateChanges {
+        DateChanges {
+            overrides: self
+                .overrides
+                .iter()
+                .filter(|o| {
+                    !(o.date == date && o.group_id == group_id && o.module_index == module_index)
+                })
+                .cloned()
+                .collect(),
+            closures: self.closures.clone(),
+        }
+    }
+
+    /// Funcion que retorna el cierre de un grupo o aula que toca una fecha y modulo
+    pub fn closure(
+        &self,
+        resource: &str,
+        resource_id: i16,
+        date: &str,
+        module_index: i16,
+    ) -> Option<&ResourceClosure> {
+        self.closures.iter().find(|c| {
+            c.resource == resource && c.resource_id == resource_id && c.covers(date, module_index)
+        })
+    }
+}
+
+/// Clase de una fecha con el cambio que la afecto
+/// `kind` es el tipo del cambio por celda o del cierre, `None` si la clase es la del horario
+#[derive(Debug, Clone)]
+pub struct ChangedLesson {
+    pub assignment: Assignment,
+    pub kind: Option<String>,
+    pub note: Option<String>,
+}
+
+/// Funcion para aplicar los cambios de una fecha a las clases de su dia de la semana
+/// # Argumentos
+/// * `snapshot` - Datos de la escuela
+/// * `schedule` - Horario semanal
+/// * `date` - Fecha a revisar
+/// * `changes` - Cambios por celda y cierres
+/// Retorna las clases que se dan y las que se cancelan (vacio si ese dia no hay clases)
+/// Los cambios por celda se aplican primero y despues los cierres de grupos y aulas
+pub fn apply_changes(
+    snapshot: &Snapshot,
+    schedule: &[Assignment],
+    date: NaiveDate,
+    changes: &DateChanges,
+) -> (Vec<ChangedLesson>, Vec<ChangedLesson>) {
+    let Some(day) = school_day(&snapshot.days, date) else {
+        return (Vec::new(), Vec::new());
+    };
+    let date = format_date(date);
+
+    let mut lessons = Vec::new();
+    let mut cancelled = Vec::new();
+
+    for a in schedule.iter().filter(|a| a.day == day) {
+        match changes.cell(&date, a.group_id, a.module_index) {
+            // La clase del horario se cancela o se reemplaza mas abajo
+            Some(o) if o.subject_id.is_none() || o.teacher_id.is_none() => {
+                cancelled.push(ChangedLesson {
+                    assignment: a.clone(),
+                    kind: Some(o.kind.clone()),
+                    note: o.note.clone(),
+                });
+            }
+            Some(_) => {}
+            None => lessons.push(ChangedLesson {
+                assignment: a.clone(),
+                kind: None,
+                note: None,
+            }),
+        }
+    }
+
+    for o in changes.overrides.iter().filter(|o| o.date == date) {
+        let (Some(subject_id), Some(teacher_id)) = (o.subject_id, o.teacher_id) else {
+            continue;
+        };
+        let subject = snapshot.subject(subject_id);
+        lessons.push(ChangedLesson {
+            assignment: Assignment {
+                id: None,
+                group_id: o.group_id,
+                day: day.clone(),
+                module_index: o.module_index,
+                subject_id,
+                teacher_id,
+                classroom_id: o.classroom_id.unwrap_or_default(),
+                subject_name: subject.map(|s| s.name.clone()).unwrap_or_default(),
+                subject_shorten: subject.map(|s| s.shorten.clone()).unwrap_or_default(),
+                subject_color: subject.map(|s| s.color.clone()).unwrap_or_default(),
+            },
+            kind: Some(o.kind.clone()),
+            note: o.note.clone(),
+        });
+    }
+
+    let mut kept = Vec::new();
+    for mut lesson in lessons {
+        let a = &lesson.assignment;
+        if let Some(c) = changes.closure(CLOSE_GROUP, a.group_id, &date, a.module_index) {
+            lesson.kind = Some(c.kind.clone());
+            lesson.note = c.note.clone();
+            cancelled.push(lesson);
+            continue;
+        }
+        if let Some(c) = changes.closure(CLOSE_CLASSROOM, a.classroom_id, &date, a.module_index) {
+            lesson.assignment.classroom_id = 0;
+            lesson.kind = Some(c.kind.clone());
+            lesson.note = c.note.clone();
+        }
+        kept.push(lesson);
+    }
+
+    kept.sort_by_key(|l| (l.assignment.module_index, l.assignment.group_id));
+    cancelled.sort_by_key(|l| (l.assignment.module_index, l.assignment.group_id));
+    (kept, cancelled)
+}
+
+/// Funcion que arma el horario efectivo de una fecha: las clases de ese dia de la semana
+/// con los cambios de la fecha ya aplicados
+/// Retorna una lista vacia si ese dia no hay clases
+pub fn effective_day(
+    snapshot: &Snapshot,
+    schedule: &[Assignment],
+    date: NaiveDate,
+    changes: &DateChanges,
+) -> Vec<Assignment> {
+    apply_changes(snapshot, schedule, date, changes)
+        .0
+        .into_iter()
+        .map(|l| l.assignment)
+        .collect()
+}
+
+/// Funcion que arma el horario de la semana con los cambios de una fecha aplicados a su dia
+/// Sirve para calcular cargas y disponibilidad tomando en cuenta los cambios de esa fecha
+pub fn effective_week(
+    snapshot: &Snapshot,
+    schedule: &[Assignment],
+    date: NaiveDate,
+    changes: &DateChanges,
+) -> Vec<Assignment> {
+    let Some(day) = school_day(&snapshot.days, date) else {
+        return schedule.to_vec();
+    };
+
+    let mut week: Vec<Assignment> = schedule.iter().filter(|a| a.day != day).cloned().collect();
+    week.extend(effective_day(snapshot, schedule, date, changes));
+    week
+}
@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{Assignment, Group, GroupSubjects};
+
+use super::snapshot::Snapshot;
+
+/// Opciones para pasar al siguiente periodo
+/// `final_grade` es el ultimo grado (sus grupos egresan), si no se manda se usa el mayor;
+/// `new_entry_groups` crea de nuevo los grupos del primer grado con las mismas letras
+/// y `copy_timetable` copia el horario de cada grupo al grupo con el mismo nombre
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RolloverOptions {
+    pub final_grade: Option<i16>,
+    pub new_entry_groups: bool,
+    pub copy_timetable: bool,
+}
+
+impl Default for RolloverOptions {
+    fn default() -> Self {
+        RolloverOptions {
+            final_grade: None,
+            new_entry_groups: true,
+            copy_timetable: false,
+        }
+    }
+}
+
+/// Datos del siguiente periodo
+/// Los grupos que suben de grado conservan su id (y sus alumnos); los nuevos grupos
+/// de primer grado usan ids nuevos
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RolloverPlan {
+    pub groups: Vec<Group>,
+    pub group_subjects: Vec<GroupSubjects>,
+    pub assignments: Vec<Assignment>,
+    pub promoted: Vec<String>,
+    pub retired: Vec<String>,
+    pub created: Vec<String>,
+}
+
+/// Funcion para armar los grupos, materias y horario del siguiente periodo
+/// # Argumentos
+/// * `snapshot` - Datos del periodo actual
+/// * `options` - Ultimo grado, si se crean grupos de primer grado y si se copia el horario
+/// Cada grupo sube un grado y toma las materias que tenia el grupo con su nuevo nombre
+/// (ej. 1A toma las materias del 2A anterior); si no habia ese grupo conserva las suyas
+pub fn plan_rollover(snapshot: &Snapshot, options: &RolloverOptions) -> RolloverPlan {
+    let mut plan = RolloverPlan::default();
+
+    let Some(final_grade) = options
+        .final_grade
+        .or_else(|| snapshot.groups.iter().map(|g| g.grade).max())
+    else {
+        return plan;
+    };
+    let entry_grade = snapshot.groups.iter().map(|g| g.grade).min();
+
+    // Grupos del periodo actual por nombre (grado, letra)
+    let by_label: BTreeMap<(i16, &str), i16> = snapshot
+        .groups
+        .iter()
+        .filter_map(|g| Some(((g.grade, g.group.as_str()), g.id?)))
+        .collect();
+    let subjects_of = |group_id: i16| -> Vec<i16> {
+        snapshot
+            .group_subjects
+            .iter()
+            .filter(|gs| gs.group_id == group_id)
+            .map(|gs| gs.subject_id)
+            .collect()
+    };
+
+    // (id nuevo, id del grupo anterior con el mismo nombre, id de donde salen sus materias)
+    let mut next_id = snapshot
+        .groups
+        .iter()
+        .filter_map(|g| g.id)
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let mut sources = Vec::new();
+
+    let mut groups: Vec<&Group> = snapshot.groups.iter().filter(|g| g.id.is_some()).collect();
+    groups.sort_by_key(|g| (g.grade, g.group.clone()));
+
+    for group in groups {
+        let id = group.id.unwrap_or_default();
+        let label = format!("{}{}", group.grade, group.group);
+
+        if group.grade >= final_grade {
+            plan.retired.push(label.clone());
+        } else {
+            let grade = group.grade + 1;
+            let same_name = by_label.get(&(grade, group.group.as_str())).copied();
+            plan.groups.push(Group {
+                grade,
+                ..group.clone()
+            });
+            plan.promoted
+                .push(format!("{} -> {}{}", label, grade, group.group));
+            sources.push((id, same_name, same_name.unwrap_or(id)));
+        }
+
+        if options.new_entry_groups && Some(group.grade) == entry_grade {
+            plan.groups.push(Group {
+                id: Some(next_id),
+                ..group.clone()
+            });
+            plan.created.push(label);
+            sources.push((next_id, Some(id), id));
+            next_id += 1;
+        }
+    }
+
+    for &(id, same_name, subjects_from) in &sources {
+        plan.group_subjects
+            .extend(
+                subjects_of(subjects_from)
+                    .into_iter()
+                    .map(|subject_id| GroupSubjects {
+                        group_id: id,
+                        subject_id,
+                    }),
+            );
+
+        if let (true, Some(previous)) = (options.copy_timetable, same_name) {
+            plan.assignments.extend(
+                snapshot
+                    .assignments
+                    .iter()
+                    .filter(|a| a.group_id == previous)
+                    .map(|a| Assignment {
+                        id: None,
+                        group_id: id,
+                        ..a.clone()
+                    }),
+            );
+        }
+    }
+
+    plan.groups.sort_by_key(|g| g.id);
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::testing::{group, lesson, subject, teacher};
+
+    #[test]
+    fn groups_move_up_and_take_the_timetable_of_their_new_name() {
+        // 1A, 2A y 3A con una materia cada uno; 3 es el ultimo grado
+        let snapshot = Snapshot {
+            subjects: vec![
+                subject(1, "Matematicas", 1),
+                subject(2, "Arte", 1),
+                subject(3, "Fisica", 1),
+            ],
+            teachers: vec![teacher(1, "Ana")],
+            teacher_subjects: vec![(1, 1), (1, 2), (1, 3)],
+            groups: vec![group(1, 1, "A"), group(2, 2, "A"), group(3, 3, "A")],
+            group_subjects: [(1, 1), (2, 2), (3, 3)]
+                .iter()
+                .map(|&(group_id, subject_id)| GroupSubjects {
+                    group_id,
+                    subject_id,
+                })
+                .collect(),
+            classrooms: vec![],
+            assignments: vec![
+                lesson(1, "Lunes", 0, 1, 1),
+                lesson(2, "Lunes", 1, 2, 1),
+                lesson(3, "Lunes", 2, 3, 1),
+            ],
+            days: vec!["Lunes".to_string()],
+            modules_per_day: 3,
+        };
+        let options = RolloverOptions {
+            copy_timetable: true,
+            ..RolloverOptions::default()
+        };
+
+        let plan = plan_rollover(&snapshot, &options);
+
+        assert_eq!(plan.promoted, vec!["1A -> 2A", "2A -> 3A"]);
+        assert_eq!(plan.retired, vec!["3A"]);
+        assert_eq!(plan.created, vec!["1A"]);
+
+        // Los grupos que suben conservan su id, el nuevo 1A usa el siguiente
+        let groups: Vec<(Option<i16>, i16)> = plan.groups.iter().map(|g| (g.id, g.grade)).collect();
+        assert_eq!(groups, vec![(Some(1), 2), (Some(2), 3), (Some(4), 1)]);
+
+        // Cada grupo toma las materias y el horario del grupo que tenia su nuevo nombre
+        let mut subjects: Vec<(i16, i16)> = plan
+            .group_subjects
+            .iter()
+            .map(|gs| (gs.group_id, gs.subject_id))
+            .collect();
+        subjects.sort();
+        assert_eq!(subjects, vec![(1, 2), (2, 3), (4, 1)]);
+
+        let mut lessons: Vec<(i16, i16, i16)> = plan
+            .assignments
+            .iter()
+            .map(|a| (a.group_id, a.module_index, a.subject_id))
+            .collect();
+        lessons.sort();
+        assert_eq!(lessons, vec![(1, 1, 2), (2, 2, 3), (4, 0, 1)]);
+        assert!(plan.assignments.iter().all(|a| a.id.is_none()));
+    }
+}
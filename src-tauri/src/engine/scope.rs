@@ -0,0 +1,241 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::Assignment;
+
+use super::diff::{diff_schedules, CellChange};
+use super::snapshot::Snapshot;
+use super::solver::{generate_with_plan, GenerateOptions, GenerationReport, Plan};
+use super::validate::validate_schedule;
+
+/// Parte del horario que se vuelve a generar
+/// Dentro de cada lista basta con coincidir con un elemento; si hay varias listas
+/// con valores se deben cumplir todas (ej. profesor 3 solo el Lunes)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GenerationScope {
+    #[serde(default)]
+    pub group_ids: Vec<i16>,
+    #[serde(default)]
+    pub teacher_ids: Vec<i16>,
+    #[serde(default)]
+    pub days: Vec<String>,
+}
+
+impl GenerationScope {
+    pub fn is_empty(&self) -> bool {
+        self.group_ids.is_empty() && self.teacher_ids.is_empty() && self.days.is_empty()
+    }
+
+    /// Indica si la asignacion se libera para volver a generarla
+    pub fn contains(&self, a: &Assignment) -> bool {
+        !self.is_empty()
+            && (self.group_ids.is_empty() || self.group_ids.contains(&a.group_id))
+            && (self.teacher_ids.is_empty() || self.teacher_ids.contains(&a.teacher_id))
+            && (self.days.is_empty() || self.days.contains(&a.day))
+    }
+}
+
+/// Resultado de una regeneracion parcial
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegenerationReport {
+    /// Horario completo (lo que se conservo mas lo que se volvio a generar)
+    pub report: GenerationReport,
+    /// Asignaciones que se liberaron
+    pub freed: usize,
+    /// Celdas que cambiaron respecto al horario del snapshot
+    pub changes: Vec<CellChange>,
+    /// Clases que se volvieron a colocar sin su aula porque estaba ocupada
+    pub dropped_rooms: Vec<DroppedRoom>,
+}
+
+/// Clase que quedo sin aula al volver a generarla
+/// `classroom_id` es el aula que tenia la materia del grupo antes de liberarla
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DroppedRoom {
+    pub group_id: i16,
+    pub subject_id: i16,
+    pub day: String,
+    pub module_index: i16,
+    pub classroom_id: i16,
+}
+
+/// Funcion para volver a generar solo una parte del horario
+/// Libera las asignaciones del snapshot que entran en `scope`, conserva el resto fijo
+/// y vuelve a colocar las materias afectadas alrededor de lo que se conservo
+/// Si el alcance tiene dias, los bloques nuevos solo se colocan en esos dias
+/// Las clases nuevas conservan el aula que tenia su materia si sigue libre en la nueva celda
+pub fn regenerate(
+    snapshot: &Snapshot,
+    scope: &GenerationScope,
+    options: &GenerateOptions,
+) -> RegenerationReport {
+    let (freed, kept): (Vec<&Assignment>, Vec<&Assignment>) =
+        snapshot.assignments.iter().partition(|a| scope.contains(a));
+
+    // Materias que se vuelven a resolver: las que se liberaron y, si se eligieron
+    // grupos, todas las de esos grupos (para completar las que ya estaban incompletas)
+    let mut pairs: BTreeSet<(i16, i16)> =
+        freed.iter().map(|a| (a.group_id, a.subject_id)).collect();
+    if scope.teacher_ids.is_empty() && scope.days.is_empty() {
+        for gs in &snapshot.group_subjects {
+            if scope.group_ids.contains(&gs.group_id) {
+                pairs.insert((gs.group_id, gs.subject_id));
+            }
+        }
+    }
+
+    let plan = Plan {
+        base: kept.into_iter().cloned().collect(),
+        pairs: Some(pairs),
+        days: if scope.days.is_empty() {
+            None
+        } else {
+            Some(scope.days.clone())
+        },
+    };
+
+    let mut report = generate_with_plan(snapshot, &plan, options);
+    let dropped_rooms = restore_rooms(&mut report.assignments, &plan.base, &freed);
+    report.violations = validate_schedule(snapshot, &report.assignments);
+    let changes = diff_schedules(&snapshot.assignments, &report.assignments);
+
+    RegenerationReport {
+        report,
+        freed: freed.len(),
+        changes,
+        dropped_rooms,
+    }
+}
+
+// El solver coloca las clases sin aula; a cada clase nueva le da el aula que tenian las
+// clases liberadas de su grupo y materia (primero la de la misma celda) si esta libre
+// Retorna las clases que tenian aula y ya no se pudo conservar
+fn restore_rooms(
+    schedule: &mut [Assignment],
+    base: &[Assignment],
+    freed: &[&Assignment],
+) -> Vec<DroppedRoom> {
+    let kept: BTreeSet<(i16, &str, i16)> = base
+        .iter()
+        .map(|a| (a.group_id, a.day.as_str(), a.module_index))
+        .collect();
+
+    let mut rooms: BTreeMap<(i16, i16), Vec<&Assignment>> = BTreeMap::new();
+    for a in freed.iter().filter(|a| a.classroom_id != 0) {
+        rooms.entry((a.group_id, a.subject_id)).or_default().push(a);
+    }
+
+    let mut busy: BTreeSet<(i16, String, i16)> = schedule
+        .iter()
+        .filter(|a| a.classroom_id != 0)
+        .map(|a| (a.classroom_id, a.day.clone(), a.module_index))
+        .collect();
+
+    let mut dropped = Vec::new();
+    for a in schedule.iter_mut() {
+        if a.classroom_id != 0 || kept.contains(&(a.group_id, a.day.as_str(), a.module_index)) {
+            continue;
+        }
+        let Some(previous) = rooms.get(&(a.group_id, a.subject_id)) else {
+            continue;
+        };
+
+        let mut candidates: Vec<i16> = previous
+            .iter()
+            .filter(|p| p.day == a.day && p.module_index == a.module_index)
+            .chain(previous.iter())
+            .map(|p| p.classroom_id)
+            .collect();
+        candidates.dedup();
+
+        let free = candidates
+            .iter()
+            .copied()
+            .find(|&room| !busy.contains(&(room, a.day.clone(), a.module_index)));
+        match free {
+            Some(room) => {
+                a.classroom_id = room;
+                busy.insert((room, a.day.clone(), a.module_index));
+            }
+            None => dropped.push(DroppedRoom {
+                group_id: a.group_id,
+                subject_id: a.subject_id,
+                day: a.day.clone(),
+                module_index: a.module_index,
+                classroom_id: candidates[0],
+            }),
+        }
+    }
+
+    dropped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::testing::{classroom, group, lesson, subject, teacher};
+    use crate::model::GroupSubjects;
+
+    fn in_room(classroom_id: i16, lesson: Assignment) -> Assignment {
+        Assignment {
+            classroom_id,
+            ..lesson
+        }
+    }
+
+    #[test]
+    fn regenerated_lessons_keep_their_room_when_it_is_free() {
+        // 1A tiene Arte en el aula 2 y Matematicas en el aula 1; 1B usa el aula 1 a
+        // primera hora y no se libera
+        let snapshot = Snapshot {
+            subjects: vec![subject(1, "Matematicas", 1), subject(2, "Arte", 1)],
+            teachers: vec![teacher(1, "Ana"), teacher(2, "Luis"), teacher(3, "Eva")],
+            teacher_subjects: vec![(1, 1), (2, 2), (3, 1)],
+            groups: vec![group(1, 1, "A"), group(2, 1, "B")],
+            group_subjects: [(1, 1), (1, 2), (2, 1)]
+                .iter()
+                .map(|&(group_id, subject_id)| GroupSubjects {
+                    group_id,
+                    subject_id,
+                })
+                .collect(),
+            classrooms: vec![classroom(1, 101), classroom(2, 102)],
+            assignments: vec![
+                in_room(2, lesson(1, "Lunes", 0, 2, 2)),
+                in_room(1, lesson(1, "Lunes", 1, 1, 1)),
+                in_room(1, lesson(2, "Lunes", 0, 1, 3)),
+            ],
+            days: vec!["Lunes".to_string()],
+            modules_per_day: 2,
+        };
+        let scope = GenerationScope {
+            group_ids: vec![1],
+            ..GenerationScope::default()
+        };
+
+        let result = regenerate(&snapshot, &scope, &GenerateOptions::default());
+
+        // El solver pone Matematicas primero: Arte se mueve a la segunda hora y conserva
+        // el aula 2, Matematicas pasa a la primera hora donde el aula 1 ya esta ocupada
+        let cell = |module_index: i16| {
+            result
+                .report
+                .assignments
+                .iter()
+                .find(|a| a.group_id == 1 && a.module_index == module_index)
+                .map(|a| (a.subject_id, a.classroom_id))
+        };
+        assert_eq!(result.freed, 2);
+        assert_eq!(cell(0), Some((1, 0)));
+        assert_eq!(cell(1), Some((2, 2)));
+
+        assert_eq!(result.dropped_rooms.len(), 1);
+        let dropped = &result.dropped_rooms[0];
+        assert_eq!(
+            (dropped.group_id, dropped.subject_id, dropped.module_index),
+            (1, 1, 0)
+        );
+        assert_eq!(dropped.classroom_id, 1);
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::Assignment;
+
+use super::snapshot::Snapshot;
+use super::solver::required_modules;
+
+// Penalizaciones usadas para calcular el puntaje total
+const MISSING_MODULE_PENALTY: i64 = 100;
+const GROUP_GAP_PENALTY: i64 = 10;
+const TEACHER_GAP_PENALTY: i64 = 5;
+
+/// Puntaje de un horario
+/// `total` es 0 para un horario perfecto y va bajando con cada penalizacion
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleScore {
+    pub required_modules: i64,
+    pub placed_modules: i64,
+    pub missing_modules: i64,
+    pub group_gaps: i64,
+    pub teacher_gaps: i64,
+    pub total: i64,
+}
+
+/// Funcion para calcular el puntaje de un horario contra los datos del snapshot
+pub fn score_schedule(snapshot: &Snapshot, schedule: &[Assignment]) -> ScheduleScore {
+    let mut required = 0;
+    let mut missing = 0;
+
+    for gs in &snapshot.group_subjects {
+        let Some(subject) = snapshot.subject(gs.subject_id) else {
+            continue;
+        };
+        let required_for_subject = required_modules(subject) as i64;
+        let assigned = schedule
+            .iter()
+            .filter(|a| a.group_id == gs.group_id && a.subject_id == gs.subject_id)
+            .count() as i64;
+
+        required += required_for_subject;
+        missing += (required_for_subject - assigned).max(0);
+    }
+
+    let group_gaps = count_gaps(
+        schedule
+            .iter()
+            .map(|a| (a.group_id, a.day.as_str(), a.module_index)),
+    );
+    let teacher_gaps = count_gaps(
+        schedule
+            .iter()
+            .map(|a| (a.teacher_id, a.day.as_str(), a.module_index)),
+    );
+
+    let total = -(missing * MISSING_MODULE_PENALTY
+        + group_gaps * GROUP_GAP_PENALTY
+        + teacher_gaps * TEACHER_GAP_PENALTY);
+
+    ScheduleScore {
+        required_modules: required,
+        placed_modules: schedule.len() as i64,
+        missing_modules: missing,
+        group_gaps,
+        teacher_gaps,
+        total,
+    }
+}
+
+// Cuenta los modulos libres entre la primera y la ultima clase de cada (entidad, dia)
+fn count_gaps<'s>(entries: impl Iterator<Item = (i16, &'s str, i16)>) -> i64 {
+    let mut modules: HashMap<(i16, &str), Vec<i16>> = HashMap::new();
+    for (owner, day, module) in entries {
+        modules.entry((owner, day)).or_default().push(module);
+    }
+
+    modules
+        .values_mut()
+        .map(|list| {
+            list.sort_unstable();
+            list.dedup();
+            let span = (list[list.len() - 1] - list[0] + 1) as i64;
+            span - list.len() as i64
+        })
+        .sum()
+}
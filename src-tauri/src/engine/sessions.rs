@@ -0,0 +1,188 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use super::calendar::{dates_between, format_date, parse_date, school_day, Holiday, Term};
+use super::diff::Lesson;
+use super::overrides::{apply_changes, ChangedLesson, DateChanges};
+use super::snapshot::Snapshot;
+use super::solver::required_modules;
+
+/// Clase del horario en una fecha real
+/// `override_kind` indica si la clase viene de un cambio por fecha (ej. "substitution")
+/// o de un cierre (ej. el aula esta cerrada y la clase se quedo sin aula)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatedSession {
+    pub date: String,
+    pub lesson: Lesson,
+    pub override_kind: Option<String>,
+    pub note: Option<String>,
+}
+
+impl DatedSession {
+    fn new(snapshot: &Snapshot, date: &str, changed: &ChangedLesson) -> Self {
+        DatedSession {
+            date: date.to_string(),
+            lesson: Lesson::new(snapshot, &changed.assignment),
+            override_kind: changed.kind.clone(),
+            note: changed.note.clone(),
+        }
+    }
+}
+
+/// Horario efectivo de una fecha
+/// `day` es el dia de la semana del horario (`None` si ese dia no hay clases),
+/// `holiday` el festivo que cae en esa fecha y `cancelled` las clases del horario
+/// semanal que no se dan por un cambio o un cierre
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectiveDay {
+    pub date: String,
+    pub day: Option<String>,
+    pub holiday: Option<String>,
+    pub lessons: Vec<DatedSession>,
+    pub cancelled: Vec<DatedSession>,
+}
+
+/// Sesiones de una materia en un grupo durante un periodo
+/// `expected` son los modulos por semana de la materia por las semanas del periodo,
+/// `scheduled` las que da el horario semanal sin contar festivos ni cambios
+/// y `delivered` las que quedan despues de quitar festivos y aplicar los cambios por fecha
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionCount {
+    pub group_id: i16,
+    pub group: String,
+    pub subject_id: i16,
+    pub subject: String,
+    pub expected: i64,
+    pub scheduled: i64,
+    pub delivered: i64,
+}
+
+/// Funcion para armar el horario efectivo de una fecha
+/// # Argumentos
+/// * `snapshot` - Datos de la escuela (con el horario base en `assignments`)
+/// * `date` - Fecha a revisar
+/// * `holidays` - Festivos y cierres de la escuela, ese dia no hay clases
+/// * `changes` - Cambios por celda y cierres de grupos o aulas
+pub fn effective_timetable(
+    snapshot: &Snapshot,
+    date: NaiveDate,
+    holidays: &[Holiday],
+    changes: &DateChanges,
+) -> EffectiveDay {
+    let date_text = format_date(date);
+    let day = school_day(&snapshot.days, date);
+    let holiday = holidays.iter().find(|h| h.covers(&date_text));
+
+    let (lessons, cancelled) = if holiday.is_some() {
+        (Vec::new(), Vec::new())
+    } else {
+        apply_changes(snapshot, &snapshot.assignments, date, changes)
+    };
+
+    EffectiveDay {
+        day,
+        holiday: holiday.map(|h| h.name.clone()),
+        lessons: lessons
+            .iter()
+            .map(|l| DatedSession::new(snapshot, &date_text, l))
+            .collect(),
+        cancelled: cancelled
+            .iter()
+            .map(|l| DatedSession::new(snapshot, &date_text, l))
+            .collect(),
+        date: date_text,
+    }
+}
+
+/// Funcion para convertir el horario semanal en las clases de cada fecha de un periodo
+/// # Argumentos
+/// * `snapshot` - Datos de la escuela (con el horario base en `assignments`)
+/// * `term` - Periodo a recorrer
+/// * `holidays` - Festivos y cierres, esos dias no hay clases
+/// * `changes` - Cambios por fecha (suplencias, cancelaciones, cierres...)
+/// Retorna las clases ordenadas por fecha, modulo y grupo
+pub fn expand_sessions(
+    snapshot: &Snapshot,
+    term: &Term,
+    holidays: &[Holiday],
+    changes: &DateChanges,
+) -> Result<Vec<DatedSession>, String> {
+    let start = parse_date(&term.start_date)?;
+    let end = parse_date(&term.end_date)?;
+
+    let mut sessions = Vec::new();
+    for date in dates_between(start, end) {
+        let date_text = format_date(date);
+        if holidays.iter().any(|h| h.covers(&date_text)) {
+            continue;
+        }
+
+        let (lessons, _) = apply_changes(snapshot, &snapshot.assignments, date, changes);
+        sessions.extend(
+            lessons
+                .iter()
+                .map(|l| DatedSession::new(snapshot, &date_text, l)),
+        );
+    }
+
+    Ok(sessions)
+}
+
+/// Funcion para contar las sesiones de cada materia por grupo en un periodo
+/// Incluye las materias registradas para el grupo aunque no tengan clases
+/// Las semanas del periodo son las semanas de calendario que tienen al menos un dia de clases
+pub fn count_sessions(
+    snapshot: &Snapshot,
+    term: &Term,
+    holidays: &[Holiday],
+    changes: &DateChanges,
+) -> Result<Vec<SessionCount>, String> {
+    let start = parse_date(&term.start_date)?;
+    let end = parse_date(&term.end_date)?;
+
+    let mut weeks = BTreeSet::new();
+    let mut scheduled: BTreeMap<(i16, i16), i64> = BTreeMap::new();
+    for date in dates_between(start, end) {
+        let Some(day) = school_day(&snapshot.days, date) else {
+            continue;
+        };
+        let week = date.iso_week();
+        weeks.insert((week.year(), week.week()));
+
+        for a in snapshot.assignments.iter().filter(|a| a.day == day) {
+            *scheduled.entry((a.group_id, a.subject_id)).or_default() += 1;
+        }
+    }
+
+    let mut delivered: BTreeMap<(i16, i16), i64> = BTreeMap::new();
+    for session in expand_sessions(snapshot, term, holidays, changes)? {
+        *delivered
+            .entry((session.lesson.group_id, session.lesson.subject_id))
+            .or_default() += 1;
+    }
+
+    let mut pairs: BTreeSet<(i16, i16)> = snapshot
+        .group_subjects
+        .iter()
+        .map(|gs| (gs.group_id, gs.subject_id))
+        .collect();
+    pairs.extend(scheduled.keys().copied());
+    pairs.extend(delivered.keys().copied());
+
+    Ok(pairs
+        .into_iter()
+        .map(|(group_id, subject_id)| SessionCount {
+            group_id,
+            group: snapshot.group_label(group_id),
+            subject_id,
+            subject: snapshot.subject_label(subject_id),
+            expected: snapshot
+                .subject(subject_id)
+                .map_or(0, |s| required_modules(s) as i64 * weeks.len() as i64),
+            scheduled: scheduled.get(&(group_id, subject_id)).copied().unwrap_or(0),
+            delivered: delivered.get(&(group_id, subject_id)).copied().unwrap_or(0),
+        })
+        .collect())
+}
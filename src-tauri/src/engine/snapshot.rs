@@ -0,0 +1,131 @@
+use crate::model::{Assignment, Classroom, Config, Group, GroupSubjects, Subject, Teacher};
+use crate::schedule_file::ScheduleData;
+
+/// Copia en memoria de todos los datos que necesita el motor
+/// Se puede cargar desde la base de datos (`util::snapshot::load_snapshot`), desde un archivo
+/// (`Snapshot::from_schedule_data`) o construir a mano
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub subjects: Vec<Subject>,
+    pub teachers: Vec<Teacher>,
+    pub teacher_subjects: Vec<(i16, i16)>, // (teacher_id, subject_id)
+    pub groups: Vec<Group>,
+    pub group_subjects: Vec<GroupSubjects>,
+    pub classrooms: Vec<Classroom>,
+    pub assignments: Vec<Assignment>,
+    pub days: Vec<String>,
+    pub modules_per_day: i16,
+}
+
+impl Default for Snapshot {
+    fn default() -> Self {
+        let config = Config::default();
+        Snapshot {
+            subjects: Vec::new(),
+            teachers: Vec::new(),
+            teacher_subjects: Vec::new(),
+            groups: Vec::new(),
+            group_subjects: Vec::new(),
+            classrooms: Vec::new(),
+            assignments: Vec::new(),
+            days: config.days,
+            modules_per_day: config.modules_per_day as i16,
+        }
+    }
+}
+
+impl Snapshot {
+    /// Funcion para construir el snapshot a partir de un archivo .roster ya leido
+    /// Si el archivo no trae la configuracion del horario se usa la de por defecto
+    pub fn from_schedule_data(data: &ScheduleData) -> Self {
+        let config = data.config.clone().unwrap_or_default();
+
+        Snapshot {
+            subjects: data.subjects.clone(),
+            teachers: data.teachers.clone(),
+            teacher_subjects: data
+                .teacher_subjects
+                .iter()
+                .map(|ts| (ts.teacher_id, ts.subject_id))
+                .collect(),
+            groups: data.groups.clone(),
+            group_subjects: data.group_subjects.clone(),
+            classrooms: data.classrooms.clone(),
+            assignments: data.assignments.clone(),
+            days: config.days,
+            modules_per_day: config.modules_per_day as i16,
+        }
+    }
+
+    pub fn subject(&self, id: i16) -> Option<&Subject> {
+        self.subjects.iter().find(|s| s.id == Some(id))
+    }
+
+    pub fn teacher(&self, id: i16) -> Option<&Teacher> {
+        self.teachers.iter().find(|t| t.id == Some(id))
+    }
+
+    pub fn group(&self, id: i16) -> Option<&Group> {
+        self.groups.iter().find(|g| g.id == Some(id))
+    }
+
+    pub fn classroom(&self, id: i16) -> Option<&Classroom> {
+        self.classrooms.iter().find(|c| c.id == Some(id))
+    }
+
+    /// Nombre legible del grupo (ej. "1A"), o `#id` si no existe
+    pub fn group_label(&self, id: i16) -> String {
+        self.group(id)
+            .map(|g| format!("{}{}", g.grade, g.group))
+            .unwrap_or_else(|| format!("#{}", id))
+    }
+
+    pub fn subject_label(&self, id: i16) -> String {
+        self.subject(id)
+            .map(|s| s.name.clone())
+            .unwrap_or_else(|| format!("#{}", id))
+    }
+
+    pub fn teacher_label(&self, id: i16) -> String {
+        self.teacher(id)
+            .map(|t| format!("{} {}", t.name, t.father_lastname))
+            .unwrap_or_else(|| format!("#{}", id))
+    }
+
+    /// Nombre del aula (edificio + numero), vacio si la clase no tiene aula
+    pub fn classroom_label(&self, id: i16) -> String {
+        self.classroom(id)
+            .map(|c| {
+                format!(
+                    "{}{}",
+                    c.building_id.clone().unwrap_or_default(),
+                    c.building_number
+                )
+            })
+            .unwrap_or_default()
+    }
+
+    /// Materias registradas para el grupo (tabla `groups_subjects`)
+    pub fn subjects_for_group(&self, group_id: i16) -> Vec<&Subject> {
+        self.group_subjects
+            .iter()
+            .filter(|gs| gs.group_id == group_id)
+            .filter_map(|gs| self.subject(gs.subject_id))
+            .collect()
+    }
+
+    /// Profesores que pueden impartir la materia (tabla `teacher_subjects`)
+    pub fn teachers_for_subject(&self, subject_id: i16) -> Vec<&Teacher> {
+        self.teacher_subjects
+            .iter()
+            .filter(|(_, sid)| *sid == subject_id)
+            .filter_map(|(tid, _)| self.teacher(*tid))
+            .collect()
+    }
+
+    pub fn is_qualified(&self, teacher_id: i16, subject_id: i16) -> bool {
+        self.teacher_subjects
+            .iter()
+            .any(|&(tid, sid)| tid == teacher_id && sid == subject_id)
+    }
+}
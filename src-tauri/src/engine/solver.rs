@@ -0,0 +1,641 @@
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::time::{Duration, Instant};
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::model::{Assignment, Group, Subject};
+
+use super::rng::SeededRng;
+use super::score::{score_schedule, ScheduleScore};
+use super::snapshot::Snapshot;
+use super::validate::{validate_schedule, Violation};
+
+// Maximo de modulos consecutivos para una materia (limita el tamano de bloque)
+const MAX_CONSECUTIVE_MODULES: i16 = 2;
+// Materias con al menos estos modulos se consideran criticas (Matematicas, Español, Ciencias)
+const CRITICAL_MODULES: i16 = 4;
+// Modulos que se asumen cuando la materia no los tiene registrados
+pub const DEFAULT_REQUIRED_MODULES: i16 = 2;
+
+/// Opciones de la generacion
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GenerateOptions {
+    /// Semilla para el orden de grupos y dias y para desempatar profesores
+    /// `None` conserva el orden por grado y letra, y en empate gana el primer profesor
+    /// La misma entrada con la misma semilla siempre produce el mismo horario
+    pub seed: Option<u64>,
+    /// Tiempo maximo en milisegundos, al agotarse ya no se colocan mas bloques
+    pub time_budget_ms: Option<u64>,
+    /// Numero de intentos con semillas distintas, se conserva el de mejor puntaje
+    /// 0 o 1 ejecuta un solo intento
+    #[serde(default)]
+    pub restarts: u32,
+    /// Hilos para ejecutar los intentos en paralelo
+    /// `None` usa todos los nucleos disponibles
+    #[serde(default)]
+    pub threads: Option<usize>,
+}
+
+/// Materia que no se pudo completar para un grupo
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissingModules {
+    pub group_id: i16,
+    pub subject_id: i16,
+    pub required: i16,
+    pub assigned: i16,
+}
+
+/// Resultado de la generacion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerationReport {
+    pub assignments: Vec<Assignment>,
+    pub missing: Vec<MissingModules>,
+    pub score: ScheduleScore,
+    pub violations: Vec<Violation>,
+    pub timed_out: bool,
+    /// Semilla del intento elegido (para reproducirlo)
+    pub seed: Option<u64>,
+    /// Intentos que se alcanzaron a ejecutar
+    pub attempts: u32,
+    /// Bloques que el intento elegido no pudo colocar
+    /// Los intentos corren en paralelo, por eso no se imprimen mientras se generan
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+/// Punto de partida de cada intento
+/// El valor por defecto genera todo el horario desde cero
+#[derive(Debug, Clone, Default)]
+pub struct Plan {
+    /// Asignaciones fijas que el solver no mueve
+    pub base: Vec<Assignment>,
+    /// Pares (grupo, materia) que se deben completar, `None` = todos
+    pub pairs: Option<BTreeSet<(i16, i16)>>,
+    /// Dias donde se pueden colocar bloques nuevos, `None` = todos
+    pub days: Option<Vec<String>>,
+}
+
+/// Funcion principal del motor: genera un horario completo a partir del snapshot
+/// Las asignaciones que ya tenga el snapshot se ignoran (se genera desde cero)
+/// Los indices de modulo van de 0 a `modules_per_day - 1`, igual que en la base de datos
+/// Los intentos se reparten entre los nucleos con rayon; es una funcion bloqueante,
+/// desde codigo async se debe llamar con `spawn_blocking`
+pub fn generate(snapshot: &Snapshot, options: &GenerateOptions) -> GenerationReport {
+    generate_with_plan(snapshot, &Plan::default(), options)
+}
+
+/// Igual que `generate` pero partiendo de un plan (asignaciones fijas y materias a completar)
+pub fn generate_with_plan(
+    snapshot: &Snapshot,
+    plan: &Plan,
+    options: &GenerateOptions,
+) -> GenerationReport {
+    match options.threads {
+        Some(threads) => match rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+            Ok(pool) => pool.install(|| generate_parallel(snapshot, plan, options)),
+            Err(e) => {
+                eprintln!("Could not build thread pool ({}), using the global one", e);
+                generate_parallel(snapshot, plan, options)
+            }
+        },
+        None => generate_parallel(snapshot, plan, options),
+    }
+}
+
+fn generate_parallel(
+    snapshot: &Snapshot,
+    plan: &Plan,
+    options: &GenerateOptions,
+) -> GenerationReport {
+    let deadline = options
+        .time_budget_ms
+        .map(|ms| Instant::now() + Duration::from_millis(ms));
+
+    let reports: Vec<Option<GenerationReport>> = attempt_seeds(options)
+        .into_par_iter()
+        .enumerate()
+        .map(|(i, seed)| {
+            // El primer intento siempre se ejecuta, los demas solo si queda tiempo
+            if i > 0 && deadline.is_some_and(|d| Instant::now() >= d) {
+                return None;
+            }
+            Some(run_attempt(snapshot, plan, seed, deadline))
+        })
+        .collect();
+
+    // Se elige en orden de intento para que el resultado no dependa de los hilos
+    let attempts = reports.iter().flatten().count() as u32;
+    let mut best = reports
+        .into_iter()
+        .flatten()
+        .reduce(|best, report| {
+            if is_better(&report, &best) {
+                report
+            } else {
+                best
+            }
+        })
+        .expect("at least one attempt is always executed");
+
+    best.attempts = attempts;
+    best
+}
+
+/// Semillas de cada intento
+/// El primero usa la semilla pedida (o ninguna), los demas se derivan de ella
+pub fn attempt_seeds(options: &GenerateOptions) -> Vec<Option<u64>> {
+    let mut seeds = vec![options.seed];
+    let mut rng = SeededRng::new(options.seed.unwrap_or_default());
+    for _ in 1..options.restarts.max(1) {
+        seeds.push(Some(rng.next_u64()));
+    }
+    seeds
+}
+
+/// Ejecuta un solo intento con la semilla dada
+pub fn run_attempt(
+    snapshot: &Snapshot,
+    plan: &Plan,
+    seed: Option<u64>,
+    deadline: Option<Instant>,
+) -> GenerationReport {
+    let mut solver = Solver::new(snapshot, plan, seed, deadline);
+    solver.run();
+    solver.into_report()
+}
+
+/// Compara dos resultados: gana el de mayor puntaje y, en empate, el de menos violaciones
+/// En empate total se conserva el anterior para que el resultado no dependa del orden
+pub fn is_better(candidate: &GenerationReport, current: &GenerationReport) -> bool {
+    match candidate.score.total.cmp(&current.score.total) {
+        Ordering::Greater => true,
+        Ordering::Less => false,
+        Ordering::Equal => candidate.violations.len() < current.violations.len(),
+    }
+}
+
+struct Solver<'a> {
+    snapshot: &'a Snapshot,
+    plan: &'a Plan,
+    schedule: Vec<Assignment>,
+    seed: Option<u64>,
+    rng: Option<SeededRng>,
+    deadline: Option<Instant>,
+    timed_out: bool,
+    warnings: Vec<String>,
+}
+
+impl<'a> Solver<'a> {
+    fn new(
+        snapshot: &'a Snapshot,
+        plan: &'a Plan,
+        seed: Option<u64>,
+        deadline: Option<Instant>,
+    ) -> Self {
+        Solver {
+            snapshot,
+            plan,
+            schedule: plan.base.clone(),
+            seed,
+            rng: seed.map(SeededRng::new),
+            deadline,
+            timed_out: false,
+            warnings: Vec::new(),
+        }
+    }
+
+    // Tres fases:
+    // 1. Reserva un bloque de cada materia critica para todos los grupos
+    // 2. Completa las materias de alta prioridad (mas de 2 modulos)
+    // 3. Completa el resto de materias grupo por grupo
+    fn run(&mut self) {
+        let snapshot = self.snapshot;
+        let mut groups = sorted_groups(snapshot);
+        if let Some(rng) = self.rng.as_mut() {
+            rng.shuffle(&mut groups);
+        }
+
+        let mut all_group_subjects: Vec<(&Group, &Subject)> = Vec::new();
+        for &group in &groups {
+            for subject in self.subjects_to_place(group) {
+                all_group_subjects.push((group, subject));
+            }
+        }
+
+        // Fase 1: materias criticas
+        for &(group, subject) in &all_group_subjects {
+            if required_modules(subject) < CRITICAL_MODULES || self.assigned(group, subject) > 0 {
+                continue;
+            }
+
+            if !self.place_block(group, subject, MAX_CONSECUTIVE_MODULES) {
+                self.warnings.push(format!(
+                    "Could not reserve {} for group {} {}",
+                    subject.name, group.grade, group.group
+                ));
+            }
+        }
+
+        // Fase 2: materias de alta prioridad
+        for &(group, subject) in &all_group_subjects {
+            if required_modules(subject) <= MAX_CONSECUTIVE_MODULES {
+                continue;
+            }
+            self.complete_subject(group, subject);
+        }
+
+        // Fase 3: el resto, grupo por grupo
+        for &group in &groups {
+            let mut group_subjects = self.subjects_to_place(group);
+            group_subjects.sort_by(|a, b| required_modules(b).cmp(&required_modules(a)));
+
+            for subject in group_subjects {
+                self.complete_subject(group, subject);
+            }
+        }
+    }
+
+    // Materias del grupo que le toca colocar a este intento (todas o las del plan)
+    fn subjects_to_place(&self, group: &Group) -> Vec<&'a Subject> {
+        let group_id = group.id.unwrap_or_default();
+        self.snapshot
+            .subjects_for_group(group_id)
+            .into_iter()
+            .filter(|s| match &self.plan.pairs {
+                Some(pairs) => pairs.contains(&(group_id, s.id.unwrap_or_default())),
+                None => true,
+            })
+            .collect()
+    }
+
+    // Asigna los modulos restantes de la materia en bloques
+    // Se detiene en el primer bloque que no se pueda colocar
+    fn complete_subject(&mut self, group: &Group, subject: &Subject) -> bool {
+        let remaining = required_modules(subject) - self.assigned(group, subject);
+        if remaining <= 0 {
+            return true;
+        }
+
+        for block_size in get_optimal_module_splits(remaining) {
+            if !self.place_block(group, subject, block_size) {
+                self.warnings.push(format!(
+                    "Could not assign block of size {} for {} in group {} {}",
+                    block_size, subject.name, group.grade, group.group
+                ));
+                return false;
+            }
+        }
+
+        true
+    }
+
+    // Busca el primer dia/modulo libre para el grupo con un profesor disponible
+    fn place_block(&mut self, group: &Group, subject: &Subject, block_size: i16) -> bool {
+        let snapshot = self.snapshot;
+        let (Some(group_id), Some(subject_id)) = (group.id, subject.id) else {
+            return false;
+        };
+
+        if self.out_of_time() {
+            return false;
+        }
+
+        let plan = self.plan;
+        let mut days: Vec<&String> = snapshot
+            .days
+            .iter()
+            .filter(|d| {
+                plan.days
+                    .as_ref()
+                    .map_or(true, |allowed| allowed.contains(d))
+            })
+            .collect();
+        if let Some(rng) = self.rng.as_mut() {
+            rng.shuffle(&mut days);
+        }
+
+        for day in days {
+            for starting_module in 0..=(snapshot.modules_per_day - block_size) {
+                if !is_schedule_available(
+                    &self.schedule,
+                    group_id,
+                    day,
+                    starting_module,
+                    block_size,
+                ) {
+                    continue;
+                }
+
+                if let Some(teacher_id) =
+                    self.find_best_teacher(subject_id, day, starting_module, block_size)
+                {
+                    for offset in 0..block_size {
+                        self.schedule.push(Assignment {
+                            id: None,
+                            group_id,
+                            day: day.clone(),
+                            module_index: starting_module + offset,
+                            subject_id,
+                            teacher_id,
+                            classroom_id: 0,
+                            subject_name: subject.name.clone(),
+                            subject_shorten: subject.shorten.clone(),
+                            subject_color: subject.color.clone(),
+                        });
+                    }
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    // Elige al profesor calificado con mejor puntaje para el bloque
+    // En empate se queda con el primero registrado, o con uno al azar si hay semilla
+    fn find_best_teacher(
+        &mut self,
+        subject_id: i16,
+        day: &str,
+        starting_module: i16,
+        block_size: i16,
+    ) -> Option<i16> {
+        let mut best: Vec<i16> = Vec::new();
+        let mut best_score = i32::MIN;
+
+        for teacher in self.snapshot.teachers_for_subject(subject_id) {
+            let Some(teacher_id) = teacher.id else {
+                continue;
+            };
+
+            if !is_teacher_available(&self.schedule, teacher_id, day, starting_module, block_size) {
+                continue;
+            }
+
+            let score = teacher_score(&self.schedule, teacher_id, day, starting_module, block_size);
+            match score.cmp(&best_score) {
+                Ordering::Greater => {
+                    best_score = score;
+                    best = vec![teacher_id];
+                }
+                Ordering::Equal => best.push(teacher_id),
+                Ordering::Less => {}
+            }
+        }
+
+        match (self.rng.as_mut(), best.len()) {
+            (_, 0) => None,
+            (Some(rng), n) if n > 1 => Some(best[rng.below(n)]),
+            _ => Some(best[0]),
+        }
+    }
+
+    fn assigned(&self, group: &Group, subject: &Subject) -> i16 {
+        self.schedule
+            .iter()
+            .filter(|a| Some(a.group_id) == group.id && Some(a.subject_id) == subject.id)
+            .count() as i16
+    }
+
+    fn out_of_time(&mut self) -> bool {
+        if !self.timed_out && self.deadline.is_some_and(|d| Instant::now() >= d) {
+            self.timed_out = true;
+        }
+        self.timed_out
+    }
+
+    fn into_report(self) -> GenerationReport {
+        let mut report = build_report(self.snapshot, self.schedule);
+        report.timed_out = self.timed_out;
+        report.seed = self.seed;
+        report.warnings = self.warnings;
+        report
+    }
+}
+
+/// Arma el reporte (faltantes, puntaje y violaciones) de un horario ya calculado
+pub fn build_report(snapshot: &Snapshot, schedule: Vec<Assignment>) -> GenerationReport {
+    let mut missing = Vec::new();
+    for group in &snapshot.groups {
+        let Some(group_id) = group.id else {
+            continue;
+        };
+        for subject in snapshot.subjects_for_group(group_id) {
+            let required = required_modules(subject);
+            let assigned = schedule
+                .iter()
+                .filter(|a| a.group_id == group_id && Some(a.subject_id) == subject.id)
+                .count() as i16;
+
+            if assigned < required {
+                missing.push(MissingModules {
+                    group_id,
+                    subject_id: subject.id.unwrap_or_default(),
+                    required,
+                    assigned,
+                });
+            }
+        }
+    }
+
+    let score = score_schedule(snapshot, &schedule);
+    let violations = validate_schedule(snapshot, &schedule);
+
+    GenerationReport {
+        assignments: schedule,
+        missing,
+        score,
+        violations,
+        timed_out: false,
+        seed: None,
+        attempts: 1,
+        warnings: Vec::new(),
+    }
+}
+
+pub fn required_modules(subject: &Subject) -> i16 {
+    subject.required_modules.unwrap_or(DEFAULT_REQUIRED_MODULES)
+}
+
+// Ordena los grupos por grado y luego por letra
+fn sorted_groups(snapshot: &Snapshot) -> Vec<&Group> {
+    let mut groups: Vec<&Group> = snapshot.groups.iter().filter(|g| g.id.is_some()).collect();
+    groups.sort_by(|a, b| match a.grade.cmp(&b.grade) {
+        Ordering::Equal => a.group.cmp(&b.group),
+        other => other,
+    });
+    groups
+}
+
+// Divide los modulos requeridos en bloques (prefiere bloques de 1-2 modulos)
+fn get_optimal_module_splits(required_modules: i16) -> Vec<i16> {
+    let mut module_blocks = Vec::new();
+    let mut remaining_modules = required_modules;
+
+    while remaining_modules > 0 {
+        let block = remaining_modules.min(MAX_CONSECUTIVE_MODULES);
+        module_blocks.push(block);
+        remaining_modules -= block;
+    }
+
+    module_blocks
+}
+
+// Checa que el grupo este libre en el bloque y que no se creen modulos muertos
+fn is_schedule_available(
+    schedule: &[Assignment],
+    group_id: i16,
+    day: &str,
+    starting_module: i16,
+    block_size: i16,
+) -> bool {
+    let group_modules_today: Vec<i16> = schedule
+        .iter()
+        .filter(|a| a.group_id == group_id && a.day == day)
+        .map(|a| a.module_index)
+        .collect();
+
+    let occupied = (starting_module..starting_module + block_size)
+        .any(|module| group_modules_today.contains(&module));
+    if occupied {
+        return false;
+    }
+
+    // Evita dejar un hueco entre la ultima clase del grupo y el nuevo bloque
+    let would_create_isolated_module = starting_module > 0
+        && !group_modules_today.contains(&(starting_module - 1))
+        && group_modules_today.iter().any(|&m| m < starting_module - 1);
+
+    !would_create_isolated_module
+}
+
+// Checa que el profesor no tenga clase en ninguno de los modulos del bloque
+fn is_teacher_available(
+    schedule: &[Assignment],
+    teacher_id: i16,
+    day: &str,
+    starting_module: i16,
+    block_size: i16,
+) -> bool {
+    !schedule.iter().any(|a| {
+        a.teacher_id == teacher_id
+            && a.day == day
+            && a.module_index >= starting_module
+            && a.module_index < starting_module + block_size
+    })
+}
+
+// Puntaje de un profesor para el bloque:
+// menos carga es mejor, y se prefiere que ya tenga clases ese dia y pegadas al bloque
+fn teacher_score(
+    schedule: &[Assignment],
+    teacher_id: i16,
+    day: &str,
+    starting_module: i16,
+    block_size: i16,
+) -> i32 {
+    let mut score = 0;
+
+    let assigned_modules = schedule
+        .iter()
+        .filter(|a| a.teacher_id == teacher_id)
+        .count();
+    score -= assigned_modules as i32 * 10;
+
+    let already_teaching_today = schedule
+        .iter()
+        .any(|a| a.teacher_id == teacher_id && a.day == day);
+    if already_teaching_today {
+        score += 50;
+    }
+
+    let has_adjacent_block = schedule.iter().any(|a| {
+        a.teacher_id == teacher_id
+            && a.day == day
+            && (a.module_index == starting_module - 1
+                || a.module_index == starting_module + block_size)
+    });
+    if has_adjacent_block {
+        score += 100;
+    }
+
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::testing::{classroom, group, subject, teacher};
+    use crate::model::GroupSubjects;
+
+    fn snapshot() -> Snapshot {
+        let subjects = vec![
+            subject(1, "Matematicas", 5),
+            subject(2, "Español", 4),
+            subject(3, "Historia", 3),
+            subject(4, "Arte", 2),
+        ];
+        let groups = vec![group(1, 1, "A"), group(2, 1, "B"), group(3, 2, "A")];
+        let group_subjects = groups
+            .iter()
+            .flat_map(|g| {
+                subjects.iter().map(|s| GroupSubjects {
+                    group_id: g.id.unwrap(),
+                    subject_id: s.id.unwrap(),
+                })
+            })
+            .collect();
+
+        Snapshot {
+            subjects,
+            teachers: vec![teacher(1, "Ana"), teacher(2, "Luis"), teacher(3, "Eva")],
+            teacher_subjects: vec![(1, 1), (1, 3), (2, 1), (2, 2), (3, 2), (3, 3), (3, 4)],
+            groups,
+            group_subjects,
+            classrooms: vec![classroom(1, 101)],
+            ..Snapshot::default()
+        }
+    }
+
+    fn cells(report: &GenerationReport) -> Vec<(i16, String, i16, i16, i16, i16)> {
+        let mut cells: Vec<_> = report
+            .assignments
+            .iter()
+            .map(|a| {
+                (
+                    a.group_id,
+                    a.day.clone(),
+                    a.module_index,
+                    a.subject_id,
+                    a.teacher_id,
+                    a.classroom_id,
+                )
+            })
+            .collect();
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn same_seed_gives_same_timetable_with_any_threads() {
+        let snapshot = snapshot();
+        let options = |threads| GenerateOptions {
+            seed: Some(42),
+            restarts: 8,
+            threads: Some(threads),
+            ..GenerateOptions::default()
+        };
+
+        let single = generate(&snapshot, &options(1));
+        let parallel = generate(&snapshot, &options(4));
+        let again = generate(&snapshot, &options(4));
+
+        assert!(!single.assignments.is_empty());
+        assert_eq!(cells(&single), cells(&parallel));
+        assert_eq!(cells(&parallel), cells(&again));
+        assert_eq!(single.seed, parallel.seed);
+        assert_eq!(single.attempts, 8);
+    }
+}
@@ -0,0 +1,141 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::model::Assignment;
+
+use super::availability::free_teachers;
+use super::calendar::{dates_between, format_date, parse_date, school_day};
+use super::diff::Lesson;
+use super::overrides::{effective_week, DateChanges, CLOSE_GROUP};
+use super::snapshot::Snapshot;
+
+/// Ausencia de un profesor entre dos fechas ("AAAA-MM-DD", las dos incluidas)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Absence {
+    pub id: i64,
+    pub teacher_id: i16,
+    pub start_date: String,
+    pub end_date: String,
+    pub reason: Option<String>,
+    pub created_at: i64,
+}
+
+impl Absence {
+    /// Funcion que dice si el profesor falta en una fecha
+    pub fn covers(&self, date: &str) -> bool {
+        self.start_date.as_str() <= date && date <= self.end_date.as_str()
+    }
+}
+
+/// Profesor propuesto para cubrir una clase
+/// `qualified` indica si esta registrado para la materia y `new_gaps` los huecos que
+/// se le agregan ese dia
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Substitute {
+    pub teacher_id: i16,
+    pub name: String,
+    pub qualified: bool,
+    pub load: i64,
+    pub new_gaps: i64,
+}
+
+/// Clase del profesor ausente que hay que cubrir en una fecha
+/// `covered_by` es el suplente ya aceptado y `cancelled` si la clase no se da ese dia
+/// (se cancelo o el grupo no tiene clases)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverNeed {
+    pub date: String,
+    pub lesson: Lesson,
+    pub override_id: Option<i64>,
+    pub covered_by: Option<i16>,
+    pub cancelled: bool,
+    pub candidates: Vec<Substitute>,
+}
+
+/// Funcion para armar las clases a cubrir de una ausencia con sus suplentes propuestos
+/// # Argumentos
+/// * `snapshot` - Datos de la escuela (con el horario base en `assignments`)
+/// * `absence` - Ausencia a cubrir
+/// * `absences` - Todas las ausencias, para no proponer a otro profesor que tambien falta
+/// * `changes` - Cambios por fecha ya guardados
+/// Los suplentes se ordenan: primero los que pueden dar la materia, despues por menor carga
+/// y al final por los huecos que se les agregan
+pub fn plan_cover(
+    snapshot: &Snapshot,
+    absence: &Absence,
+    absences: &[Absence],
+    changes: &DateChanges,
+) -> Result<Vec<CoverNeed>, String> {
+    let start = parse_date(&absence.start_date)?;
+    let end = parse_date(&absence.end_date)?;
+
+    let mut needs = Vec::new();
+    for date in dates_between(start, end) {
+        let Some(day) = school_day(&snapshot.days, date) else {
+            continue;
+        };
+        let date_text = format_date(date);
+
+        let mut lessons: Vec<&Assignment> = snapshot
+            .assignments
+            .iter()
+            .filter(|a| a.teacher_id == absence.teacher_id && a.day == day)
+            .collect();
+        lessons.sort_by_key(|a| (a.module_index, a.group_id));
+
+        for a in lessons {
+            let current = changes.cell(&date_text, a.group_id, a.module_index);
+
+            needs.push(CoverNeed {
+                date: date_text.clone(),
+                lesson: Lesson::new(snapshot, a),
+                override_id: current.map(|o| o.id),
+                covered_by: current.and_then(|o| o.teacher_id),
+                cancelled: current.is_some_and(|o| o.teacher_id.is_none())
+                    || changes
+                        .closure(CLOSE_GROUP, a.group_id, &date_text, a.module_index)
+                        .is_some(),
+                candidates: substitutes_for(snapshot, a, date, absences, changes),
+            });
+        }
+    }
+
+    Ok(needs)
+}
+
+/// Funcion para proponer suplentes para una clase del horario base en una fecha
+/// No toma en cuenta el cambio que ya tenga esa misma celda, asi el suplente aceptado
+/// sigue apareciendo como opcion
+pub fn substitutes_for(
+    snapshot: &Snapshot,
+    lesson: &Assignment,
+    date: NaiveDate,
+    absences: &[Absence],
+    changes: &DateChanges,
+) -> Vec<Substitute> {
+    let date_text = format_date(date);
+    let others = changes.without_cell(&date_text, lesson.group_id, lesson.module_index);
+    let week = effective_week(snapshot, &snapshot.assignments, date, &others);
+
+    let mut candidates: Vec<Substitute> =
+        free_teachers(snapshot, &week, &lesson.day, lesson.module_index, 1, None)
+            .into_iter()
+            .filter(|t| t.teacher_id != lesson.teacher_id)
+            .filter(|t| {
+                !absences
+                    .iter()
+                    .any(|a| a.teacher_id == t.teacher_id && a.covers(&date_text))
+            })
+            .map(|t| Substitute {
+                qualified: snapshot.is_qualified(t.teacher_id, lesson.subject_id),
+                teacher_id: t.teacher_id,
+                name: t.name,
+                load: t.load,
+                new_gaps: t.new_gaps,
+            })
+            .collect();
+
+    candidates.sort_by_key(|s| (!s.qualified, s.load, s.new_gaps, s.teacher_id));
+    candidates
+}
@@ -0,0 +1,77 @@
+// Constructores de datos para las pruebas del motor
+// Solo llenan lo que las pruebas revisan, el resto queda vacio
+
+use crate::model::{Assignment, Classroom, Group, Subject, Teacher};
+
+pub fn subject(id: i16, name: &str, modules: i16) -> Subject {
+    Subject {
+        id: Some(id),
+        name: name.to_string(),
+        shorten: name[..3].to_uppercase(),
+        color: "#000".to_string(),
+        spec: None,
+        required_modules: Some(modules),
+        priority: None,
+    }
+}
+
+pub fn teacher(id: i16, name: &str) -> Teacher {
+    Teacher {
+        id: Some(id),
+        name: name.to_string(),
+        father_lastname: "Perez".to_string(),
+        mother_lastname: None,
+        email: None,
+        phone: None,
+        degree: None,
+        commisioned_hours: None,
+        active_hours: None,
+        performance: None,
+        preferred_days: None,
+        preferred_modules: None,
+    }
+}
+
+pub fn group(id: i16, grade: i16, letter: &str) -> Group {
+    Group {
+        id: Some(id),
+        grade,
+        group: letter.to_string(),
+        career: None,
+        students: None,
+        max_modules_per_day: None,
+    }
+}
+
+pub fn classroom(id: i16, building_number: i16) -> Classroom {
+    Classroom {
+        id: Some(id),
+        building_id: Some("A".to_string()),
+        building_number,
+        building_type: None,
+        capacity: None,
+        availability: None,
+    }
+}
+
+// Clase sin aula
+pub fn lesson(
+    group_id: i16,
+    day: &str,
+    module_index: i16,
+    subject_id: i16,
+    teacher_id: i16,
+) -> Assignment {
+    Assignment {
+        id: None,
+        group_id,
+        day: day.to_string(),
+        module_index,
+        subject_id,
+        teacher_id,
+        classroom_id: 0,
+        subject_name: String::new(),
+        subject_shorten: String::new(),
+        subject_color: String::new(),
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::Assignment;
+
+use super::snapshot::Snapshot;
+use super::solver::required_modules;
+
+/// Gravedad de una violacion
+/// `Hard` hace invalido el horario, `Soft` solo lo hace menos deseable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Hard,
+    Soft,
+}
+
+/// Tipo de restriccion que se rompio
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationKind {
+    TeacherDoubleBooked,
+    GroupDoubleBooked,
+    TeacherNotQualified,
+    SubjectUnderRequired,
+    SubjectOverRequired,
+    TeacherOverHours,
+    RoomDoubleBooked,
+    RoomOverCapacity,
+    GroupOverDailyCap,
+}
+
+impl ViolationKind {
+    pub fn severity(&self) -> Severity {
+        match self {
+            ViolationKind::TeacherDoubleBooked
+            | ViolationKind::GroupDoubleBooked
+            | ViolationKind::TeacherNotQualified
+            | ViolationKind::RoomDoubleBooked => Severity::Hard,
+            ViolationKind::SubjectUnderRequired
+            | ViolationKind::SubjectOverRequired
+            | ViolationKind::TeacherOverHours
+            | ViolationKind::RoomOverCapacity
+            | ViolationKind::GroupOverDailyCap => Severity::Soft,
+        }
+    }
+}
+
+/// Violacion de una restriccion dentro del horario
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Violation {
+    pub kind: ViolationKind,
+    pub severity: Severity,
+    pub message: String,
+    pub day: Option<String>,
+    pub module_index: Option<i16>,
+    pub group_id: Option<i16>,
+    pub teacher_id: Option<i16>,
+    pub subject_id: Option<i16>,
+    pub classroom_id: Option<i16>,
+    // Que tan grave es (clases en el mismo modulo, modulos de mas o de menos, alumnos),
+    // sirve para saber si un cambio empeora una violacion que ya existia
+    #[serde(skip)]
+    amount: i16,
+}
+
+impl Violation {
+    fn new(kind: ViolationKind, message: String) -> Self {
+        Violation {
+            kind,
+            severity: kind.severity(),
+            message,
+            day: None,
+            module_index: None,
+            group_id: None,
+            teacher_id: None,
+            subject_id: None,
+            classroom_id: None,
+            amount: 1,
+        }
+    }
+
+    fn at(mut self, day: &str, module_index: i16) -> Self {
+        self.day = Some(day.to_string());
+        self.module_index = Some(module_index);
+        self
+    }
+
+    fn with_amount(mut self, amount: i16) -> Self {
+        self.amount = amount;
+        self
+    }
+
+    // Misma restriccion en el mismo lugar (sin importar el mensaje)
+    fn same_place(&self, other: &Violation) -> bool {
+        self.kind == other.kind
+            && self.day == other.day
+            && self.module_index == other.module_index
+            && self.group_id == other.group_id
+            && self.teacher_id == other.teacher_id
+            && self.subject_id == other.subject_id
+            && self.classroom_id == other.classroom_id
+    }
+}
+
+/// Funcion para validar un horario completo contra los datos del snapshot
+/// Retorna todas las violaciones encontradas (vacio si el horario es valido)
+pub fn validate_schedule(snapshot: &Snapshot, schedule: &[Assignment]) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    // Profesor con dos clases en el mismo modulo
+    let mut by_teacher_slot: BTreeMap<(i16, &str, i16), Vec<&Assignment>> = BTreeMap::new();
+    // Grupo con dos clases en el mismo modulo
+    let mut by_group_slot: BTreeMap<(i16, &str, i16), Vec<&Assignment>> = BTreeMap::new();
+    // Aula con dos clases en el mismo modulo (0 es sin aula)
+    let mut by_room_slot: BTreeMap<(i16, &str, i16), Vec<&Assignment>> = BTreeMap::new();
+    // Modulos de cada grupo por dia
+    let mut by_group_day: BTreeMap<(i16, &str), i16> = BTreeMap::new();
+    // Grupos que usan cada aula
+    let mut room_groups: BTreeSet<(i16, i16)> = BTreeSet::new();
+
+    for a in schedule {
+        by_teacher_slot
+            .entry((a.teacher_id, a.day.as_str(), a.module_index))
+            .or_default()
+            .push(a);
+        by_group_slot
+            .entry((a.group_id, a.day.as_str(), a.module_index))
+            .or_default()
+            .push(a);
+        *by_group_day
+            .entry((a.group_id, a.day.as_str()))
+            .or_default() += 1;
+        if a.classroom_id != 0 {
+            by_room_slot
+                .entry((a.classroom_id, a.day.as_str(), a.module_index))
+                .or_default()
+                .push(a);
+            room_groups.insert((a.classroom_id, a.group_id));
+        }
+
+        if !snapshot.is_qualified(a.teacher_id, a.subject_id) {
+            let mut v = Violation::new(
+                ViolationKind::TeacherNotQualified,
+                format!(
+                    "Teacher {} is not registered for subject {}",
+                    snapshot.teacher_label(a.teacher_id),
+                    snapshot.subject_label(a.subject_id)
+                ),
+            )
+            .at(&a.day, a.module_index);
+            v.group_id = Some(a.group_id);
+            v.teacher_id = Some(a.teacher_id);
+            v.subject_id = Some(a.subject_id);
+            violations.push(v);
+        }
+    }
+
+    for ((teacher_id, day, module_index), list) in by_teacher_slot {
+        if list.len() > 1 {
+            let mut v = Violation::new(
+                ViolationKind::TeacherDoubleBooked,
+                format!(
+                    "Teacher {} has {} lessons on {} module {}",
+                    snapshot.teacher_label(teacher_id),
+                    list.len(),
+                    day,
+                    module_index + 1
+                ),
+            )
+            .at(day, module_index)
+            .with_amount(list.len() as i16);
+            v.teacher_id = Some(teacher_id);
+            violations.push(v);
+        }
+    }
+
+    for ((group_id, day, module_index), list) in by_group_slot {
+        if list.len() > 1 {
+            let mut v = Violation::new(
+                ViolationKind::GroupDoubleBooked,
+                format!(
+                    "Group {} has {} lessons on {} module {}",
+                    snapshot.group_label(group_id),
+                    list.len(),
+                    day,
+                    module_index + 1
+                ),
+            )
+            .at(day, module_index)
+            .with_amount(list.len() as i16);
+            v.group_id = Some(group_id);
+            violations.push(v);
+        }
+    }
+
+    for ((classroom_id, day, module_index), list) in by_room_slot {
+        if list.len() > 1 {
+            let groups: Vec<String> = list
+                .iter()
+                .map(|a| snapshot.group_label(a.group_id))
+                .collect();
+            let mut v = Violation::new(
+                ViolationKind::RoomDoubleBooked,
+                format!(
+                    "Classroom {} is used by {} on {} module {}",
+                    snapshot.classroom_label(classroom_id),
+                    groups.join(", "),
+                    day,
+                    module_index + 1
+                ),
+            )
+            .at(day, module_index)
+            .with_amount(list.len() as i16);
+            v.classroom_id = Some(classroom_id);
+            violations.push(v);
+        }
+    }
+
+    // Grupos con mas alumnos que la capacidad del aula
+    for (classroom_id, group_id) in room_groups {
+        let capacity = snapshot.classroom(classroom_id).and_then(|c| c.capacity);
+        let students = snapshot.group(group_id).and_then(|g| g.students);
+        let (Some(capacity), Some(students)) = (capacity, students) else {
+            continue;
+        };
+        if capacity > 0 && students > capacity {
+            let mut v = Violation::new(
+                ViolationKind::RoomOverCapacity,
+                format!(
+                    "Group {} has {} students but classroom {} only fits {}",
+                    snapshot.group_label(group_id),
+                    students,
+                    snapshot.classroom_label(classroom_id),
+                    capacity
+                ),
+            )
+            .with_amount(students - capacity);
+            v.group_id = Some(group_id);
+            v.classroom_id = Some(classroom_id);
+            violations.push(v);
+        }
+    }
+
+    // Modulos del grupo por dia contra `max_modules_per_day`
+    for ((group_id, day), count) in by_group_day {
+        let Some(max) = snapshot.group(group_id).and_then(|g| g.max_modules_per_day) else {
+            continue;
+        };
+        if max > 0 && count > max {
+            let mut v = Violation::new(
+                ViolationKind::GroupOverDailyCap,
+                format!(
+                    "Group {} has {} modules on {} (maximum {})",
+                    snapshot.group_label(group_id),
+                    count,
+                    day,
+                    max
+                ),
+            )
+            .with_amount(count - max);
+            v.day = Some(day.to_string());
+            v.group_id = Some(group_id);
+            violations.push(v);
+        }
+    }
+
+    // Modulos por materia contra `required_modules`
+    for gs in &snapshot.group_subjects {
+        let Some(subject) = snapshot.subject(gs.subject_id) else {
+            continue;
+        };
+        let required = required_modules(subject);
+        let assigned = schedule
+            .iter()
+            .filter(|a| a.group_id == gs.group_id && a.subject_id == gs.subject_id)
+            .count() as i16;
+
+        let kind = match assigned.cmp(&required) {
+            std::cmp::Ordering::Less => ViolationKind::SubjectUnderRequired,
+            std::cmp::Ordering::Greater => ViolationKind::SubjectOverRequired,
+            std::cmp::Ordering::Equal => continue,
+        };
+
+        let mut v = Violation::new(
+            kind,
+            format!(
+                "Group {} has {} of {} modules of {}",
+                snapshot.group_label(gs.group_id),
+                assigned,
+                required,
+                subject.name
+            ),
+        )
+        .with_amount((assigned - required).abs());
+        v.group_id = Some(gs.group_id);
+        v.subject_id = Some(gs.subject_id);
+        violations.push(v);
+    }
+
+    // Modulos por profesor contra sus horas comisionadas
+    for teacher in &snapshot.teachers {
+        let (Some(teacher_id), Some(hours)) = (teacher.id, teacher.commisioned_hours) else {
+            continue;
+        };
+        if hours <= 0 {
+            continue;
+        }
+        let assigned = schedule
+            .iter()
+            .filter(|a| a.teacher_id == teacher_id)
+            .count() as i16;
+        if assigned > hours {
+            let mut v = Violation::new(
+                ViolationKind::TeacherOverHours,
+                format!(
+                    "Teacher {} has {} modules but only {} commissioned hours",
+                    snapshot.teacher_label(teacher_id),
+                    assigned,
+                    hours
+                ),
+            )
+            .with_amount(assigned - hours);
+            v.teacher_id = Some(teacher_id);
+            violations.push(v);
+        }
+    }
+
+    violations
+}
+
+/// Funcion para validar un cambio al horario
+/// Compara las violaciones de antes y despues y retorna solo las que agrega o empeora el
+/// cambio (ej. un profesor que ya se pasaba de horas y recibe otra clase), asi una edicion
+/// no se bloquea por problemas que ya existian en otra parte del horario
+pub fn introduced_violations(
+    snapshot: &Snapshot,
+    before: &[Assignment],
+    after: &[Assignment],
+) -> Vec<Violation> {
+    let existing: Vec<Violation> = validate_schedule(snapshot, before);
+
+    validate_schedule(snapshot, after)
+        .into_iter()
+        .filter(|v| {
+            !existing
+                .iter()
+                .any(|e| e.same_place(v) && e.amount >= v.amount)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::testing::{group, lesson, subject, teacher};
+    use crate::model::GroupSubjects;
+
+    #[test]
+    fn edits_only_report_violations_they_introduce_or_worsen() {
+        // Ana tiene 1 hora comisionada y ya da Matematicas a 1A y 1B
+        let mut ana = teacher(1, "Ana");
+        ana.commisioned_hours = Some(1);
+        let snapshot = Snapshot {
+            subjects: vec![subject(1, "Matematicas", 1), subject(2, "Arte", 1)],
+            teachers: vec![ana, teacher(2, "Luis")],
+            teacher_subjects: vec![(1, 1), (1, 2), (2, 2)],
+            groups: vec![group(1, 1, "A"), group(2, 1, "B")],
+            group_subjects: [(1, 1), (1, 2), (2, 1)]
+                .iter()
+                .map(|&(group_id, subject_id)| GroupSubjects {
+                    group_id,
+                    subject_id,
+                })
+                .collect(),
+            classrooms: vec![],
+            assignments: vec![],
+            days: vec!["Lunes".to_string()],
+            modules_per_day: 3,
+        };
+        let before = vec![
+            lesson(1, "Lunes", 0, 1, 1),
+            lesson(1, "Lunes", 1, 2, 2),
+            lesson(2, "Lunes", 1, 1, 1),
+        ];
+        let over_hours = |violations: &[Violation]| {
+            violations
+                .iter()
+                .filter(|v| v.kind == ViolationKind::TeacherOverHours)
+                .count()
+        };
+        assert_eq!(over_hours(&validate_schedule(&snapshot, &before)), 1);
+
+        // Mover Arte de 1A a otro modulo no cambia las horas de Ana
+        let mut moved = before.clone();
+        moved[1].module_index = 2;
+        assert!(introduced_violations(&snapshot, &before, &moved).is_empty());
+
+        // Darle Arte de 1A a Ana la pasa de 2 a 3 modulos
+        let mut worse = moved.clone();
+        worse[1].teacher_id = 1;
+        let introduced = introduced_violations(&snapshot, &moved, &worse);
+        assert_eq!(introduced.len(), 1);
+        assert_eq!(introduced[0].kind, ViolationKind::TeacherOverHours);
+        assert_eq!(introduced[0].teacher_id, Some(1));
+    }
+}
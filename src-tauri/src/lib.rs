@@ -1,5 +1,14 @@
+pub mod engine;
+pub mod model;
+pub mod schedule_file;
+
+// La interfaz: comandos de tauri y base de datos
+#[cfg(feature = "gui")]
 pub mod class;
+#[cfg(feature = "gui")]
 pub mod db;
+#[cfg(feature = "gui")]
 pub mod util;
 
+#[cfg(feature = "gui")]
 pub use db::AppState;
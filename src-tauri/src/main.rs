@@ -5,6 +5,9 @@ mod class;
 mod db;
 mod util;
 
+// El motor y los tipos de datos vienen de la libreria, los comparte con school_roster-cli
+use school_roster::{engine, model, schedule_file};
+
 use crate::db::{connect, AppState};
 use std::process;
 use tauri::Manager as _; // Necesario para poder usar manage()
@@ -56,7 +59,53 @@ async fn main() {
             crate::util::assignments::get_assignment,
             crate::util::assignments::save_assignment,
             crate::util::assignments::delete_assignment,
+            crate::util::assignments::save_assignments,
+            crate::util::assignments::move_assignment,
+            crate::util::assignments::swap_assignments,
+            crate::util::history::undo,
+            crate::util::history::redo,
+            crate::util::history::get_history,
+            crate::util::audit::get_audit_log,
+            crate::util::audit::set_audit_user,
+            crate::util::audit::get_audit_user,
+            crate::util::conflicts::find_conflicts,
+            crate::util::availability::find_free_resources,
+            crate::util::availability::find_meeting_slots,
+            crate::util::absences::create_absence,
+            crate::util::absences::get_absences,
+            crate::util::absences::delete_absence,
+            crate::util::absences::plan_substitutes,
+            crate::util::absences::accept_substitution,
+            crate::util::absences::remove_substitution,
+            crate::util::calendar::create_term,
+            crate::util::calendar::get_terms,
+            crate::util::calendar::delete_term,
+            crate::util::terms::get_active_term,
+            crate::util::terms::set_active_term,
+            crate::util::terms::rollover_term,
+            crate::util::calendar::create_holiday,
+            crate::util::calendar::get_holidays,
+            crate::util::calendar::delete_holiday,
+            crate::util::calendar::get_dated_sessions,
+            crate::util::calendar::get_session_counts,
+            crate::util::overrides::set_schedule_override,
+            crate::util::overrides::get_date_changes,
+            crate::util::overrides::delete_schedule_override,
+            crate::util::overrides::create_resource_closure,
+            crate::util::overrides::delete_resource_closure,
+            crate::util::overrides::get_effective_timetable,
             crate::util::generate::generate_schedule,
+            crate::util::generate::regenerate_schedule,
+            crate::util::staging::preview_schedule,
+            crate::util::staging::get_staged_diff,
+            crate::util::staging::apply_staged_schedule,
+            crate::util::staging::discard_staged_schedule,
+            crate::util::versions::create_schedule_version,
+            crate::util::versions::get_schedule_versions,
+            crate::util::versions::restore_schedule_version,
+            crate::util::versions::delete_schedule_version,
+            crate::util::versions::compare_schedule_versions,
+            crate::util::versions::get_change_sheet,
             crate::util::settings::get_config,
             crate::util::settings::save_config,
             crate::util::settings::get_school_info,
@@ -69,6 +118,9 @@ async fn main() {
             crate::util::file_handler::export_file,
             crate::util::file_handler::import_file,
             crate::util::file_handler::delete_all_data,
+            crate::util::file_handler::pick_roster_file,
+            crate::util::file_handler::preview_merge_file,
+            crate::util::file_handler::merge_file,
             crate::util::ai::init_model,
             crate::util::ai::query_ai,
             crate::util::ai::check_api_key
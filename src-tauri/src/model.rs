@@ -0,0 +1,310 @@
+/*
+    Tipos de datos de la escuela: materias, profesores, grupos, alumnos, aulas, horario y
+    configuracion.
+    No dependen de tauri, asi el motor y school_roster-cli los usan sin la interfaz.
+    Los modulos de `class` y `util` los re-exportan junto con sus comandos.
+*/
+
+use serde::{Deserialize, Serialize};
+use sqlx::error::Error as SqlxError;
+use sqlx::{sqlite::SqliteRow, FromRow, Row};
+
+// Entidades que se registran en la bitacora (y que se cuentan al combinar archivos)
+pub const TEACHER: &str = "teacher";
+pub const SUBJECT: &str = "subject";
+pub const GROUP: &str = "group";
+pub const STUDENT: &str = "student";
+pub const CLASSROOM: &str = "classroom";
+pub const ASSIGNMENT: &str = "assignment";
+pub const CONFIG: &str = "config";
+pub const SCHOOL: &str = "school";
+pub const DATABASE: &str = "database";
+pub const ABSENCE: &str = "absence";
+pub const SCHEDULE_OVERRIDE: &str = "schedule_override";
+pub const TERM: &str = "term";
+pub const HOLIDAY: &str = "holiday";
+pub const RESOURCE_CLOSURE: &str = "resource_closure";
+
+/// Estructura de una materia
+/// Se utiliza para mapear los datos de una materia de la base de datos a un objeto en Rust
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Subject {
+    pub id: Option<i16>, // ID opcional cuando se crea por medio de la clase
+    pub name: String,
+    pub shorten: String,
+    pub color: String,
+    pub spec: Option<String>,
+    pub required_modules: Option<i16>,
+    pub priority: Option<i16>,
+}
+
+impl<'r> FromRow<'r, SqliteRow> for Subject {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, SqlxError> {
+        Ok(Subject {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+            shorten: row.try_get("shorten")?,
+            color: row.try_get("color")?,
+            spec: row.try_get("spec")?,
+            required_modules: row.try_get("required_modules")?,
+            priority: row.try_get("priority")?,
+        })
+    }
+}
+
+/// Estructura de una materia con profesor asignado
+/// Se utiliza para mapear los datos de una materia de la base de datos a un objeto en Rust
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SubjectWithTeacher {
+    pub id: i16,
+    pub name: String,
+    pub shorten: String,
+    pub color: String,
+    pub spec: String,
+    pub required_modules: Option<i16>,
+    pub priority: Option<i16>,
+    pub assigned_teacher: Option<SimpleTeacher>,
+}
+
+impl<'r> FromRow<'r, SqliteRow> for SubjectWithTeacher {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, SqlxError> {
+        Ok(SubjectWithTeacher {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+            shorten: row.try_get("shorten")?,
+            color: row.try_get("color")?,
+            spec: row.try_get("spec")?,
+            required_modules: row.try_get("required_modules")?,
+            priority: row.try_get("priority")?,
+            assigned_teacher: None,
+        })
+    }
+}
+
+/// Estructura simple de un profesor, solo contiene el ID, el nombre y el primer apellido
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SimpleTeacher {
+    pub id: Option<i16>,
+    pub name: String,
+    pub father_lastname: String,
+}
+
+/// Estructura de un profesor
+/// Se utiliza para mapear los datos de un profesor de la base de datos a un objeto en Rust
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Teacher {
+    pub id: Option<i16>,
+    pub name: String,
+    pub father_lastname: String,
+    pub mother_lastname: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub degree: Option<String>,
+    pub commisioned_hours: Option<i16>,      // Total de horas
+    pub active_hours: Option<i16>,           // Horas activas en el programa
+    pub performance: Option<i16>,            // Desempeño
+    pub preferred_days: Option<Vec<String>>, // Dias preferidos del profesor
+    pub preferred_modules: Option<Vec<i16>>, // Modulos preferidos del profesor (eg: |1, 2, 3])
+}
+
+// Implement FromRow for Teacher
+impl<'r> FromRow<'r, SqliteRow> for Teacher {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, SqlxError> {
+        // For the Vec fields, get them as strings and deserialize
+        let preferred_days_str: String = row.try_get("preferred_days")?;
+        let preferred_days: Vec<String> =
+            serde_json::from_str(&preferred_days_str).unwrap_or_default();
+
+        let preferred_modules_str: String = row.try_get("preferred_modules")?;
+        let preferred_modules: Vec<i16> =
+            serde_json::from_str(&preferred_modules_str).unwrap_or_default();
+
+        Ok(Teacher {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+            father_lastname: row.try_get("father_lastname")?,
+            mother_lastname: row.try_get("mother_lastname")?,
+            email: row.try_get("email")?,
+            phone: row.try_get("phone")?,
+            degree: row.try_get("degree")?,
+            commisioned_hours: row.try_get("commisioned_hours")?,
+            active_hours: row.try_get("active_hours")?,
+            performance: row.try_get("performance")?,
+            preferred_days: Some(preferred_days),
+            preferred_modules: Some(preferred_modules),
+        })
+    }
+}
+
+/// Relacion entre un profesor y una materia que puede dar (tabla `teacher_subjects`)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TeacherSubjects {
+    pub teacher_id: i16,
+    pub subject_id: i16,
+}
+
+impl<'r> FromRow<'r, SqliteRow> for TeacherSubjects {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, SqlxError> {
+        Ok(TeacherSubjects {
+            teacher_id: row.try_get("teacher_id")?,
+            subject_id: row.try_get("subject_id")?,
+        })
+    }
+}
+
+/// Estructura de un grupo
+/// Se utiliza para mapear los datos del grupo de la base de datos a un objeto en Rust
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Group {
+    pub id: Option<i16>,
+    pub grade: i16,
+    pub group: String,
+    pub career: Option<String>,
+    pub students: Option<i16>,
+    pub max_modules_per_day: Option<i16>,
+}
+
+impl<'r> FromRow<'r, SqliteRow> for Group {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, SqlxError> {
+        Ok(Group {
+            id: row.try_get("id")?,
+            grade: row.try_get("grade")?,
+            group: row.try_get("group")?,
+            career: row.try_get("career")?,
+            students: row.try_get("students")?,
+            max_modules_per_day: row.try_get("max_modules_per_day")?,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GroupSubjects {
+    pub group_id: i16,
+    pub subject_id: i16,
+}
+
+impl<'r> FromRow<'r, SqliteRow> for GroupSubjects {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, SqlxError> {
+        Ok(GroupSubjects {
+            group_id: row.try_get("group_id")?,
+            subject_id: row.try_get("subject_id")?,
+        })
+    }
+}
+
+/// Estructura de un estudiante
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Student {
+    pub id: Option<i16>,
+    pub name: String,
+    pub father_lastname: String,
+    pub mother_lastname: Option<String>,
+    pub group_id: Option<i16>,
+}
+
+impl<'r> FromRow<'r, SqliteRow> for Student {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, SqlxError> {
+        Ok(Student {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+            father_lastname: row.try_get("father_lastname")?,
+            mother_lastname: row.try_get("mother_lastname")?,
+            group_id: row.try_get("group_id")?,
+        })
+    }
+}
+
+/// Estructural salon
+/// Se utiliza para mapear los datos de la base de datos a un objeto en Rust
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Classroom {
+    pub id: Option<i16>,
+    pub building_id: Option<String>, // Puede ser una letra o numero entonces lo dejaremos como String
+    pub building_number: i16, // Numero de aula, lo que sigue despues del building_id (ejemplo: 303)
+    pub building_type: Option<String>,
+    pub capacity: Option<i16>,
+    pub availability: Option<Vec<(String, i16)>>, // Lista con (dias, modulos)
+}
+
+impl<'r> FromRow<'r, SqliteRow> for Classroom {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, SqlxError> {
+        let availability_str: String = row.try_get("availability")?;
+        let availability: Vec<(String, i16)> =
+            serde_json::from_str(&availability_str).unwrap_or_default();
+
+        Ok(Classroom {
+            id: row.try_get("id")?,
+            building_id: row.try_get("building_id")?,
+            building_number: row.try_get("building_number")?,
+            building_type: row.try_get("building_type")?,
+            capacity: row.try_get("capacity")?,
+            availability: Some(availability),
+        })
+    }
+}
+
+/// Estructura de la asignacion
+/// Se utiliza para mapear los datos que van y vienen de la base de datos
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct Assignment {
+    pub id: Option<i16>,
+    pub group_id: i16,
+    pub day: String,
+    pub module_index: i16,
+    pub subject_id: i16,
+    pub teacher_id: i16,
+    pub classroom_id: i16,
+    // Se copian de la materia; los archivos de texto pueden omitirlos
+    #[serde(default)]
+    pub subject_name: String,
+    #[serde(default)]
+    pub subject_shorten: String,
+    #[serde(default)]
+    pub subject_color: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Config {
+    pub days: Vec<String>,
+    #[serde(rename = "modulesPerDay")]
+    pub modules_per_day: u32,
+    #[serde(rename = "moduleDuration")]
+    pub module_duration: u32,
+    #[serde(rename = "durationUnit")]
+    pub duration_unit: String,
+    #[serde(rename = "hasBreaks")]
+    pub has_breaks: bool,
+    #[serde(rename = "breakCount")]
+    pub break_count: u32,
+    #[serde(rename = "breakDuration")]
+    pub break_duration: u32,
+    #[serde(rename = "breakPositions")]
+    pub break_positions: Vec<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SchoolInfo {
+    pub name: String,
+    pub logo_path: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            days: vec![
+                "Lunes".to_string(),
+                "Martes".to_string(),
+                "Miércoles".to_string(),
+                "Jueves".to_string(),
+                "Viernes".to_string(),
+            ],
+            modules_per_day: 9,
+            module_duration: 50,
+            duration_unit: "minutes".to_string(),
+            has_breaks: false,
+            break_count: 1,
+            break_duration: 30,
+            break_positions: vec![2],
+        }
+    }
+}
@@ -0,0 +1,945 @@
+/*
+    Formato de los archivos de horario: .roster (binario, con CRC32, compresion y cifrado
+    opcionales) y .json / .yaml (texto, ver docs/schedule.schema.json).
+    No depende de tauri ni de la base de datos, asi school_roster-cli lee y escribe los
+    mismos archivos que la interfaz. Importar y exportar desde la aplicacion esta en
+    util::file_handler.
+*/
+
+use aes_gcm::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Nonce,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use bincode::{self, Options};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{Read, Write},
+    path::Path,
+};
+use thiserror::Error;
+
+use crate::model::{
+    Assignment, Classroom, Config, Group, GroupSubjects, SchoolInfo, Student, Subject,
+    SubjectWithTeacher, Teacher, TeacherSubjects,
+};
+
+#[derive(Debug, Error)]
+pub enum ScheduleFileError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] sqlx::Error),
+
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] bincode::Error),
+
+    #[error("Invalid file format")]
+    InvalidFormat,
+
+    #[error("The file is truncated")]
+    Truncated,
+
+    #[error("The file is corrupted (checksum mismatch)")]
+    ChecksumMismatch,
+
+    #[error("The file is too large ({0} bytes)")]
+    TooLarge(u64),
+
+    #[error("Compression error: {0}")]
+    CompressionError(String),
+
+    #[error("The file is encrypted, a password is needed")]
+    PasswordRequired,
+
+    #[error("Wrong password")]
+    WrongPassword,
+
+    #[error("The file was modified after it was encrypted")]
+    Tampered,
+
+    #[error("Encryption error: {0}")]
+    EncryptionError(String),
+
+    #[error("Unsupported file type: .{0}")]
+    UnsupportedFileType(String),
+
+    #[error("Text format error: {0}")]
+    TextError(String),
+
+    #[error("Unsupported schema version: {0}")]
+    UnsupportedSchema(u32),
+
+    #[error("Unsupported version: {0}")]
+    UnsuportedVersion(u16),
+
+    #[error("Data error: {0}")]
+    DataError(String),
+
+    #[error("Invalid data: {0}")]
+    InvalidData(String),
+}
+
+// Alias del resultado
+pub type ScheduleResult<T> = std::result::Result<T, ScheduleFileError>;
+
+// File format header
+// Header de las versiones 1 y 2 (serializado con bincode)
+#[derive(Serialize, Deserialize, Debug)]
+struct ScheduleFileHeader {
+    magic: [u8; 4],  // "SCHA" in ASCII
+    version: u16,    // File format version
+    created_at: i64, // UTC timestamp
+    checksum: u32,   // Simple checksum for integrity verification
+}
+
+// Header desde la version 3, se escribe a mano en little endian para poder revisarlo
+// antes de leer los datos:
+// magic (4) | version (2) | flags (2) | created_at (8) | tamaño guardado (8) |
+// tamaño de los datos sin comprimir (8) | CRC32 (4)
+// El CRC32 cubre los bytes anteriores del header y los datos guardados
+#[derive(Debug)]
+struct RosterHeader {
+    version: u16,
+    flags: u16,
+    created_at: i64,
+    stored_len: u64,
+    data_len: u64,
+    checksum: u32,
+}
+
+impl RosterHeader {
+    const SIZE: usize = 36;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::SIZE);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bytes.extend_from_slice(&self.flags.to_le_bytes());
+        bytes.extend_from_slice(&self.created_at.to_le_bytes());
+        bytes.extend_from_slice(&self.stored_len.to_le_bytes());
+        bytes.extend_from_slice(&self.data_len.to_le_bytes());
+        bytes.extend_from_slice(&self.checksum.to_le_bytes());
+        bytes
+    }
+
+    fn parse(bytes: &[u8]) -> ScheduleResult<Self> {
+        if bytes.len() < Self::SIZE {
+            return Err(ScheduleFileError::Truncated);
+        }
+
+        Ok(RosterHeader {
+            version: u16::from_le_bytes(field(bytes, 4)),
+            flags: u16::from_le_bytes(field(bytes, 6)),
+            created_at: i64::from_le_bytes(field(bytes, 8)),
+            stored_len: u64::from_le_bytes(field(bytes, 16)),
+            data_len: u64::from_le_bytes(field(bytes, 24)),
+            checksum: u32::from_le_bytes(field(bytes, 32)),
+        })
+    }
+}
+
+// Copia `N` bytes desde `at` (el llamador ya reviso el tamaño)
+fn field<const N: usize>(bytes: &[u8], at: usize) -> [u8; N] {
+    let mut out = [0u8; N];
+    out.copy_from_slice(&bytes[at..at + N]);
+    out
+}
+
+const MAGIC: &[u8; 4] = b"SCHA";
+
+// Los datos guardados estan comprimidos con zstd
+const FLAG_ZSTD: u16 = 1;
+// Los datos guardados estan cifrados con una contraseña
+const FLAG_ENCRYPTED: u16 = 2;
+
+// Los datos cifrados empiezan con los parametros para sacar la clave:
+// memoria de Argon2id en KiB (4) | iteraciones (4) | paralelismo (4) | sal (16) | nonce (12) |
+// verificador (32)
+// Argon2id da 64 bytes: la clave de AES-256-GCM y el verificador, que sirve para distinguir
+// una contraseña incorrecta de un archivo modificado
+// El header (sin el checksum) va como dato asociado, asi tampoco se puede cambiar
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const VERIFIER_LEN: usize = 32;
+const CRYPTO_PREFIX_LEN: usize = 12 + SALT_LEN + NONCE_LEN + VERIFIER_LEN;
+const TAG_LEN: usize = 16;
+
+// Costo de Argon2id al cifrar y maximos que se aceptan al leer un archivo
+const KDF_MEMORY_KIB: u32 = 19 * 1024;
+const KDF_ITERATIONS: u32 = 2;
+const KDF_PARALLELISM: u32 = 1;
+const MAX_KDF_MEMORY_KIB: u32 = 256 * 1024;
+const MAX_KDF_ITERATIONS: u32 = 16;
+const MAX_KDF_PARALLELISM: u32 = 8;
+
+// Limites para no leer archivos enormes ni reservar memoria por un tamaño falso
+const MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+const MAX_DATA_SIZE: u64 = 256 * 1024 * 1024;
+
+// Version del formato que se escribe
+// 1: solo materias, profesores, grupos, aulas y horario (sin ids en las relaciones)
+// 2: todas las tablas de la escuela con sus ids, la configuracion y los datos de la escuela
+// 3: los mismos datos con CRC32, compresion zstd y cifrado con contraseña opcionales
+const FORMAT_VERSION: u16 = 3;
+
+// Version del esquema de los archivos de texto (.json, .yaml), ver docs/schedule.schema.json
+// Solo cambia si un archivo de la version anterior ya no se puede leer igual
+const SCHEMA_VERSION: u32 = 1;
+
+/// Opciones para escribir un archivo .roster
+/// Con `password` (no vacia) los datos se cifran y se necesita la misma para leerlo
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct RosterOptions {
+    pub compress: bool,
+    pub password: Option<String>,
+}
+
+impl Default for RosterOptions {
+    fn default() -> Self {
+        RosterOptions {
+            compress: true,
+            password: None,
+        }
+    }
+}
+
+// Tablas que se van a exportar en el archivo
+// Cada fila conserva su id para que las relaciones sigan igual al importar
+// `config` y `school` son `None` en archivos de la version 1 (no se tocan al importar)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScheduleData {
+    pub subjects: Vec<Subject>,
+    pub teachers: Vec<Teacher>,
+    pub teacher_subjects: Vec<TeacherSubjects>,
+    pub groups: Vec<Group>,
+    pub group_subjects: Vec<GroupSubjects>,
+    pub students: Vec<Student>,
+    pub classrooms: Vec<Classroom>,
+    pub assignments: Vec<Assignment>,
+    pub config: Option<Config>,
+    pub school: Option<SchoolInfo>,
+}
+
+/// Documento de texto (.json, .yaml) con los mismos datos que un archivo .roster
+/// Las tablas van al mismo nivel que `schema_version` (sin `serde(flatten)`, asi los
+/// errores de formato conservan la linea y columna)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScheduleDocument {
+    pub schema_version: u32,
+    pub subjects: Vec<Subject>,
+    pub teachers: Vec<Teacher>,
+    pub teacher_subjects: Vec<TeacherSubjects>,
+    pub groups: Vec<Group>,
+    pub group_subjects: Vec<GroupSubjects>,
+    pub students: Vec<Student>,
+    pub classrooms: Vec<Classroom>,
+    pub assignments: Vec<Assignment>,
+    pub config: Option<Config>,
+    pub school: Option<SchoolInfo>,
+}
+
+impl From<ScheduleData> for ScheduleDocument {
+    fn from(data: ScheduleData) -> Self {
+        ScheduleDocument {
+            schema_version: SCHEMA_VERSION,
+            subjects: data.subjects,
+            teachers: data.teachers,
+            teacher_subjects: data.teacher_subjects,
+            groups: data.groups,
+            group_subjects: data.group_subjects,
+            students: data.students,
+            classrooms: data.classrooms,
+            assignments: data.assignments,
+            config: data.config,
+            school: data.school,
+        }
+    }
+}
+
+impl From<ScheduleDocument> for ScheduleData {
+    fn from(document: ScheduleDocument) -> Self {
+        ScheduleData {
+            subjects: document.subjects,
+            teachers: document.teachers,
+            teacher_subjects: document.teacher_subjects,
+            groups: document.groups,
+            group_subjects: document.group_subjects,
+            students: document.students,
+            classrooms: document.classrooms,
+            assignments: document.assignments,
+            config: document.config,
+            school: document.school,
+        }
+    }
+}
+
+// Solo la version, para revisarla antes de leer el resto del documento
+#[derive(Deserialize)]
+struct SchemaProbe {
+    schema_version: u32,
+}
+
+/// Formato de texto de un archivo de horario
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextFormat {
+    Json,
+    Yaml,
+}
+
+impl TextFormat {
+    /// Funcion que retorna el formato de texto segun la extension del archivo
+    /// Retorna `None` para los .roster (o si no tiene extension)
+    pub fn from_path(path: &Path) -> ScheduleResult<Option<TextFormat>> {
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "" | "roster" => Ok(None),
+            "json" => Ok(Some(TextFormat::Json)),
+            "yaml" | "yml" => Ok(Some(TextFormat::Yaml)),
+            other => Err(ScheduleFileError::UnsupportedFileType(other.to_string())),
+        }
+    }
+}
+
+// Datos de los archivos de la version 1
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ScheduleDataV1 {
+    subjects: Vec<Subject>,
+    teachers: Vec<Teacher>,
+    teacher_subjects: Vec<SubjectWithTeacher>,
+    groups: Vec<Group>,
+    classrooms: Vec<Classroom>,
+    assignments: Vec<Assignment>,
+}
+
+impl From<ScheduleDataV1> for ScheduleData {
+    // La version 1 guardaba un solo profesor por materia y no guardaba las materias de
+    // cada grupo, se deducen de sus asignaciones
+    fn from(data: ScheduleDataV1) -> Self {
+        let teacher_subjects = data
+            .teacher_subjects
+            .iter()
+            .filter_map(|s| {
+                Some(TeacherSubjects {
+                    teacher_id: s.assigned_teacher.as_ref()?.id?,
+                    subject_id: s.id,
+                })
+            })
+            .collect();
+
+        let mut group_subjects: Vec<GroupSubjects> = Vec::new();
+        for a in &data.assignments {
+            let exists = group_subjects
+                .iter()
+                .any(|gs| gs.group_id == a.group_id && gs.subject_id == a.subject_id);
+            if !exists {
+                group_subjects.push(GroupSubjects {
+                    group_id: a.group_id,
+                    subject_id: a.subject_id,
+                });
+            }
+        }
+
+        ScheduleData {
+            subjects: data.subjects,
+            teachers: data.teachers,
+            teacher_subjects,
+            groups: data.groups,
+            group_subjects,
+            students: Vec::new(),
+            classrooms: data.classrooms,
+            assignments: data.assignments,
+            config: None,
+            school: None,
+        }
+    }
+}
+
+// Suma de bytes de las versiones 1 y 2, solo se usa para leer esos archivos
+fn simple_checksum(data: &[u8]) -> u32 {
+    data.iter().fold(0u32, |acc, &x| acc.wrapping_add(x as u32))
+}
+
+/// Funcion para escribir los datos en un archivo .roster (no necesita la aplicacion)
+/// # Argumentos
+/// * `output_path` - Ruta del archivo
+/// * `data` - Datos a guardar
+/// * `options` - Compresion y contraseña (`RosterOptions::default()` comprime sin cifrar)
+pub fn write_roster_file(
+    output_path: &Path,
+    data: &ScheduleData,
+    options: &RosterOptions,
+) -> ScheduleResult<()> {
+    let bytes = encode_roster(data, options)?;
+
+    // Crear el archivo
+    let mut file = File::create(output_path)?;
+    file.write_all(&bytes)?;
+
+    Ok(())
+}
+
+/// Funcion para armar el contenido de un archivo .roster: header y datos
+pub fn encode_roster(data: &ScheduleData, options: &RosterOptions) -> ScheduleResult<Vec<u8>> {
+    // Serializamos los datos utilizando bincode
+    let serialized_data = bincode::serialize(data)?;
+    let data_len = serialized_data.len() as u64;
+    if data_len > MAX_DATA_SIZE {
+        return Err(ScheduleFileError::TooLarge(data_len));
+    }
+
+    let mut flags = 0;
+    let mut payload = if options.compress {
+        flags |= FLAG_ZSTD;
+        zstd::encode_all(serialized_data.as_slice(), 0)
+            .map_err(|e| ScheduleFileError::CompressionError(e.to_string()))?
+    } else {
+        serialized_data
+    };
+
+    let password = options.password.as_deref().filter(|p| !p.is_empty());
+    let mut stored_len = payload.len();
+    if password.is_some() {
+        flags |= FLAG_ENCRYPTED;
+        stored_len += CRYPTO_PREFIX_LEN + TAG_LEN;
+    }
+
+    let mut header = RosterHeader {
+        version: FORMAT_VERSION,
+        flags,
+        created_at: chrono::Utc::now().timestamp(),
+        stored_len: stored_len as u64,
+        data_len,
+        checksum: 0,
+    };
+    if let Some(password) = password {
+        let header_bytes = header.to_bytes();
+        payload = encrypt(password, &payload, &header_bytes[..RosterHeader::SIZE - 4])?;
+    }
+    header.checksum = roster_checksum(&header.to_bytes(), &payload);
+
+    let mut bytes = header.to_bytes();
+    bytes.extend_from_slice(&payload);
+    Ok(bytes)
+}
+
+// CRC32 de los bytes del header (sin el checksum) y los datos guardados
+fn roster_checksum(header: &[u8], payload: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&header[..RosterHeader::SIZE - 4]);
+    hasher.update(payload);
+    hasher.finalize()
+}
+
+// Cifra los datos con una clave sacada de la contraseña
+// Retorna los parametros de la clave seguidos de los datos cifrados
+fn encrypt(password: &str, data: &[u8], header: &[u8]) -> ScheduleResult<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+    let keys = derive_keys(
+        password,
+        &salt,
+        KDF_MEMORY_KIB,
+        KDF_ITERATIONS,
+        KDF_PARALLELISM,
+    )?;
+    let cipher = Aes256Gcm::new_from_slice(&keys[..32])
+        .map_err(|e| ScheduleFileError::EncryptionError(e.to_string()))?;
+    let encrypted = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: data,
+                aad: header,
+            },
+        )
+        .map_err(|_| ScheduleFileError::EncryptionError("Failed to encrypt the data".into()))?;
+
+    let mut out = Vec::with_capacity(CRYPTO_PREFIX_LEN + encrypted.len());
+    out.extend_from_slice(&KDF_MEMORY_KIB.to_le_bytes());
+    out.extend_from_slice(&KDF_ITERATIONS.to_le_bytes());
+    out.extend_from_slice(&KDF_PARALLELISM.to_le_bytes());
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&keys[32..]);
+    out.extend_from_slice(&encrypted);
+    Ok(out)
+}
+
+// Descifra los datos guardados por `encrypt`
+// Si ni el verificador ni el cifrado coinciden la contraseña es incorrecta; si solo uno de
+// los dos falla el archivo se modifico
+fn decrypt(password: &str, payload: &[u8], header: &[u8]) -> ScheduleResult<Vec<u8>> {
+    if payload.len() < CRYPTO_PREFIX_LEN + TAG_LEN {
+        return Err(ScheduleFileError::Truncated);
+    }
+
+    let memory = u32::from_le_bytes(field(payload, 0));
+    let iterations = u32::from_le_bytes(field(payload, 4));
+    let parallelism = u32::from_le_bytes(field(payload, 8));
+    if memory > MAX_KDF_MEMORY_KIB
+        || iterations > MAX_KDF_ITERATIONS
+        || parallelism > MAX_KDF_PARALLELISM
+    {
+        return Err(ScheduleFileError::InvalidFormat);
+    }
+
+    let (salt, rest) = payload[12..].split_at(SALT_LEN);
+    let (nonce, rest) = rest.split_at(NONCE_LEN);
+    let (verifier, encrypted) = rest.split_at(VERIFIER_LEN);
+
+    let keys = derive_keys(password, salt, memory, iterations, parallelism)
+        .map_err(|_| ScheduleFileError::InvalidFormat)?;
+    let cipher = Aes256Gcm::new_from_slice(&keys[..32])
+        .map_err(|e| ScheduleFileError::EncryptionError(e.to_string()))?;
+    let decrypted = cipher.decrypt(
+        Nonce::from_slice(nonce),
+        Payload {
+            msg: encrypted,
+            aad: header,
+        },
+    );
+
+    // Si el verificador no coincide pero los datos se descifran la contraseña es correcta
+    // y lo que se cambio fue el verificador
+    match (same_bytes(&keys[32..], verifier), decrypted) {
+        (true, Ok(data)) => Ok(data),
+        (true, Err(_)) | (false, Ok(_)) => Err(ScheduleFileError::Tampered),
+        (false, Err(_)) => Err(ScheduleFileError::WrongPassword),
+    }
+}
+
+// Saca la clave de cifrado y el verificador de la contraseña con Argon2id
+fn derive_keys(
+    password: &str,
+    salt: &[u8],
+    memory: u32,
+    iterations: u32,
+    parallelism: u32,
+) -> ScheduleResult<[u8; 64]> {
+    let params = Params::new(memory, iterations, parallelism, Some(64))
+        .map_err(|e| ScheduleFileError::EncryptionError(e.to_string()))?;
+
+    let mut keys = [0u8; 64];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, &mut keys)
+        .map_err(|e| ScheduleFileError::EncryptionError(e.to_string()))?;
+    Ok(keys)
+}
+
+// Compara sin salir en el primer byte distinto
+fn same_bytes(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// Opciones de bincode iguales a `bincode::serialize` pero sin leer mas de `limit` bytes
+fn bincode_options(limit: u64) -> impl Options {
+    bincode::options()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(limit)
+}
+
+/// Funcion para leer y verificar un archivo .roster (no necesita la aplicacion)
+/// La contraseña solo se usa si el archivo esta cifrado
+pub fn read_roster_file(file_path: &Path, password: Option<&str>) -> ScheduleResult<ScheduleData> {
+    decode_roster(&read_limited(file_path)?, password)
+}
+
+/// Funcion para leer un archivo de horario en cualquier formato (.roster, .json o .yaml)
+/// El formato se elige por la extension; la contraseña solo se usa en .roster cifrados
+pub fn read_schedule_file(
+    file_path: &Path,
+    password: Option<&str>,
+) -> ScheduleResult<ScheduleData> {
+    match TextFormat::from_path(file_path)? {
+        Some(format) => decode_text(&read_limited(file_path)?, format),
+        None => read_roster_file(file_path, password),
+    }
+}
+
+/// Funcion para escribir los datos en el formato que indique la extension del archivo
+/// Solo los .roster se pueden comprimir o cifrar
+pub fn write_schedule_file(
+    output_path: &Path,
+    data: &ScheduleData,
+    options: &RosterOptions,
+) -> ScheduleResult<()> {
+    let Some(format) = TextFormat::from_path(output_path)? else {
+        return write_roster_file(output_path, data, options);
+    };
+
+    if options.password.as_deref().is_some_and(|p| !p.is_empty()) {
+        return Err(ScheduleFileError::EncryptionError(
+            "Only .roster files can be encrypted".to_string(),
+        ));
+    }
+
+    fs::write(output_path, encode_text(data, format)?)?;
+    Ok(())
+}
+
+/// Funcion para convertir los datos a un documento de texto con la version del esquema
+/// Las filas quedan en el mismo orden que en la base de datos (por id), asi el mismo
+/// contenido siempre genera el mismo texto
+pub fn encode_text(data: &ScheduleData, format: TextFormat) -> ScheduleResult<String> {
+    let document = ScheduleDocument::from(data.clone());
+
+    match format {
+        TextFormat::Json => serde_json::to_string_pretty(&document)
+            .map(|text| text + "\n")
+            .map_err(|e| ScheduleFileError::TextError(e.to_string())),
+        TextFormat::Yaml => serde_yaml::to_string(&document)
+            .map_err(|e| ScheduleFileError::TextError(e.to_string())),
+    }
+}
+
+/// Funcion para leer un documento de texto ya cargado en memoria
+/// Revisa `schema_version` antes de leer las tablas; los errores de formato traen la
+/// linea y columna para poder corregir el archivo a mano
+pub fn decode_text(bytes: &[u8], format: TextFormat) -> ScheduleResult<ScheduleData> {
+    let text =
+        std::str::from_utf8(bytes).map_err(|e| ScheduleFileError::TextError(e.to_string()))?;
+
+    let probe: SchemaProbe = parse_text(text, format)?;
+    if probe.schema_version != SCHEMA_VERSION {
+        return Err(ScheduleFileError::UnsupportedSchema(probe.schema_version));
+    }
+
+    let document: ScheduleDocument = parse_text(text, format)?;
+    Ok(document.into())
+}
+
+fn parse_text<T: DeserializeOwned>(text: &str, format: TextFormat) -> ScheduleResult<T> {
+    match format {
+        TextFormat::Json => {
+            serde_json::from_str(text).map_err(|e| ScheduleFileError::TextError(e.to_string()))
+        }
+        TextFormat::Yaml => {
+            serde_yaml::from_str(text).map_err(|e| ScheduleFileError::TextError(e.to_string()))
+        }
+    }
+}
+
+// Lee un archivo completo sin pasar de MAX_FILE_SIZE
+fn read_limited(file_path: &Path) -> ScheduleResult<Vec<u8>> {
+    let file = File::open(file_path)?;
+
+    let size = file.metadata()?.len();
+    if size > MAX_FILE_SIZE {
+        return Err(ScheduleFileError::TooLarge(size));
+    }
+
+    // Aunque el archivo crezca mientras se lee no se pasa del limite
+    let mut buffer = Vec::with_capacity(size as usize);
+    file.take(MAX_FILE_SIZE + 1).read_to_end(&mut buffer)?;
+    if buffer.len() as u64 > MAX_FILE_SIZE {
+        return Err(ScheduleFileError::TooLarge(buffer.len() as u64));
+    }
+
+    Ok(buffer)
+}
+
+/// Funcion para leer el contenido de un archivo .roster ya cargado en memoria
+/// Revisa el header, el tamaño y el checksum antes de deserializar y nunca reserva mas
+/// memoria de la que dice el header (con su limite)
+pub fn decode_roster(bytes: &[u8], password: Option<&str>) -> ScheduleResult<ScheduleData> {
+    // Verifica los bytes
+    if bytes.len() < 6 {
+        return Err(ScheduleFileError::Truncated);
+    }
+    if &bytes[..4] != MAGIC {
+        return Err(ScheduleFileError::InvalidFormat);
+    }
+
+    // Checa la version
+    match u16::from_le_bytes(field(bytes, 4)) {
+        1 | 2 => decode_legacy(bytes),
+        FORMAT_VERSION => decode_current(bytes, password),
+        version => Err(ScheduleFileError::UnsuportedVersion(version)),
+    }
+}
+
+// Archivos de las versiones 1 y 2: header de bincode, suma de bytes y datos sin comprimir
+fn decode_legacy(bytes: &[u8]) -> ScheduleResult<ScheduleData> {
+    let header_size = bincode::serialized_size(&ScheduleFileHeader {
+        magic: *MAGIC,
+        version: 0,
+        created_at: 0,
+        checksum: 0,
+    })? as usize;
+    if bytes.len() < header_size {
+        return Err(ScheduleFileError::Truncated);
+    }
+
+    let header: ScheduleFileHeader = bincode::deserialize(&bytes[..header_size])?;
+
+    // Checksum
+    let data_part = &bytes[header_size..];
+    if simple_checksum(data_part) != header.checksum {
+        return Err(ScheduleFileError::ChecksumMismatch);
+    };
+
+    // Deserealizar los datos (los de la version 1 se convierten al formato actual)
+    let options = bincode_options(data_part.len() as u64);
+    let data: ScheduleData = match header.version {
+        1 => options.deserialize::<ScheduleDataV1>(data_part)?.into(),
+        _ => options.deserialize(data_part)?,
+    };
+
+    Ok(data)
+}
+
+fn decode_current(bytes: &[u8], password: Option<&str>) -> ScheduleResult<ScheduleData> {
+    let header = RosterHeader::parse(bytes)?;
+
+    if header.flags & !(FLAG_ZSTD | FLAG_ENCRYPTED) != 0 {
+        return Err(ScheduleFileError::InvalidFormat);
+    }
+    if header.data_len > MAX_DATA_SIZE {
+        return Err(ScheduleFileError::TooLarge(header.data_len));
+    }
+
+    let payload = &bytes[RosterHeader::SIZE..];
+    match (payload.len() as u64).cmp(&header.stored_len) {
+        std::cmp::Ordering::Less => return Err(ScheduleFileError::Truncated),
+        std::cmp::Ordering::Greater => return Err(ScheduleFileError::InvalidFormat),
+        std::cmp::Ordering::Equal => {}
+    }
+
+    if roster_checksum(bytes, payload) != header.checksum {
+        return Err(ScheduleFileError::ChecksumMismatch);
+    }
+
+    let decrypted;
+    let payload = if header.flags & FLAG_ENCRYPTED != 0 {
+        let password = password
+            .filter(|p| !p.is_empty())
+            .ok_or(ScheduleFileError::PasswordRequired)?;
+        decrypted = decrypt(password, payload, &bytes[..RosterHeader::SIZE - 4])?;
+        decrypted.as_slice()
+    } else {
+        payload
+    };
+
+    let data = if header.flags & FLAG_ZSTD != 0 {
+        decompress(payload, header.data_len)?
+    } else {
+        payload.to_vec()
+    };
+    if data.len() as u64 != header.data_len {
+        return Err(ScheduleFileError::InvalidFormat);
+    }
+
+    Ok(bincode_options(header.data_len).deserialize(&data)?)
+}
+
+// Descomprime sin pasar del tamaño que dice el header
+fn decompress(payload: &[u8], data_len: u64) -> ScheduleResult<Vec<u8>> {
+    let decoder = zstd::stream::read::Decoder::new(payload)
+        .map_err(|e| ScheduleFileError::CompressionError(e.to_string()))?;
+
+    let mut data = Vec::with_capacity(data_len.min(MAX_FILE_SIZE) as usize);
+    decoder
+        .take(data_len + 1)
+        .read_to_end(&mut data)
+        .map_err(|e| ScheduleFileError::CompressionError(e.to_string()))?;
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data() -> ScheduleData {
+        ScheduleData {
+            subjects: vec![Subject {
+                id: Some(1),
+                name: "Matematicas".to_string(),
+                shorten: "MAT".to_string(),
+                color: "#000".to_string(),
+                spec: None,
+                required_modules: Some(5),
+                priority: None,
+            }],
+            teachers: Vec::new(),
+            teacher_subjects: Vec::new(),
+            groups: Vec::new(),
+            group_subjects: Vec::new(),
+            students: Vec::new(),
+            classrooms: Vec::new(),
+            assignments: Vec::new(),
+            config: None,
+            school: None,
+        }
+    }
+
+    fn encoded() -> Vec<u8> {
+        encode_roster(&data(), &RosterOptions::default()).unwrap()
+    }
+
+    // Cambia el header y vuelve a calcular el CRC, como lo haria un archivo armado a mano
+    fn forge(bytes: &[u8], change: impl FnOnce(&mut RosterHeader)) -> Vec<u8> {
+        let mut header = RosterHeader::parse(bytes).unwrap();
+        change(&mut header);
+        let payload = &bytes[RosterHeader::SIZE..];
+        header.checksum = roster_checksum(&header.to_bytes(), payload);
+
+        let mut forged = header.to_bytes();
+        forged.extend_from_slice(payload);
+        forged
+    }
+
+    #[test]
+    fn reads_what_it_writes() {
+        let decoded = decode_roster(&encoded(), None).unwrap();
+        assert_eq!(decoded.subjects.len(), 1);
+        assert_eq!(decoded.subjects[0].shorten, "MAT");
+    }
+
+    #[test]
+    fn truncated_header_is_rejected() {
+        let bytes = encoded();
+
+        for len in [0, 3, 5] {
+            assert!(matches!(
+                decode_roster(&bytes[..len], None),
+                Err(ScheduleFileError::Truncated)
+            ));
+        }
+        // Magic y version completos pero el resto del header cortado
+        for len in [6, 20, RosterHeader::SIZE - 1] {
+            assert!(matches!(
+                decode_roster(&bytes[..len], None),
+                Err(ScheduleFileError::Truncated)
+            ));
+        }
+        // Header de la version 1 cortado
+        let mut legacy = MAGIC.to_vec();
+        legacy.extend_from_slice(&1u16.to_le_bytes());
+        assert!(matches!(
+            decode_roster(&legacy, None),
+            Err(ScheduleFileError::Truncated)
+        ));
+    }
+
+    #[test]
+    fn forged_lengths_are_rejected() {
+        let bytes = encoded();
+
+        let longer = forge(&bytes, |h| h.stored_len += 1);
+        assert!(matches!(
+            decode_roster(&longer, None),
+            Err(ScheduleFileError::Truncated)
+        ));
+
+        let shorter = forge(&bytes, |h| h.stored_len -= 1);
+        assert!(matches!(
+            decode_roster(&shorter, None),
+            Err(ScheduleFileError::InvalidFormat)
+        ));
+
+        let huge = forge(&bytes, |h| h.data_len = MAX_DATA_SIZE + 1);
+        assert!(matches!(
+            decode_roster(&huge, None),
+            Err(ScheduleFileError::TooLarge(_))
+        ));
+
+        let wrong_size = forge(&bytes, |h| h.data_len -= 1);
+        assert!(matches!(
+            decode_roster(&wrong_size, None),
+            Err(ScheduleFileError::InvalidFormat)
+        ));
+
+        // Sin volver a calcular el CRC el cambio se nota en el checksum
+        let mut unsigned = bytes.clone();
+        unsigned[24] ^= 1;
+        assert!(matches!(
+            decode_roster(&unsigned, None),
+            Err(ScheduleFileError::ChecksumMismatch)
+        ));
+    }
+
+    #[test]
+    fn flipped_byte_fails_the_checksum() {
+        let bytes = encoded();
+
+        let mut payload = bytes.clone();
+        let last = payload.len() - 1;
+        payload[last] ^= 0x40;
+        assert!(matches!(
+            decode_roster(&payload, None),
+            Err(ScheduleFileError::ChecksumMismatch)
+        ));
+
+        // created_at tambien esta cubierto
+        let mut header = bytes.clone();
+        header[8] ^= 1;
+        assert!(matches!(
+            decode_roster(&header, None),
+            Err(ScheduleFileError::ChecksumMismatch)
+        ));
+    }
+
+    #[test]
+    fn changed_verifier_is_reported_as_tampered() {
+        let options = RosterOptions {
+            compress: true,
+            password: Some("secreta".to_string()),
+        };
+        let mut bytes = encode_roster(&data(), &options).unwrap();
+        assert!(matches!(
+            decode_roster(&bytes, None),
+            Err(ScheduleFileError::PasswordRequired)
+        ));
+
+        // Cambia un byte del verificador y vuelve a calcular el CRC
+        bytes[RosterHeader::SIZE + CRYPTO_PREFIX_LEN - 1] ^= 1;
+        let mut header = RosterHeader::parse(&bytes).unwrap();
+        header.checksum = roster_checksum(&bytes, &bytes[RosterHeader::SIZE..]);
+        bytes[..RosterHeader::SIZE].copy_from_slice(&header.to_bytes());
+
+        assert!(matches!(
+            decode_roster(&bytes, Some("secreta")),
+            Err(ScheduleFileError::Tampered)
+        ));
+        assert!(matches!(
+            decode_roster(&bytes, Some("otra")),
+            Err(ScheduleFileError::WrongPassword)
+        ));
+    }
+
+    #[test]
+    fn zstd_bomb_stops_at_the_declared_size() {
+        // 32 MiB de ceros comprimidos en unos cuantos KiB, el header dice que son 1 KiB
+        let bomb = zstd::encode_all(vec![0u8; 32 * 1024 * 1024].as_slice(), 19).unwrap();
+        let mut header = RosterHeader {
+            version: FORMAT_VERSION,
+            flags: FLAG_ZSTD,
+            created_at: 0,
+            stored_len: bomb.len() as u64,
+            data_len: 1024,
+            checksum: 0,
+        };
+        header.checksum = roster_checksum(&header.to_bytes(), &bomb);
+        let mut bytes = header.to_bytes();
+        bytes.extend_from_slice(&bomb);
+
+        assert!(matches!(
+            decode_roster(&bytes, None),
+            Err(ScheduleFileError::InvalidFormat)
+        ));
+        // Solo se descomprime hasta un byte despues del tamaño declarado
+        assert_eq!(decompress(&bomb, 1024).unwrap().len(), 1025);
+    }
+}
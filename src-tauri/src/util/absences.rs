@@ -0,0 +1,342 @@
+/*
+    Ausencias y suplencias.
+    Una ausencia marca a un profesor fuera entre dos fechas. Con ella se arman las clases
+    que hay que cubrir cada dia y los suplentes propuestos; al aceptar un suplente se guarda
+    un cambio solo para esa fecha en `schedule_overrides`, el horario base no se modifica.
+*/
+
+use futures::TryStreamExt;
+
+use crate::db::{AppState, DbPool};
+use crate::engine::{
+    format_date, parse_date, plan_cover, school_day, substitutes_for, Absence, CoverNeed,
+    ScheduleOverride,
+};
+use crate::util::snapshot::load_snapshot;
+
+use super::audit;
+use super::overrides::{fetch_date_changes, save_override, OverrideEdit};
+use super::terms::ACTIVE_TERM;
+
+/// Funcion para registrar la ausencia de un profesor
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `teacher_id` - Profesor que falta
+/// * `start_date` - Primer dia de la ausencia ("AAAA-MM-DD")
+/// * `end_date` - Ultimo dia de la ausencia ("AAAA-MM-DD"), el mismo dia si no se manda
+/// * `reason` - Motivo (opcional)
+/// Retorna la ausencia creada
+#[tauri::command(rename_all = "snake_case")]
+pub async fn create_absence(
+    pool: tauri::State<'_, AppState>,
+    teacher_id: i16,
+    start_date: String,
+    end_date: Option<String>,
+    reason: Option<String>,
+) -> Result<Absence, String> {
+    let start = parse_date(&start_date)?;
+    let end = match end_date {
+        Some(end_date) => parse_date(&end_date)?,
+        None => start,
+    };
+    if end < start {
+        return Err("The absence ends before it starts".to_string());
+    }
+
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let teacher_exists: Option<i64> = sqlx::query_scalar("SELECT id FROM teachers WHERE id = ?1")
+        .bind(teacher_id)
+        .fetch_optional(&mut tx)
+        .await
+        .map_err(|e| format!("Failed to get teacher: {}", e))?;
+    if teacher_exists.is_none() {
+        return Err(format!("Teacher {} does not exist", teacher_id));
+    }
+
+    let id = sqlx::query(&format!(
+        "INSERT INTO absences (teacher_id, start_date, end_date, reason, created_at, term_id)
+        VALUES (?1, ?2, ?3, ?4, ?5, {})",
+        ACTIVE_TERM
+    ))
+    .bind(teacher_id)
+    .bind(format_date(start))
+    .bind(format_date(end))
+    .bind(reason.as_deref().map(str::trim).filter(|r| !r.is_empty()))
+    .bind(chrono::Utc::now().timestamp())
+    .execute(&mut tx)
+    .await
+    .map_err(|e| format!("Failed to create absence: {}", e))?
+    .last_insert_rowid();
+
+    let new_value = audit::row_json(&mut tx, "absences", id).await?;
+    audit::record(
+        &mut tx,
+        audit::ABSENCE,
+        Some(id),
+        "create",
+        None,
+        new_value.as_ref(),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    fetch_absence(&pool.db, id).await
+}
+
+/// Funcion para obtener las ausencias, de la mas reciente a la mas antigua
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `from` - Solo las que terminan en o despues de esta fecha (opcional)
+/// * `to` - Solo las que empiezan en o antes de esta fecha (opcional)
+#[tauri::command]
+pub async fn get_absences(
+    pool: tauri::State<'_, AppState>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<Absence>, String> {
+    let from = from
+        .as_deref()
+        .map(parse_date)
+        .transpose()?
+        .map(format_date);
+    let to = to.as_deref().map(parse_date).transpose()?.map(format_date);
+
+    sqlx::query_as::<_, Absence>(&format!(
+        "SELECT id, teacher_id, start_date, end_date, reason, created_at
+        FROM absences
+        WHERE term_id = {}
+          AND (?1 IS NULL OR end_date >= ?1)
+          AND (?2 IS NULL OR start_date <= ?2)
+        ORDER BY start_date DESC, id DESC",
+        ACTIVE_TERM
+    ))
+    .bind(from)
+    .bind(to)
+    .fetch(&pool.db)
+    .try_collect()
+    .await
+    .map_err(|e| format!("Failed to get absences: {}", e))
+}
+
+/// Funcion para borrar una ausencia junto con las suplencias que se aceptaron para ella
+#[tauri::command]
+pub async fn delete_absence(pool: tauri::State<'_, AppState>, id: i64) -> Result<(), String> {
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let missing = || format!("Absence {} does not exist", id);
+    let old_value = audit::row_json(&mut tx, "absences", id)
+        .await?
+        .ok_or_else(missing)?;
+
+    let deleted = sqlx::query(&format!(
+        "DELETE FROM absences WHERE id = ?1 AND term_id = {}",
+        ACTIVE_TERM
+    ))
+    .bind(id)
+    .execute(&mut tx)
+    .await
+    .map_err(|e| format!("Failed to delete absence: {}", e))?;
+    if deleted.rows_affected() == 0 {
+        return Err(missing());
+    }
+
+    sqlx::query("DELETE FROM schedule_overrides WHERE absence_id = ?1")
+        .bind(id)
+        .execute(&mut tx)
+        .await
+        .map_err(|e| format!("Failed to delete substitutions: {}", e))?;
+
+    audit::record(
+        &mut tx,
+        audit::ABSENCE,
+        Some(id),
+        "delete",
+        Some(&old_value),
+        None,
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(())
+}
+
+/// Funcion para obtener las clases que deja sin cubrir una ausencia, dia por dia,
+/// con los suplentes propuestos para cada una
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `absence_id` - Ausencia a cubrir
+/// Los suplentes vienen ordenados: primero los que pueden dar la materia, despues
+/// los de menor carga y los que ganan menos huecos
+#[tauri::command(rename_all = "snake_case")]
+pub async fn plan_substitutes(
+    pool: tauri::State<'_, AppState>,
+    absence_id: i64,
+) -> Result<Vec<CoverNeed>, String> {
+    let absence = fetch_absence(&pool.db, absence_id).await?;
+    let snapshot = load_snapshot(&pool.db).await?;
+    let absences = fetch_absences_between(&pool.db, &absence.start_date, &absence.end_date).await?;
+    let changes = fetch_date_changes(&pool.db, &absence.start_date, &absence.end_date).await?;
+
+    plan_cover(&snapshot, &absence, &absences, &changes)
+}
+
+/// Funcion para aceptar un suplente para una clase de una ausencia
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `absence_id` - Ausencia que se cubre
+/// * `date` - Fecha de la clase ("AAAA-MM-DD")
+/// * `group_id` - Grupo de la clase
+/// * `module_index` - Modulo de la clase (empieza en 0)
+/// * `teacher_id` - Suplente, `None` para marcar que la clase no se da ese dia
+/// * `classroom_id` - Aula para ese dia, si no se manda se usa la del horario
+/// La materia se mantiene; si ya habia un suplente para esa clase se reemplaza
+#[tauri::command(rename_all = "snake_case")]
+pub async fn accept_substitution(
+    pool: tauri::State<'_, AppState>,
+    absence_id: i64,
+    date: String,
+    group_id: i16,
+    module_index: i16,
+    teacher_id: Option<i16>,
+    classroom_id: Option<i16>,
+) -> Result<ScheduleOverride, String> {
+    let absence = fetch_absence(&pool.db, absence_id).await?;
+    let date = parse_date(&date)?;
+    let date_text = format_date(date);
+    if !absence.covers(&date_text) {
+        return Err(format!(
+            "{} is outside the absence ({} to {})",
+            date_text, absence.start_date, absence.end_date
+        ));
+    }
+
+    let snapshot = load_snapshot(&pool.db).await?;
+    let day = school_day(&snapshot.days, date)
+        .ok_or_else(|| format!("There are no classes on {}", date_text))?;
+    let lesson = snapshot
+        .assignments
+        .iter()
+        .find(|a| {
+            a.group_id == group_id
+                && a.day == day
+                && a.module_index == module_index
+                && a.teacher_id == absence.teacher_id
+        })
+        .cloned()
+        .ok_or_else(|| {
+            format!(
+                "{} has no class with {} on {} module {}",
+                snapshot.group_label(group_id),
+                snapshot.teacher_label(absence.teacher_id),
+                day,
+                module_index + 1
+            )
+        })?;
+
+    if let Some(teacher_id) = teacher_id {
+        let absences = fetch_absences_between(&pool.db, &date_text, &date_text).await?;
+        let changes = fetch_date_changes(&pool.db, &date_text, &date_text).await?;
+        let candidates = substitutes_for(&snapshot, &lesson, date, &absences, &changes);
+        if !candidates.iter().any(|c| c.teacher_id == teacher_id) {
+            return Err(format!(
+                "{} is not available on {} module {}",
+                snapshot.teacher_label(teacher_id),
+                date_text,
+                module_index + 1
+            ));
+        }
+    }
+
+    let edit = OverrideEdit {
+        date: date_text.clone(),
+        group_id,
+        module_index,
+        subject_id: Some(lesson.subject_id),
+        teacher_id,
+        classroom_id: classroom_id.or(Some(lesson.classroom_id)),
+        kind: Some(
+            if teacher_id.is_some() {
+                "substitution"
+            } else {
+                "cancelled"
+            }
+            .to_string(),
+        ),
+        note: None,
+    };
+    let saved = save_override(&pool.db, &snapshot, &edit, Some(absence_id)).await?;
+
+    println!(
+        "{} {} module {}: {}",
+        snapshot.group_label(group_id),
+        date_text,
+        module_index + 1,
+        teacher_id.map_or("cancelled".to_string(), |t| snapshot.teacher_label(t))
+    );
+
+    Ok(saved)
+}
+
+/// Funcion para quitar una suplencia aceptada, la clase vuelve a quedar sin cubrir
+#[tauri::command(rename_all = "snake_case")]
+pub async fn remove_substitution(
+    pool: tauri::State<'_, AppState>,
+    override_id: i64,
+) -> Result<(), String> {
+    audit::delete_recorded_in_active_term(
+        &pool.db,
+        "schedule_overrides",
+        audit::SCHEDULE_OVERRIDE,
+        override_id,
+    )
+    .await
+}
+
+/// Funcion que retorna una ausencia del periodo activo
+pub async fn fetch_absence(db: &DbPool, id: i64) -> Result<Absence, String> {
+    sqlx::query_as::<_, Absence>(&format!(
+        "SELECT id, teacher_id, start_date, end_date, reason, created_at
+        FROM absences WHERE id = ?1 AND term_id = {}",
+        ACTIVE_TERM
+    ))
+    .bind(id)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| format!("Failed to get absence: {}", e))?
+    .ok_or_else(|| format!("Absence {} does not exist", id))
+}
+
+/// Funcion que retorna las ausencias del periodo activo que tocan alguna fecha entre
+/// `from` y `to`
+pub async fn fetch_absences_between(
+    db: &DbPool,
+    from: &str,
+    to: &str,
+) -> Result<Vec<Absence>, String> {
+    sqlx::query_as::<_, Absence>(&format!(
+        "SELECT id, teacher_id, start_date, end_date, reason, created_at
+        FROM absences
+        WHERE term_id = {} AND end_date >= ?1 AND start_date <= ?2",
+        ACTIVE_TERM
+    ))
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await
+    .map_err(|e| format!("Failed to get absences: {}", e))
+}
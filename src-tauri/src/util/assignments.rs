@@ -1,25 +1,50 @@
-use crate::db::AppState;
+use crate::db::{AppState, DbPool};
+use crate::engine::{introduced_violations, CellValue, Severity, Snapshot, Violation};
+pub use crate::model::Assignment;
+use crate::util::history::{self, change_cell, read_cell, Tx};
+use crate::util::snapshot::load_snapshot;
 use futures::TryStreamExt; // Para poder usar try_next() en los streams
 use serde::{Deserialize, Serialize};
-use sqlx::{prelude::FromRow, Row};
+use sqlx::Row;
+use std::collections::BTreeMap;
 
-/// Estructura de la asignacion
-/// Se utiliza para mapear los datos que van y vienen de la base de datos
-#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
-pub struct Assignment {
-    pub id: Option<i16>,
+/// Cambio a una celda del horario dentro de un lote
+/// Sin materia o sin profesor la celda se vacia
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssignmentEdit {
     pub group_id: i16,
     pub day: String,
     pub module_index: i16,
-    pub subject_id: i16,
-    pub teacher_id: i16,
-    pub classroom_id: i16,
-    pub subject_name: String,
-    pub subject_shorten: String,
-    pub subject_color: String,
+    pub subject_id: Option<i16>,
+    pub teacher_id: Option<i16>,
+    pub classroom_id: Option<i16>,
+}
+
+impl AssignmentEdit {
+    /// Valor que queda en la celda, `None` si se vacia
+    pub fn value(&self) -> Option<CellValue> {
+        Some(CellValue {
+            subject_id: self.subject_id?,
+            teacher_id: self.teacher_id?,
+            classroom_id: self.classroom_id.unwrap_or_default(),
+        })
+    }
+}
+
+/// Resultado de guardar cambios al horario
+/// Si `saved` es falso no se cambio nada y `violations` dice por que
+/// Si es verdadero `violations` tiene las violaciones blandas que se aceptaron con `force`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveResult {
+    pub saved: bool,
+    pub violations: Vec<Violation>,
 }
 
 /// Funcion que asigna una materia al modulo
+/// Si la celda ya tenia clase se conserva su aula
+/// El cambio se valida con las mismas restricciones que el generador: con violaciones
+/// duras no se guarda, con violaciones blandas solo se guarda si `force` es verdadero
+/// El cambio se guarda en el historial para poder deshacerlo
 #[allow(dead_code, unused)]
 // #[tauri::command]
 #[tauri::command(rename_all = "snake_case")]
@@ -30,27 +55,389 @@ pub async fn save_assignment(
     module_index: i32,
     subject_id: i32,
     teacher_id: i32,
-) -> Result<(), String> {
-    sqlx::query(
-        "
-        INSERT INTO assignments (group_id, day, module_index, subject_id, teacher_id)
-        VALUES (?1, ?2, ?3, ?4, ?5)
-        ON CONFLICT (group_id, day, module_index) DO UPDATE
-        SET subject_id = excluded.subject_id, teacher_id = excluded.teacher_id
-        ",
+    force: Option<bool>,
+) -> Result<SaveResult, String> {
+    let (group_id, module_index) = (group_id as i16, module_index as i16);
+    let snapshot = load_snapshot(&pool.db).await?;
+
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let classroom_id = read_cell(&mut tx, group_id, day, module_index)
+        .await?
+        .map(|c| c.classroom_id);
+
+    let edit = AssignmentEdit {
+        group_id,
+        day: day.to_string(),
+        module_index,
+        subject_id: Some(subject_id as i16),
+        teacher_id: Some(teacher_id as i16),
+        classroom_id,
+    };
+
+    let result = apply_checked(
+        &mut tx,
+        &snapshot,
+        &[edit],
+        force.unwrap_or(false),
+        "Asignar materia",
     )
-    .bind(group_id)
-    .bind(day)
-    .bind(module_index)
-    .bind(subject_id)
-    .bind(teacher_id)
-    .execute(&pool.db)
     .await
     .map_err(|e| format!("Error creating the assignment: {}", e))?;
 
+    if result.saved {
+        tx.commit()
+            .await
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    }
+
+    Ok(result)
+}
+
+/// Funcion para cambiar varias celdas a la vez
+/// Todo se aplica en una sola transaccion y se deshace como un solo paso
+/// Se valida igual que `save_assignment`: se guarda todo o nada
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `edits` - Celdas a cambiar
+/// * `label` - Descripcion para el historial (opcional)
+/// * `force` - Guardar aunque haya violaciones blandas (opcional)
+#[tauri::command]
+pub async fn save_assignments(
+    pool: tauri::State<'_, AppState>,
+    edits: Vec<AssignmentEdit>,
+    label: Option<String>,
+    force: Option<bool>,
+) -> Result<SaveResult, String> {
+    let snapshot = load_snapshot(&pool.db).await?;
+
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let result = apply_checked(
+        &mut tx,
+        &snapshot,
+        &edits,
+        force.unwrap_or(false),
+        label.as_deref().unwrap_or("Editar varias clases"),
+    )
+    .await?;
+
+    if result.saved {
+        tx.commit()
+            .await
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    }
+
+    Ok(result)
+}
+
+/// Funcion para mover una clase (arrastrar y soltar)
+/// Se mueve todo el bloque de modulos seguidos con la misma materia y profesor,
+/// cada modulo con su aula; el modulo tomado queda en el destino
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `group_id` - Grupo de la clase
+/// * `day` - Dia y `module_index` modulo de la celda que se toma
+/// * `to_day` - Dia y `to_module_index` modulo donde se suelta
+/// * `force` - Guardar aunque haya violaciones blandas (opcional)
+/// Retorna error si el bloque no cabe o cae sobre otra clase (para eso esta `swap_assignments`)
+#[tauri::command(rename_all = "snake_case")]
+pub async fn move_assignment(
+    pool: tauri::State<'_, AppState>,
+    group_id: i16,
+    day: String,
+    module_index: i16,
+    to_day: String,
+    to_module_index: i16,
+    force: Option<bool>,
+) -> Result<SaveResult, String> {
+    let snapshot = load_snapshot(&pool.db).await?;
+
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let schedule = history::read_schedule(&mut tx).await?;
+    let block = block_at(&schedule, group_id, &day, module_index)
+        .ok_or_else(|| format!("There is no lesson on {} module {}", day, module_index + 1))?;
+
+    // El bloque conserva su forma: el modulo tomado cae en `to_module_index`
+    let start = to_module_index - (module_index - block[0].module_index);
+    check_fits(&snapshot, &to_day, start, block.len() as i16)?;
+
+    let mut cells: BTreeMap<(String, i16), Option<CellValue>> = BTreeMap::new();
+    for a in &block {
+        cells.insert((a.day.clone(), a.module_index), None);
+    }
+    for (offset, a) in block.iter().enumerate() {
+        let target = (to_day.clone(), start + offset as i16);
+        if !cells.contains_key(&target) && cell_taken(&schedule, group_id, &target) {
+            return Err(format!(
+                "Group {} already has a lesson on {} module {}",
+                snapshot.group_label(group_id),
+                target.0,
+                target.1 + 1
+            ));
+        }
+        cells.insert(target, Some(CellValue::from(*a)));
+    }
+
+    let edits = cell_edits(group_id, cells);
+    let result = apply_checked(
+        &mut tx,
+        &snapshot,
+        &edits,
+        force.unwrap_or(false),
+        "Mover clase",
+    )
+    .await?;
+
+    if result.saved {
+        tx.commit()
+            .await
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    }
+
+    Ok(result)
+}
+
+/// Funcion para intercambiar dos clases del mismo grupo
+/// Cada clase se mueve como bloque (igual que `move_assignment`): el bloque de la
+/// celda `a` empieza donde empezaba el de la celda `b` y al reves
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `group_id` - Grupo de las dos clases
+/// * `day_a`, `module_a` - Celda de la primera clase
+/// * `day_b`, `module_b` - Celda de la segunda clase
+/// * `force` - Guardar aunque haya violaciones blandas (opcional)
+#[tauri::command(rename_all = "snake_case")]
+pub async fn swap_assignments(
+    pool: tauri::State<'_, AppState>,
+    group_id: i16,
+    day_a: String,
+    module_a: i16,
+    day_b: String,
+    module_b: i16,
+    force: Option<bool>,
+) -> Result<SaveResult, String> {
+    let snapshot = load_snapshot(&pool.db).await?;
+
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let schedule = history::read_schedule(&mut tx).await?;
+    let missing =
+        |day: &str, module: i16| format!("There is no lesson on {} module {}", day, module + 1);
+    let block_a =
+        block_at(&schedule, group_id, &day_a, module_a).ok_or_else(|| missing(&day_a, module_a))?;
+    let block_b =
+        block_at(&schedule, group_id, &day_b, module_b).ok_or_else(|| missing(&day_b, module_b))?;
+
+    if block_a[0].day == block_b[0].day && block_a[0].module_index == block_b[0].module_index {
+        return Err("Cannot swap a lesson with itself".to_string());
+    }
+
+    // Los dos bloques se quitan y cada uno se pone donde empezaba el otro
+    let mut cells: BTreeMap<(String, i16), Option<CellValue>> = BTreeMap::new();
+    for a in block_a.iter().chain(block_b.iter()) {
+        cells.insert((a.day.clone(), a.module_index), None);
+    }
+    let mut placed: Vec<(String, i16)> = Vec::new();
+    for (block, to) in [(&block_a, &block_b[0]), (&block_b, &block_a[0])] {
+        check_fits(&snapshot, &to.day, to.module_index, block.len() as i16)?;
+        for (offset, a) in block.iter().enumerate() {
+            let target = (to.day.clone(), to.module_index + offset as i16);
+            let free = !placed.contains(&target)
+                && (cells.contains_key(&target) || !cell_taken(&schedule, group_id, &target));
+            if !free {
+                return Err(format!(
+                    "The lessons do not fit when swapped: group {} would have two lessons on {} module {}",
+                    snapshot.group_label(group_id),
+                    target.0,
+                    target.1 + 1
+                ));
+            }
+            placed.push(target.clone());
+            cells.insert(target, Some(CellValue::from(*a)));
+        }
+    }
+
+    let edits = cell_edits(group_id, cells);
+    let result = apply_checked(
+        &mut tx,
+        &snapshot,
+        &edits,
+        force.unwrap_or(false),
+        "Intercambiar clases",
+    )
+    .await?;
+
+    if result.saved {
+        tx.commit()
+            .await
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    }
+
+    Ok(result)
+}
+
+// Bloque de modulos seguidos del grupo con la misma materia y profesor que contiene la celda
+// Ordenado por modulo, `None` si la celda esta vacia
+fn block_at<'s>(
+    schedule: &'s [Assignment],
+    group_id: i16,
+    day: &str,
+    module_index: i16,
+) -> Option<Vec<&'s Assignment>> {
+    let cell = |module: i16| {
+        schedule
+            .iter()
+            .find(|a| a.group_id == group_id && a.day == day && a.module_index == module)
+    };
+    let origin = cell(module_index)?;
+    let same =
+        |a: &Assignment| a.subject_id == origin.subject_id && a.teacher_id == origin.teacher_id;
+
+    let mut first = module_index;
+    while let Some(a) = cell(first - 1).filter(|a| same(a)) {
+        first = a.module_index;
+    }
+    let mut block = Vec::new();
+    let mut module = first;
+    while let Some(a) = cell(module).filter(|a| same(a)) {
+        block.push(a);
+        module += 1;
+    }
+
+    Some(block)
+}
+
+// Checa que el dia exista y que el bloque quepa en los modulos del dia
+fn check_fits(snapshot: &Snapshot, day: &str, start: i16, len: i16) -> Result<(), String> {
+    if !snapshot.days.iter().any(|d| d == day) {
+        return Err(format!("{} is not a school day", day));
+    }
+    if start < 0 || start + len > snapshot.modules_per_day {
+        return Err(format!(
+            "A block of {} modules does not fit there ({} modules per day)",
+            len, snapshot.modules_per_day
+        ));
+    }
     Ok(())
 }
 
+fn cell_taken(schedule: &[Assignment], group_id: i16, (day, module_index): &(String, i16)) -> bool {
+    schedule
+        .iter()
+        .any(|a| a.group_id == group_id && a.day == *day && a.module_index == *module_index)
+}
+
+fn cell_edits(
+    group_id: i16,
+    cells: BTreeMap<(String, i16), Option<CellValue>>,
+) -> Vec<AssignmentEdit> {
+    cells
+        .into_iter()
+        .map(|((day, module_index), value)| AssignmentEdit {
+            group_id,
+            day,
+            module_index,
+            subject_id: value.as_ref().map(|v| v.subject_id),
+            teacher_id: value.as_ref().map(|v| v.teacher_id),
+            classroom_id: value.as_ref().map(|v| v.classroom_id),
+        })
+        .collect()
+}
+
+/// Funcion que valida y aplica cambios de celdas dentro de una transaccion
+/// Solo se reportan las violaciones que agregan los cambios (ver `introduced_violations`)
+/// Si hay violaciones duras, o blandas sin `force`, no escribe nada y `saved` queda en falso;
+/// si no, escribe las celdas y las guarda en el historial como una sola operacion
+/// El commit le toca a quien llama
+pub async fn apply_checked(
+    tx: &mut Tx<'_>,
+    snapshot: &Snapshot,
+    edits: &[AssignmentEdit],
+    force: bool,
+    label: &str,
+) -> Result<SaveResult, String> {
+    let before = history::read_schedule(tx).await?;
+
+    let mut after: Vec<Assignment> = before
+        .iter()
+        .filter(|a| {
+            !edits.iter().any(|e| {
+                e.group_id == a.group_id && e.day == a.day && e.module_index == a.module_index
+            })
+        })
+        .cloned()
+        .collect();
+    for edit in edits {
+        if let Some(value) = edit.value() {
+            after.push(Assignment {
+                id: None,
+                group_id: edit.group_id,
+                day: edit.day.clone(),
+                module_index: edit.module_index,
+                subject_id: value.subject_id,
+                teacher_id: value.teacher_id,
+                classroom_id: value.classroom_id,
+                subject_name: String::new(),
+                subject_shorten: String::new(),
+                subject_color: String::new(),
+            });
+        }
+    }
+
+    let violations = introduced_violations(snapshot, &before, &after);
+    let blocked = violations
+        .iter()
+        .any(|v| v.severity == Severity::Hard || !force);
+    if blocked {
+        println!(
+            "Assignment change rejected with {} violations",
+            violations.len()
+        );
+        return Ok(SaveResult {
+            saved: false,
+            violations,
+        });
+    }
+
+    let mut changes = Vec::with_capacity(edits.len());
+    for edit in edits {
+        changes.push(
+            change_cell(
+                tx,
+                edit.group_id,
+                &edit.day,
+                edit.module_index,
+                edit.value(),
+            )
+            .await?,
+        );
+    }
+    history::record(tx, label, &changes).await?;
+
+    Ok(SaveResult {
+        saved: true,
+        violations,
+    })
+}
+
 /// Funcion para obtener una materia al modulo
 /// Retorna la asignacion y el
 #[allow(dead_code, unused)]
@@ -90,6 +477,11 @@ pub async fn get_assignment(
 pub async fn get_all_assignments(
     pool: tauri::State<'_, AppState>,
 ) -> Result<Vec<Assignment>, String> {
+    fetch_all_assignments(&pool.db).await
+}
+
+/// Funcion que retorna todas las asignaciones directamente del pool
+pub async fn fetch_all_assignments(db: &DbPool) -> Result<Vec<Assignment>, String> {
     let result: Vec<Assignment> = sqlx::query_as::<_, Assignment>(
         r#"
         SELECT a.id, a.group_id, a.day, a.module_index, a.teacher_id, a.classroom_id,
@@ -98,7 +490,7 @@ pub async fn get_all_assignments(
         JOIN subjects s ON a.subject_id = s.id
         "#,
     )
-    .fetch(&pool.db)
+    .fetch(db)
     .try_collect()
     .await
     .map_err(|e| format!("An error occurred while getting the assignments: {}", e))?;
@@ -113,11 +505,25 @@ pub async fn delete_assignment(
     pool: tauri::State<'_, AppState>,
     assign_id: i32,
 ) -> Result<(), String> {
-    sqlx::query("DELETE FROM assignments WHERE id=?")
-        .bind(assign_id)
-        .execute(&pool.db)
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    // Si no existe no hay nada que borrar
+    let Some((group_id, day, module_index)) = history::cell_of(&mut tx, assign_id).await? else {
+        return Ok(());
+    };
+
+    let change = change_cell(&mut tx, group_id, &day, module_index, None)
         .await
         .map_err(|e| format!("Error deleting assignment: {}", e))?;
+    history::record(&mut tx, "Quitar materia", &[change]).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
 
     Ok(())
 }
@@ -0,0 +1,351 @@
+/*
+    Bitacora de cambios.
+    Cada comando que modifica profesores, materias, grupos, aulas, el horario, la
+    configuracion o los datos de la escuela agrega una entrada con la fecha, el usuario
+    (si se configuro uno), la entidad y los valores de antes y despues en JSON.
+*/
+
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::error::Error as SqlxError;
+use sqlx::{sqlite::SqliteRow, Column, Executor, FromRow, Row, Sqlite};
+
+use crate::db::{AppState, DbPool};
+use crate::engine::CellChange;
+
+use super::terms::ACTIVE_TERM;
+
+// Entidades que se registran en la bitacora
+pub use crate::model::{
+    ABSENCE, ASSIGNMENT, CLASSROOM, CONFIG, DATABASE, GROUP, HOLIDAY, RESOURCE_CLOSURE,
+    SCHEDULE_OVERRIDE, SCHOOL, STUDENT, SUBJECT, TEACHER, TERM,
+};
+
+// Llave de la tabla `config` donde se guarda el usuario actual
+const USER_KEY: &str = "audit_user";
+
+/// Entrada de la bitacora
+/// `action` es "create", "update" o "delete"; para el horario tambien "undo" y "redo"
+/// y para la base de datos "import" o "merge" al abrir un archivo
+/// En el horario `entity_id` es la operacion del historial y `description` su nombre
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub created_at: i64,
+    pub user_name: Option<String>,
+    pub entity: String,
+    pub entity_id: Option<i64>,
+    pub action: String,
+    pub description: Option<String>,
+    pub old_value: Option<Value>,
+    pub new_value: Option<Value>,
+}
+
+impl<'r> FromRow<'r, SqliteRow> for AuditEntry {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, SqlxError> {
+        let parse = |column: &str| -> Result<Option<Value>, SqlxError> {
+            let text: Option<String> = row.try_get(column)?;
+            Ok(text.and_then(|t| serde_json::from_str(&t).ok()))
+        };
+
+        Ok(AuditEntry {
+            id: row.try_get("id")?,
+            created_at: row.try_get("created_at")?,
+            user_name: row.try_get("user_name")?,
+            entity: row.try_get("entity")?,
+            entity_id: row.try_get("entity_id")?,
+            action: row.try_get("action")?,
+            description: row.try_get("description")?,
+            old_value: parse("old_value")?,
+            new_value: parse("new_value")?,
+        })
+    }
+}
+
+/// Filtro para consultar la bitacora, todos los campos son opcionales
+/// `from` y `to` son fechas UTC en segundos (`from` incluido, `to` excluido)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditFilter {
+    pub entity: Option<String>,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+/// Funcion para consultar la bitacora, de la entrada mas reciente a la mas antigua
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `filter` - Entidad y rango de fechas (opcional)
+/// Sin limite se regresan las ultimas 500 entradas
+#[tauri::command]
+pub async fn get_audit_log(
+    pool: tauri::State<'_, AppState>,
+    filter: Option<AuditFilter>,
+) -> Result<Vec<AuditEntry>, String> {
+    let filter = filter.unwrap_or_default();
+
+    sqlx::query_as::<_, AuditEntry>(
+        "SELECT id, created_at, user_name, entity, entity_id, action, description,
+            old_value, new_value
+        FROM audit_log
+        WHERE (?1 IS NULL OR entity = ?1)
+          AND (?2 IS NULL OR created_at >= ?2)
+          AND (?3 IS NULL OR created_at < ?3)
+        ORDER BY id DESC
+        LIMIT ?4",
+    )
+    .bind(filter.entity)
+    .bind(filter.from)
+    .bind(filter.to)
+    .bind(filter.limit.unwrap_or(500))
+    .fetch(&pool.db)
+    .try_collect()
+    .await
+    .map_err(|e| format!("Failed to get audit log: {}", e))
+}
+
+/// Funcion para cambiar el usuario que se guarda en la bitacora
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `name` - Nombre del usuario, `None` o vacio para no guardar usuario
+#[tauri::command]
+pub async fn set_audit_user(
+    pool: tauri::State<'_, AppState>,
+    name: Option<String>,
+) -> Result<(), String> {
+    match name.as_deref().map(str::trim).filter(|n| !n.is_empty()) {
+        Some(name) => sqlx::query(
+            "INSERT INTO config (key, value) VALUES (?1, ?2)
+            ON CONFLICT(key) DO UPDATE SET value = ?2",
+        )
+        .bind(USER_KEY)
+        .bind(name)
+        .execute(&pool.db)
+        .await
+        .map_err(|e| format!("Failed saving audit user: {}", e))?,
+        None => sqlx::query("DELETE FROM config WHERE key = ?1")
+            .bind(USER_KEY)
+            .execute(&pool.db)
+            .await
+            .map_err(|e| format!("Failed saving audit user: {}", e))?,
+    };
+
+    Ok(())
+}
+
+/// Funcion para consultar el usuario que se guarda en la bitacora
+#[tauri::command]
+pub async fn get_audit_user(pool: tauri::State<'_, AppState>) -> Result<Option<String>, String> {
+    sqlx::query_scalar("SELECT value FROM config WHERE key = ?1")
+        .bind(USER_KEY)
+        .fetch_optional(&pool.db)
+        .await
+        .map_err(|e| format!("Failed to get audit user: {}", e))
+}
+
+/// Funcion para agregar una entrada a la bitacora
+/// Se puede llamar con el pool o dentro de una transaccion
+/// El usuario se toma de la configuracion
+pub async fn record<'c, E>(
+    db: E,
+    entity: &str,
+    entity_id: Option<i64>,
+    action: &str,
+    old_value: Option<&Value>,
+    new_value: Option<&Value>,
+) -> Result<(), String>
+where
+    E: Executor<'c, Database = Sqlite>,
+{
+    insert(db, entity, entity_id, action, None, old_value, new_value).await
+}
+
+/// Funcion para registrar en la bitacora una operacion del historial del horario
+/// Guarda todas las celdas que cambiaron en una sola entrada
+pub async fn record_schedule<'c, E>(
+    db: E,
+    operation_id: i64,
+    action: &str,
+    label: &str,
+    changes: &[&CellChange],
+) -> Result<(), String>
+where
+    E: Executor<'c, Database = Sqlite>,
+{
+    let cells = |after: bool| {
+        Value::Array(
+            changes
+                .iter()
+                .map(|c| {
+                    let value = if after { &c.after } else { &c.before };
+                    json!({
+                        "group_id": c.group_id,
+                        "day": c.day,
+                        "module_index": c.module_index,
+                        "subject_id": value.as_ref().map(|v| v.subject_id),
+                        "teacher_id": value.as_ref().map(|v| v.teacher_id),
+                        "classroom_id": value.as_ref().map(|v| v.classroom_id),
+                    })
+                })
+                .collect(),
+        )
+    };
+
+    insert(
+        db,
+        ASSIGNMENT,
+        Some(operation_id),
+        action,
+        Some(label),
+        Some(&cells(false)),
+        Some(&cells(true)),
+    )
+    .await
+}
+
+async fn insert<'c, E>(
+    db: E,
+    entity: &str,
+    entity_id: Option<i64>,
+    action: &str,
+    description: Option<&str>,
+    old_value: Option<&Value>,
+    new_value: Option<&Value>,
+) -> Result<(), String>
+where
+    E: Executor<'c, Database = Sqlite>,
+{
+    sqlx::query(
+        "INSERT INTO audit_log
+            (created_at, user_name, entity, entity_id, action, description, old_value, new_value)
+        VALUES (?1, (SELECT value FROM config WHERE key = ?2), ?3, ?4, ?5, ?6, ?7, ?8)",
+    )
+    .bind(chrono::Utc::now().timestamp())
+    .bind(USER_KEY)
+    .bind(entity)
+    .bind(entity_id)
+    .bind(action)
+    .bind(description)
+    .bind(old_value.map(Value::to_string))
+    .bind(new_value.map(Value::to_string))
+    .execute(db)
+    .await
+    .map_err(|e| format!("Failed to update audit log: {}", e))?;
+
+    Ok(())
+}
+
+/// Funcion que borra un registro por id y lo registra en la bitacora con sus valores,
+/// las dos cosas en una misma transaccion
+/// Falla si el registro no existe
+pub async fn delete_recorded(
+    db: &DbPool,
+    table: &str,
+    entity: &str,
+    id: i64,
+) -> Result<(), String> {
+    delete_recorded_where(db, table, entity, id, "").await
+}
+
+/// Igual que `delete_recorded` para las tablas con `term_id`
+/// Falla si el registro no existe o es de otro periodo
+pub async fn delete_recorded_in_active_term(
+    db: &DbPool,
+    table: &str,
+    entity: &str,
+    id: i64,
+) -> Result<(), String> {
+    let filter = format!("AND term_id = {}", ACTIVE_TERM);
+    delete_recorded_where(db, table, entity, id, &filter).await
+}
+
+// `filter` se agrega al `WHERE id = ?1` del borrado
+async fn delete_recorded_where(
+    db: &DbPool,
+    table: &str,
+    entity: &str,
+    id: i64,
+    filter: &str,
+) -> Result<(), String> {
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let missing = || format!("Can't delete {} {}, it does not exist", entity, id);
+    let old_value = row_json(&mut tx, table, id).await?.ok_or_else(missing)?;
+
+    let deleted = sqlx::query(&format!("DELETE FROM {} WHERE id = ?1 {}", table, filter))
+        .bind(id)
+        .execute(&mut tx)
+        .await
+        .map_err(|e| format!("Failed to delete {}: {}", entity, e))?;
+    if deleted.rows_affected() == 0 {
+        return Err(missing());
+    }
+
+    record(&mut tx, entity, Some(id), "delete", Some(&old_value), None).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))
+}
+
+/// Funcion que lee un registro de cualquier tabla como JSON (columna -> valor)
+/// Retorna `None` si no existe
+pub async fn row_json<'c, E>(db: E, table: &str, id: i64) -> Result<Option<Value>, String>
+where
+    E: Executor<'c, Database = Sqlite>,
+{
+    let row = sqlx::query(&format!("SELECT * FROM {} WHERE id = ?1", table))
+        .bind(id)
+        .fetch_optional(db)
+        .await
+        .map_err(|e| format!("Failed to read {} for audit log: {}", table, e))?;
+
+    Ok(row.map(|row| {
+        let mut object = serde_json::Map::new();
+        for column in row.columns() {
+            let i = column.ordinal();
+            let value = if let Ok(v) = row.try_get::<Option<i64>, _>(i) {
+                v.map(Value::from).unwrap_or(Value::Null)
+            } else if let Ok(v) = row.try_get::<f64, _>(i) {
+                Value::from(v)
+            } else if let Ok(v) = row.try_get::<String, _>(i) {
+                Value::from(v)
+            } else {
+                Value::Null
+            };
+            object.insert(column.name().to_string(), value);
+        }
+        Value::Object(object)
+    }))
+}
+
+/// Funcion que agrega al JSON de un registro los ids de una tabla relacionada
+/// (ej. las materias de un profesor). `query` recibe el id del registro en `?1`
+pub async fn with_links<'c, E>(
+    db: E,
+    value: Option<Value>,
+    field: &str,
+    query: &str,
+    id: i64,
+) -> Result<Option<Value>, String>
+where
+    E: Executor<'c, Database = Sqlite>,
+{
+    let Some(Value::Object(mut object)) = value else {
+        return Ok(value);
+    };
+
+    let ids: Vec<i64> = sqlx::query_scalar(query)
+        .bind(id)
+        .fetch_all(db)
+        .await
+        .map_err(|e| format!("Failed to read {} for audit log: {}", field, e))?;
+    object.insert(field.to_string(), Value::from(ids));
+
+    Ok(Some(Value::Object(object)))
+}
@@ -0,0 +1,126 @@
+/*
+    Consultas de disponibilidad.
+    Sirven para cubrir a un profesor o apartar un aula sin revisar la cuadricula a mano:
+    dado un dia y un rango de modulos regresan los profesores y aulas que estan libres,
+    o dado un grupo de profesores regresan los horarios en que todos pueden reunirse.
+*/
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::AppState;
+use crate::engine::{
+    common_free_slots, free_classrooms, free_teachers, ClassroomFilter, FreeClassroom, FreeTeacher,
+    MeetingSlot, Snapshot,
+};
+use crate::util::snapshot::load_snapshot;
+
+/// Profesores y aulas libres en un rango de modulos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FreeResources {
+    pub teachers: Vec<FreeTeacher>,
+    pub classrooms: Vec<FreeClassroom>,
+}
+
+/// Funcion para saber quien esta libre en un dia y rango de modulos
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `day` - Dia a revisar
+/// * `module_index` - Primer modulo del rango (empieza en 0)
+/// * `modules` - Cantidad de modulos del rango (1 si no se manda)
+/// * `subject_id` - Solo profesores que pueden dar esta materia (opcional)
+/// * `classroom_filter` - Tipo de aula y capacidad minima (opcional)
+/// Los profesores vienen ordenados por lo poco que se altera su dia (sin huecos nuevos)
+/// y las aulas de la que mejor se ajusta a la mas grande
+#[tauri::command(rename_all = "snake_case")]
+pub async fn find_free_resources(
+    pool: tauri::State<'_, AppState>,
+    day: String,
+    module_index: i16,
+    modules: Option<i16>,
+    subject_id: Option<i16>,
+    classroom_filter: Option<ClassroomFilter>,
+) -> Result<FreeResources, String> {
+    let snapshot = load_snapshot(&pool.db).await?;
+    let len = modules.unwrap_or(1);
+    check_range(&snapshot, &day, module_index, len)?;
+
+    let schedule = &snapshot.assignments;
+    Ok(FreeResources {
+        teachers: free_teachers(&snapshot, schedule, &day, module_index, len, subject_id),
+        classrooms: free_classrooms(
+            &snapshot,
+            schedule,
+            &day,
+            module_index,
+            len,
+            &classroom_filter.unwrap_or_default(),
+        ),
+    })
+}
+
+/// Funcion para buscar horarios de reunion en los que todos los profesores estan libres
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `teacher_ids` - Profesores que tienen que asistir
+/// * `modules` - Duracion de la reunion en modulos
+/// * `classroom_id` - Aula que tambien tiene que estar libre (opcional)
+/// * `limit` - Cantidad maxima de resultados (opcional)
+/// Primero vienen las ventanas pegadas a las clases de mas profesores
+#[tauri::command(rename_all = "snake_case")]
+pub async fn find_meeting_slots(
+    pool: tauri::State<'_, AppState>,
+    teacher_ids: Vec<i16>,
+    modules: i16,
+    classroom_id: Option<i16>,
+    limit: Option<usize>,
+) -> Result<Vec<MeetingSlot>, String> {
+    let snapshot = load_snapshot(&pool.db).await?;
+
+    if teacher_ids.is_empty() {
+        return Err("Select at least one teacher for the meeting".to_string());
+    }
+    if let Some(id) = teacher_ids
+        .iter()
+        .find(|&&id| snapshot.teacher(id).is_none())
+    {
+        return Err(format!("Teacher {} does not exist", id));
+    }
+    if let Some(id) = classroom_id.filter(|&id| snapshot.classroom(id).is_none()) {
+        return Err(format!("Classroom {} does not exist", id));
+    }
+    if modules < 1 || modules > snapshot.modules_per_day {
+        return Err(format!(
+            "A meeting of {} modules does not fit in a day ({} modules per day)",
+            modules, snapshot.modules_per_day
+        ));
+    }
+
+    let mut slots = common_free_slots(
+        &snapshot,
+        &snapshot.assignments,
+        &teacher_ids,
+        modules,
+        classroom_id,
+    );
+    if let Some(limit) = limit {
+        slots.truncate(limit);
+    }
+
+    Ok(slots)
+}
+
+// Checa que el dia exista y que el rango quepa en los modulos del dia
+fn check_range(snapshot: &Snapshot, day: &str, start: i16, len: i16) -> Result<(), String> {
+    if !snapshot.days.iter().any(|d| d == day) {
+        return Err(format!("{} is not a school day", day));
+    }
+    if len < 1 || start < 0 || start + len > snapshot.modules_per_day {
+        return Err(format!(
+            "Modules {} to {} are outside the day ({} modules per day)",
+            start + 1,
+            start + len,
+            snapshot.modules_per_day
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,296 @@
+/*
+    Calendario escolar.
+    El horario es una plantilla semanal; aqui se guardan los periodos (con fecha de inicio
+    y fin) y los festivos, y con ellos se convierte la plantilla en las clases de cada fecha
+    para saber cuantas sesiones de cada materia recibe realmente cada grupo.
+*/
+
+use futures::TryStreamExt;
+
+use crate::db::{AppState, DbPool};
+use crate::engine::{
+    count_sessions, expand_sessions, format_date, parse_date, DatedSession, Holiday, SessionCount,
+    Term,
+};
+use crate::util::snapshot::load_snapshot;
+
+use super::audit;
+use super::overrides::fetch_date_changes;
+
+/// Funcion para crear un periodo escolar
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `name` - Nombre del periodo (ej. "Semestre Agosto-Diciembre")
+/// * `start_date` - Primer dia del periodo ("AAAA-MM-DD")
+/// * `end_date` - Ultimo dia del periodo ("AAAA-MM-DD")
+/// Retorna el periodo creado
+#[tauri::command(rename_all = "snake_case")]
+pub async fn create_term(
+    pool: tauri::State<'_, AppState>,
+    name: String,
+    start_date: String,
+    end_date: String,
+) -> Result<Term, String> {
+    let (name, start, end) = check_range(&name, &start_date, &end_date)?;
+
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let id = sqlx::query(
+        "INSERT INTO terms (name, start_date, end_date, created_at) VALUES (?1, ?2, ?3, ?4)",
+    )
+    .bind(name)
+    .bind(start)
+    .bind(end)
+    .bind(chrono::Utc::now().timestamp())
+    .execute(&mut tx)
+    .await
+    .map_err(|e| format!("Failed to create term: {}", e))?
+    .last_insert_rowid();
+
+    let new_value = audit::row_json(&mut tx, "terms", id).await?;
+    audit::record(
+        &mut tx,
+        audit::TERM,
+        Some(id),
+        "create",
+        None,
+        new_value.as_ref(),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    fetch_term(&pool.db, id).await
+}
+
+/// Funcion para obtener los periodos escolares ordenados por fecha de inicio
+#[tauri::command]
+pub async fn get_terms(pool: tauri::State<'_, AppState>) -> Result<Vec<Term>, String> {
+    sqlx::query_as::<_, Term>(&format!("{} ORDER BY start_date, id", SELECT_TERMS))
+        .fetch(&pool.db)
+        .try_collect()
+        .await
+        .map_err(|e| format!("Failed to get terms: {}", e))
+}
+
+/// Funcion para borrar un periodo escolar junto con sus datos guardados
+/// El periodo activo no se puede borrar
+#[tauri::command]
+pub async fn delete_term(pool: tauri::State<'_, AppState>, id: i64) -> Result<(), String> {
+    if fetch_term(&pool.db, id).await?.active {
+        return Err("Can't delete the active term, switch to another term first".to_string());
+    }
+    audit::delete_recorded(&pool.db, "terms", audit::TERM, id).await
+}
+
+/// Funcion para registrar un dia festivo o un cierre de la escuela
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `name` - Motivo (ej. "Dia de muertos")
+/// * `start_date` - Primer dia sin clases ("AAAA-MM-DD")
+/// * `end_date` - Ultimo dia sin clases ("AAAA-MM-DD"), el mismo dia si no se manda
+/// Retorna el festivo creado
+#[tauri::command(rename_all = "snake_case")]
+pub async fn create_holiday(
+    pool: tauri::State<'_, AppState>,
+    name: String,
+    start_date: String,
+    end_date: Option<String>,
+) -> Result<Holiday, String> {
+    let end_date = end_date.unwrap_or_else(|| start_date.clone());
+    let (name, start, end) = check_range(&name, &start_date, &end_date)?;
+
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let id = sqlx::query(
+        "INSERT INTO holidays (name, start_date, end_date, created_at) VALUES (?1, ?2, ?3, ?4)",
+    )
+    .bind(name)
+    .bind(start)
+    .bind(end)
+    .bind(chrono::Utc::now().timestamp())
+    .execute(&mut tx)
+    .await
+    .map_err(|e| format!("Failed to create holiday: {}", e))?
+    .last_insert_rowid();
+
+    let new_value = audit::row_json(&mut tx, "holidays", id).await?;
+    audit::record(
+        &mut tx,
+        audit::HOLIDAY,
+        Some(id),
+        "create",
+        None,
+        new_value.as_ref(),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    sqlx::query_as::<_, Holiday>(&format!("{} WHERE id = ?1", SELECT_HOLIDAYS))
+        .bind(id)
+        .fetch_one(&pool.db)
+        .await
+        .map_err(|e| format!("Failed to get holiday: {}", e))
+}
+
+/// Funcion para obtener los festivos ordenados por fecha
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `term_id` - Solo los que caen dentro de este periodo (opcional)
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_holidays(
+    pool: tauri::State<'_, AppState>,
+    term_id: Option<i64>,
+) -> Result<Vec<Holiday>, String> {
+    match term_id {
+        Some(term_id) => {
+            let term = fetch_term(&pool.db, term_id).await?;
+            fetch_holidays_between(&pool.db, &term.start_date, &term.end_date).await
+        }
+        None => {
+            sqlx::query_as::<_, Holiday>(&format!("{} ORDER BY start_date, id", SELECT_HOLIDAYS))
+                .fetch(&pool.db)
+                .try_collect()
+                .await
+                .map_err(|e| format!("Failed to get holidays: {}", e))
+        }
+    }
+}
+
+/// Funcion para borrar un dia festivo
+#[tauri::command]
+pub async fn delete_holiday(pool: tauri::State<'_, AppState>, id: i64) -> Result<(), String> {
+    audit::delete_recorded(&pool.db, "holidays", audit::HOLIDAY, id).await
+}
+
+/// Funcion para obtener las clases de cada fecha de un periodo
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `term_id` - Periodo a recorrer
+/// * `group_id` - Solo las clases de este grupo (opcional)
+/// * `teacher_id` - Solo las clases de este profesor (opcional)
+/// Se saltan los festivos y se aplican los cambios por fecha y los cierres
+/// Solo se puede con el periodo activo, el horario de los periodos archivados no se recorre
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_dated_sessions(
+    pool: tauri::State<'_, AppState>,
+    term_id: i64,
+    group_id: Option<i16>,
+    teacher_id: Option<i16>,
+) -> Result<Vec<DatedSession>, String> {
+    let term = fetch_calendar_term(&pool.db, term_id).await?;
+    let snapshot = load_snapshot(&pool.db).await?;
+    let holidays = fetch_holidays_between(&pool.db, &term.start_date, &term.end_date).await?;
+    let changes = fetch_date_changes(&pool.db, &term.start_date, &term.end_date).await?;
+
+    Ok(expand_sessions(&snapshot, &term, &holidays, &changes)?
+        .into_iter()
+        .filter(|s| group_id.map_or(true, |g| s.lesson.group_id == g))
+        .filter(|s| teacher_id.map_or(true, |t| s.lesson.teacher_id == t))
+        .collect())
+}
+
+/// Funcion para contar las sesiones de cada materia por grupo en un periodo
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `term_id` - Periodo a contar
+/// * `group_id` - Solo las materias de este grupo (opcional)
+/// Para cada materia retorna las sesiones esperadas (modulos por semana por semanas del
+/// periodo), las que da el horario semanal y las que realmente se dan
+/// Igual que `get_dated_sessions`, solo acepta el periodo activo
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_session_counts(
+    pool: tauri::State<'_, AppState>,
+    term_id: i64,
+    group_id: Option<i16>,
+) -> Result<Vec<SessionCount>, String> {
+    let term = fetch_calendar_term(&pool.db, term_id).await?;
+    let snapshot = load_snapshot(&pool.db).await?;
+    let holidays = fetch_holidays_between(&pool.db, &term.start_date, &term.end_date).await?;
+    let changes = fetch_date_changes(&pool.db, &term.start_date, &term.end_date).await?;
+
+    Ok(count_sessions(&snapshot, &term, &holidays, &changes)?
+        .into_iter()
+        .filter(|c| group_id.map_or(true, |g| c.group_id == g))
+        .collect())
+}
+
+const SELECT_TERMS: &str =
+    "SELECT id, name, start_date, end_date, active, previous_term_id, archived_at
+    FROM terms";
+
+const SELECT_HOLIDAYS: &str = "SELECT id, name, start_date, end_date FROM holidays";
+
+/// Funcion que retorna un periodo escolar
+pub async fn fetch_term(db: &DbPool, id: i64) -> Result<Term, String> {
+    sqlx::query_as::<_, Term>(&format!("{} WHERE id = ?1", SELECT_TERMS))
+        .bind(id)
+        .fetch_optional(db)
+        .await
+        .map_err(|e| format!("Failed to get term: {}", e))?
+        .ok_or_else(|| format!("Term {} does not exist", id))
+}
+
+/// Funcion que retorna un periodo solo si es el activo
+/// El horario, los cambios por fecha y los cierres se leen del periodo activo, asi que
+/// usar las fechas de otro periodo mezclaria datos de los dos
+async fn fetch_calendar_term(db: &DbPool, id: i64) -> Result<Term, String> {
+    let term = fetch_term(db, id).await?;
+    if !term.active {
+        return Err(format!(
+            "Term {} is not the active term, switch to it to see its calendar",
+            term.name
+        ));
+    }
+    Ok(term)
+}
+
+/// Funcion que retorna los festivos que tocan alguna fecha entre `from` y `to`
+pub async fn fetch_holidays_between(
+    db: &DbPool,
+    from: &str,
+    to: &str,
+) -> Result<Vec<Holiday>, String> {
+    sqlx::query_as::<_, Holiday>(&format!(
+        "{} WHERE end_date >= ?1 AND start_date <= ?2 ORDER BY start_date, id",
+        SELECT_HOLIDAYS
+    ))
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await
+    .map_err(|e| format!("Failed to get holidays: {}", e))
+}
+
+/// Funcion que revisa el nombre y el rango de fechas de un periodo o festivo
+/// Retorna el nombre sin espacios y las fechas ya normalizadas ("AAAA-MM-DD")
+pub fn check_range<'a>(
+    name: &'a str,
+    start_date: &str,
+    end_date: &str,
+) -> Result<(&'a str, String, String), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("The name can't be empty".to_string());
+    }
+    let start = parse_date(start_date)?;
+    let end = parse_date(end_date)?;
+    if end < start {
+        return Err(format!("{} ends before it starts", name));
+    }
+
+    Ok((name, format_date(start), format_date(end)))
+}
@@ -0,0 +1,45 @@
+/*
+    Revision del horario actual.
+    Corre todas las restricciones del motor sobre la tabla `assignments` y regresa
+    cada problema encontrado para mostrarlo en un panel de problemas.
+*/
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::AppState;
+use crate::engine::{validate_schedule, Severity, Violation};
+use crate::util::snapshot::load_snapshot;
+
+/// Problemas encontrados en el horario, primero los duros
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConflictReport {
+    pub hard: usize,
+    pub soft: usize,
+    pub violations: Vec<Violation>,
+}
+
+/// Funcion para buscar todos los problemas del horario actual
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// Revisa profesores, grupos y aulas con dos clases a la vez, aulas con menos capacidad
+/// que el grupo, profesores con mas modulos que horas comisionadas, grupos arriba de su
+/// maximo diario, materias con mas o menos modulos que `required_modules` y profesores
+/// que no tienen registrada la materia
+#[tauri::command]
+pub async fn find_conflicts(pool: tauri::State<'_, AppState>) -> Result<ConflictReport, String> {
+    let snapshot = load_snapshot(&pool.db).await?;
+
+    let mut violations = validate_schedule(&snapshot, &snapshot.assignments);
+    violations.sort_by_key(|v| v.severity != Severity::Hard);
+
+    let hard = violations
+        .iter()
+        .filter(|v| v.severity == Severity::Hard)
+        .count();
+
+    Ok(ConflictReport {
+        hard,
+        soft: violations.len() - hard,
+        violations,
+    })
+}
@@ -1,74 +1,70 @@
 use crate::{
     class::{
-        classrooms::{create_classrooms, get_classrooms, Classroom},
-        groups::{create_groups, get_groups, Group, GroupSubjects},
-        subjects::{
-            create_subjects, get_subjects, get_subjects_with_teachers, link_subject_to_teacher,
-            Subject, SubjectWithTeacher,
-        },
-        teachers::{create_teachers, get_all_teachers, Teacher},
+        classrooms::Classroom,
+        groups::{Group, GroupSubjects, Student},
+        subjects::Subject,
+        teachers::{Teacher, TeacherSubjects},
+    },
+    db::{AppState, DbPool, DB_NAME},
+    engine::{plan_merge, MergeOptions, MergeReport},
+    schedule_file::{
+        read_schedule_file, write_schedule_file, RosterOptions, ScheduleData, ScheduleFileError,
+        ScheduleResult,
     },
-    db::{AppState, DB_NAME},
-    util::assignments::save_assignment,
 };
-use bincode;
-// use futures::{FutureExt, TryFutureExt};
 use serde::{Deserialize, Serialize};
+use sqlx::Row;
 use std::{
-    fs::{self, File},
-    io::{Read, Write},
+    collections::BTreeSet,
+    fs,
     path::{Path, PathBuf},
 };
-use tauri::{api::dialog, App, Manager, Window};
-use thiserror::Error;
-
-use super::assignments::{get_all_assignments, Assignment};
-
-#[derive(Debug, Error)]
-pub enum ScheduleFileError {
-    #[error("IO error: {0}")]
-    IoError(#[from] std::io::Error),
-
-    #[error("Database error: {0}")]
-    DatabaseError(#[from] sqlx::Error),
-
-    #[error("Serialization error: {0}")]
-    SerializationError(#[from] bincode::Error),
-
-    #[error("Invalid file format")]
-    InvalidFormat,
-
-    #[error("Unsupported version: {0}")]
-    UnsuportedVersion(u16),
-}
-
-// Alias del resultado
-type ScheduleResult<T> = std::result::Result<T, ScheduleFileError>;
-
-// File format header
-// Header del formato de archivo
-#[derive(Serialize, Deserialize, Debug)]
-struct ScheduleFileHeader {
-    magic: [u8; 4],  // "SCHA" in ASCII
-    version: u16,    // File format version
-    created_at: i64, // UTC timestamp
-    checksum: u32,   // Simple checksum for integrity verification
+use tauri::{api::dialog, Window};
+
+use super::assignments::fetch_all_assignments;
+use super::audit;
+use super::history::Tx;
+use super::settings::{load_config, Config, SchoolInfo};
+
+/// Resumen de lo que se importo de un archivo
+/// `config` y `school` indican si se sobrescribieron la configuracion del horario y los
+/// datos de la escuela; `backup` es la copia de la base de datos anterior
+/// `discarded` son las versiones del horario, cambios por fecha, cierres, ausencias,
+/// periodos y festivos que se borraron: el archivo no los guarda y apuntan a los grupos,
+/// profesores y aulas anteriores (siguen en `backup`)
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ImportSummary {
+    pub subjects: usize,
+    pub teachers: usize,
+    pub teacher_subjects: usize,
+    pub groups: usize,
+    pub group_subjects: usize,
+    pub students: usize,
+    pub classrooms: usize,
+    pub assignments: usize,
+    pub config: bool,
+    pub school: bool,
+    pub discarded: u64,
+    pub backup: Option<String>,
 }
 
-// Tablas que se van a exportar en el archivo
-#[derive(Serialize, Deserialize, Debug)]
-struct ScheduleData {
-    subjects: Vec<Subject>,
-    teachers: Vec<Teacher>,
-    teacher_subjects: Vec<SubjectWithTeacher>,
-    groups: Vec<Group>,
-    classrooms: Vec<Classroom>,
-    assignments: Vec<Assignment>,
-    // group_subjects: Vec<GroupSubjects>,
-}
-
-fn simple_checksum(data: &[u8]) -> u32 {
-    data.iter().fold(0u32, |acc, &x| acc.wrapping_add(x as u32))
+impl ImportSummary {
+    fn of(data: &ScheduleData) -> Self {
+        ImportSummary {
+            subjects: data.subjects.len(),
+            teachers: data.teachers.len(),
+            teacher_subjects: data.teacher_subjects.len(),
+            groups: data.groups.len(),
+            group_subjects: data.group_subjects.len(),
+            students: data.students.len(),
+            classrooms: data.classrooms.len(),
+            assignments: data.assignments.len(),
+            config: data.config.is_some(),
+            school: data.school.is_some(),
+            discarded: 0,
+            backup: None,
+        }
+    }
 }
 
 #[tauri::command]
@@ -80,12 +76,15 @@ pub async fn export_pdf_file() -> Option<String> {
         .map(|p| p.to_string_lossy().to_string())
 }
 
-/// Funcion para exportar el archivo (.roster)
+/// Funcion para exportar el archivo (.roster, .json o .yaml segun la extension elegida)
+/// Los .roster se comprimen a menos que `compress` sea falso y se cifran si se manda `password`
 #[tauri::command]
 pub async fn export_file(
     _handle: tauri::AppHandle,
     pool: tauri::State<'_, AppState>,
     _window: Window,
+    compress: Option<bool>,
+    password: Option<String>,
 ) -> Result<(), String> {
     // Selector de archivos del usuario
     // Utilizar canal para comunicar callback y async
@@ -94,6 +93,8 @@ pub async fn export_file(
     // TODO: Hacer un nombre predeterminado utilizando nombre de escuela
     dialog::FileDialogBuilder::new()
         .add_filter("Roster Files", &["roster"])
+        .add_filter("JSON", &["json"])
+        .add_filter("YAML", &["yaml", "yml"])
         .set_title("Guardar datos")
         .save_file(move |file_path| {
             tx.send(file_path).unwrap();
@@ -113,7 +114,11 @@ pub async fn export_file(
     };
 
     // Convierte el error personalizado a un String para que tauri lo pueda manejar
-    match export_file_impl(pool, &file_path).await {
+    let options = RosterOptions {
+        compress: compress.unwrap_or(true),
+        password,
+    };
+    match export_file_impl(pool, &file_path, &options).await {
         Ok(()) => Ok(()),
         Err(e) => Err(format!("Export error: {}", e)),
     }
@@ -123,108 +128,139 @@ pub async fn export_file(
 async fn export_file_impl(
     pool: tauri::State<'_, AppState>,
     output_path: &PathBuf,
+    options: &RosterOptions,
 ) -> ScheduleResult<()> {
-    let subjects = get_subjects(pool.clone())
+    let data = load_schedule_data(&pool.db)
         .await
-        .map_err(|_| ScheduleFileError::DatabaseError(sqlx::Error::RowNotFound))?;
+        .map_err(ScheduleFileError::DataError)?;
+
+    write_schedule_file(output_path, &data, options)
+}
 
-    let teachers = get_all_teachers(pool.clone())
+/// Funcion para leer todas las tablas de la escuela de la base de datos
+/// # Argumentos
+/// * `db` - Pool de la base de datos
+/// Las filas se leen ordenadas por id para que el mismo contenido genere el mismo archivo
+pub async fn load_schedule_data(db: &DbPool) -> Result<ScheduleData, String> {
+    let subjects = sqlx::query_as::<_, Subject>("SELECT * FROM subjects ORDER BY id")
+        .fetch_all(db)
         .await
-        .map_err(|e| ScheduleFileError::IoError(std::io::Error::new(std::io::ErrorKind::Other, e)))?
-        .into_iter()
-        .map(|(t, _)| t)
-        .collect();
+        .map_err(|e| format!("Failed to load subjects: {}", e))?;
 
-    let teacher_subjects = get_subjects_with_teachers(pool.clone())
+    let teachers = sqlx::query_as::<_, Teacher>("SELECT * FROM teachers ORDER BY id")
+        .fetch_all(db)
         .await
-        .map_err(|_| ScheduleFileError::DatabaseError(sqlx::Error::RowNotFound))?;
+        .map_err(|e| format!("Failed to load teachers: {}", e))?;
 
-    let groups = get_groups(pool.clone())
+    let teacher_subjects = sqlx::query_as::<_, TeacherSubjects>(
+        "SELECT teacher_id, subject_id FROM teacher_subjects ORDER BY teacher_id, subject_id",
+    )
+    .fetch_all(db)
+    .await
+    .map_err(|e| format!("Failed to load teacher subjects: {}", e))?;
+
+    let groups = sqlx::query_as::<_, Group>("SELECT * FROM groups ORDER BY id")
+        .fetch_all(db)
         .await
-        .map_err(|e| ScheduleFileError::IoError(std::io::Error::new(std::io::ErrorKind::Other, e)))?
-        .into_iter()
-        .map(|(g, _)| g)
-        .collect();
+        .map_err(|e| format!("Failed to load groups: {}", e))?;
+
+    let group_subjects = sqlx::query_as::<_, GroupSubjects>(
+        "SELECT group_id, subject_id FROM groups_subjects ORDER BY group_id, subject_id",
+    )
+    .fetch_all(db)
+    .await
+    .map_err(|e| format!("Failed to load group subjects: {}", e))?;
 
-    let classrooms = get_classrooms(pool.clone())
+    let students = sqlx::query_as::<_, Student>("SELECT * FROM students ORDER BY id")
+        .fetch_all(db)
         .await
-        .map_err(|_| ScheduleFileError::DatabaseError(sqlx::Error::RowNotFound))?;
+        .map_err(|e| format!("Failed to load students: {}", e))?;
 
-    let assignments = get_all_assignments(pool.clone())
+    let classrooms = sqlx::query_as::<_, Classroom>("SELECT * FROM classroom ORDER BY id")
+        .fetch_all(db)
         .await
-        .map_err(|_| ScheduleFileError::DatabaseError(sqlx::Error::RowNotFound))?;
+        .map_err(|e| format!("Failed to load classrooms: {}", e))?;
+
+    let mut assignments = fetch_all_assignments(db).await?;
+    assignments.sort_by_key(|a| a.id);
 
-    // let group_subjects = get_all_group_subjects(pool.clone()).await?;
+    let school = sqlx::query("SELECT name, logo_path FROM school WHERE id = 1")
+        .fetch_optional(db)
+        .await
+        .map_err(|e| format!("Failed to load school info: {}", e))?
+        .map(|row| SchoolInfo {
+            name: row.get("name"),
+            logo_path: row.get("logo_path"),
+        });
 
-    // Package data
-    let data = ScheduleData {
+    Ok(ScheduleData {
         subjects,
         teachers,
         teacher_subjects,
         groups,
+        group_subjects,
+        students,
         classrooms,
         assignments,
-        // group_subjects,
-    };
-
-    // Serializamos los datos utilizando bincode
-    let serialized_data = bincode::serialize(&data)?;
-
-    let checksum = simple_checksum(&serialized_data);
+        config: Some(load_config(db).await?),
+        school,
+    })
+}
 
-    let header = ScheduleFileHeader {
-        magic: *b"SCHA", // Bytes para identificar nuestra formato
-        version: 1,      // Version inicial
-        created_at: chrono::Utc::now().timestamp(),
-        checksum,
-    };
-    let serialized_header = bincode::serialize(&header)?;
+/// Error de `import_file`
+/// `kind` le dice a la interfaz si falta la contraseña o no es la correcta, asi puede
+/// volver a pedirla sin depender del texto de `message`
+#[derive(Serialize, Debug, Clone)]
+pub struct ImportError {
+    pub kind: ImportErrorKind,
+    pub message: String,
+}
 
-    // Crear el archivo
-    let mut file = File::create(output_path)?;
-    file.write_all(&serialized_header)?;
-    file.write_all(&serialized_data)?;
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportErrorKind {
+    PasswordRequired,
+    WrongPassword,
+    Other,
+}
 
-    Ok(())
+impl From<ScheduleFileError> for ImportError {
+    fn from(e: ScheduleFileError) -> Self {
+        let kind = match e {
+            ScheduleFileError::PasswordRequired => ImportErrorKind::PasswordRequired,
+            ScheduleFileError::WrongPassword => ImportErrorKind::WrongPassword,
+            _ => ImportErrorKind::Other,
+        };
+        ImportError {
+            kind,
+            message: format!("Import error: {}", e),
+        }
+    }
 }
 
-/// Funcion para exportar el archivo (.roster)
+/// Funcion para importar un archivo (.roster, .json o .yaml)
+/// Reemplaza todos los datos de la escuela por los del archivo y retorna el resumen de lo
+/// que se importo; si algo falla la base de datos se queda como estaba
+/// # Argumentos
+/// * `path` - Ruta del archivo, elegida con `pick_roster_file`
+/// * `password` - Contraseña si el archivo esta cifrado
+/// Si falta la contraseña o no es la correcta se puede volver a llamar con la misma ruta
 #[tauri::command]
 pub async fn import_file(
     handle: tauri::AppHandle,
     pool: tauri::State<'_, AppState>,
-    _window: Window,
-) -> Result<(), String> {
-    // Selector de archivos del usuario
-    // Utilizar canal para comunicar callback y async
-    let (tx, rx) = std::sync::mpsc::channel();
-
-    // TODO: Hacer un nombre predeterminado utilizando nombre de escuela
-    dialog::FileDialogBuilder::new()
-        .add_filter("Roster Files", &["roster"])
-        .set_title("Abrir archivo de horario")
-        .pick_file(move |file_path| {
-            tx.send(file_path).unwrap();
-        });
-
-    // Esperar al usuario
-    let file_path = match rx.recv().unwrap() {
-        Some(path) => path,
-        None => return Err("Cancelado por el usuario".to_string()),
-    };
-
+    path: String,
+    password: Option<String>,
+) -> Result<ImportSummary, ImportError> {
     // Asegurarse de que la extension si es '.roster'
+    let file_path = PathBuf::from(path);
     let file_path = if file_path.extension().is_none() {
         file_path.with_extension("roster")
     } else {
         file_path
     };
 
-    // Convierte el error personalizado a un String para que tauri lo pueda manejar
-    match import_file_impl(pool, handle, &file_path).await {
-        Ok(()) => Ok(()),
-        Err(e) => Err(format!("Export error: {}", e)),
-    }
+    Ok(import_file_impl(pool, handle, &file_path, password.as_deref()).await?)
 }
 
 // Implementacion de la funcion con errores personalizados
@@ -232,45 +268,18 @@ async fn import_file_impl(
     pool: tauri::State<'_, AppState>,
     handle: tauri::AppHandle,
     file_path: &PathBuf,
-) -> ScheduleResult<()> {
-    let file = File::open(file_path);
-    let mut buffer = Vec::new();
-
-    file?.read_to_end(&mut buffer)?;
-
-    // Verifica el header
-    if buffer.len() < std::mem::size_of::<ScheduleFileHeader>() {
-        return Err(ScheduleFileError::InvalidFormat);
-    }
-
-    let header_size = bincode::serialized_size(&ScheduleFileHeader {
-        magic: *b"SCHA",
-        version: 0,
-        created_at: 0,
-        checksum: 0,
-    })? as usize;
-
-    let header: ScheduleFileHeader = bincode::deserialize(&buffer[..header_size])?;
-
-    // Verifica los bytes
-    if &header.magic != b"SCHA" {
-        return Err(ScheduleFileError::InvalidFormat);
-    }
-
-    // Checa la version
-    if header.version > 1 {
-        return Err(ScheduleFileError::UnsuportedVersion(header.version));
-    }
-
-    // Checksum
-    let data_part = &buffer[header_size..];
-    let computed_checksum = simple_checksum(data_part);
-    if computed_checksum != header.checksum {
-        return Err(ScheduleFileError::InvalidFormat);
+    password: Option<&str>,
+) -> ScheduleResult<ImportSummary> {
+    let data = read_schedule_file(file_path, password)?;
+
+    // Los archivos de la version 1 no traen configuracion, se valida con la actual
+    let config = match &data.config {
+        Some(config) => config.clone(),
+        None => load_config(&pool.db)
+            .await
+            .map_err(ScheduleFileError::DataError)?,
     };
-
-    // Deserealizar los datos
-    let data: ScheduleData = bincode::deserialize(data_part)?;
+    validate_schedule_data(&data, &config).map_err(ScheduleFileError::InvalidData)?;
 
     // Base de datos utilizada
     // Obtiene la ruta de la aplicacion.
@@ -282,80 +291,687 @@ async fn import_file_impl(
 
     // Antes de continuar, hacer un backup (cada vez que se abre un archivo se sobrescribira el backup anterior)
     let backup_path = format!("{}.backup", db_path.display());
-    fs::copy(db_path, backup_path)?;
+    fs::copy(db_path, &backup_path)?;
 
     // Importar los datos en la base de datos
-    clear_data(pool.clone()).await;
-
-    create_subjects(pool.clone(), data.subjects).await;
-    create_teachers(pool.clone(), data.teachers).await;
-    link_subject_to_teacher(pool.clone(), data.teacher_subjects).await;
-    create_groups(pool.clone(), data.groups).await;
-    create_classrooms(pool.clone(), data.classrooms).await;
-    for assignment in data.assignments {
-        save_assignment(
-            pool.clone(),
-            assignment.group_id.into(),
-            assignment.day.as_str(),
-            assignment.module_index.into(),
-            assignment.subject_id.into(),
-            assignment.teacher_id.into(),
-        )
-        .await;
-    }
+    let mut summary = import_data(&pool.db, &data)
+        .await
+        .map_err(ScheduleFileError::DataError)?;
+    summary.backup = Some(backup_path);
 
-    Ok(())
+    Ok(summary)
 }
 
-// WARNING
-/// Funcion para eliminar todos los datos registrados en el programa
+/// Funcion para reemplazar todos los datos de la escuela por los de un archivo
+/// # Argumentos
+/// * `db` - Pool de la base de datos
+/// * `data` - Datos ya validados con `validate_schedule_data`
+/// Todo se hace en una transaccion: si falla una fila no se borra ni se importa nada
+pub async fn import_data(db: &DbPool, data: &ScheduleData) -> Result<ImportSummary, String> {
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let discarded = clear_tables(&mut tx).await?;
+    insert_schedule_data(&mut tx, data).await?;
+
+    let summary = ImportSummary {
+        discarded,
+        ..ImportSummary::of(data)
+    };
+    audit::record(
+        &mut *tx,
+        audit::DATABASE,
+        None,
+        "import",
+        None,
+        serde_json::to_value(&summary).ok().as_ref(),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(summary)
+}
+
+/// Funcion para elegir un archivo de horario y retornar su ruta
+/// Se usa para importarlo (`import_file`) o combinarlo con los datos actuales
+/// (`preview_merge_file` y `merge_file`); la ruta sirve para reintentar con otra contraseña
 #[tauri::command]
-pub async fn delete_all_data(pool: tauri::State<'_, AppState>) -> Result<(), String> {
-    clear_data(pool).await;
-    Ok(())
+pub async fn pick_roster_file() -> Option<String> {
+    dialog::blocking::FileDialogBuilder::new()
+        .set_title("Abrir archivo de horario")
+        .add_filter("Roster Files", &["roster", "json", "yaml", "yml"])
+        .pick_file()
+        .map(|p| p.to_string_lossy().to_string())
+}
+
+/// Funcion para ver que pasaria al combinar un archivo con los datos actuales, sin guardar
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `path` - Ruta del archivo (.roster, .json o .yaml)
+/// * `options` - Entidades a traer y que hacer con cada conflicto
+/// * `password` - Contraseña si el archivo esta cifrado
+/// Retorna el mismo resumen que `merge_file`, con los conflictos encontrados para elegir
+/// que hacer con cada uno
+#[tauri::command]
+pub async fn preview_merge_file(
+    pool: tauri::State<'_, AppState>,
+    path: String,
+    options: Option<MergeOptions>,
+    password: Option<String>,
+) -> Result<MergeReport, String> {
+    let data = read_for_merge(&pool.db, &path, password.as_deref()).await?;
+    let current = load_schedule_data(&pool.db).await?;
+
+    Ok(plan_merge(&current, &data, &options.unwrap_or_default()).report)
+}
+
+/// Funcion para combinar un archivo con los datos actuales sin borrar nada
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `path` - Ruta del archivo (.roster, .json o .yaml)
+/// * `options` - Entidades a traer y que hacer con cada conflicto
+/// * `password` - Contraseña si el archivo esta cifrado
+/// Las filas nuevas reciben ids nuevos; la configuracion y los datos de la escuela no cambian
+#[tauri::command]
+pub async fn merge_file(
+    pool: tauri::State<'_, AppState>,
+    path: String,
+    options: Option<MergeOptions>,
+    password: Option<String>,
+) -> Result<MergeReport, String> {
+    let data = read_for_merge(&pool.db, &path, password.as_deref()).await?;
+    merge_data(&pool.db, &data, &options.unwrap_or_default()).await
 }
 
-/// Funcion que limpia todos los datos previamente guardados (solo las tablas usadas en el importe)
-// BUG: Por alguna razon no se puede utilizar '?', entonces los mensajes de WARNING en el compilador no se pueden arreglar.
-#[allow(unused_must_use)]
-async fn clear_data(pool: tauri::State<'_, AppState>) -> Result<(), String> {
-    println!("Clearing previous data...");
+// Lee y valida un archivo para combinarlo
+async fn read_for_merge(
+    db: &DbPool,
+    path: &str,
+    password: Option<&str>,
+) -> Result<ScheduleData, String> {
+    let data = read_schedule_file(Path::new(path), password)
+        .map_err(|e| format!("Could not read {}: {}", path, e))?;
+
+    let config = match &data.config {
+        Some(config) => config.clone(),
+        None => load_config(db).await?,
+    };
+    validate_schedule_data(&data, &config).map_err(|e| format!("Invalid data: {}", e))?;
+
+    Ok(data)
+}
 
-    sqlx::query("DELETE FROM group_subjects")
-        .execute(&pool.db)
+/// Funcion para guardar en una transaccion los cambios que combinan un archivo
+/// # Argumentos
+/// * `db` - Pool de la base de datos
+/// * `data` - Datos ya validados con `validate_schedule_data`
+/// * `options` - Entidades a traer y que hacer con cada conflicto
+pub async fn merge_data(
+    db: &DbPool,
+    data: &ScheduleData,
+    options: &MergeOptions,
+) -> Result<MergeReport, String> {
+    let current = load_schedule_data(db).await?;
+    let plan = plan_merge(&current, data, options);
+
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    // Primero las filas nuevas: las materias de un profesor o grupo sobrescrito pueden
+    // ser materias que se acaban de crear
+    insert_schedule_data(&mut tx, &plan.insert).await?;
+    update_schedule_rows(&mut tx, &plan.overwrite).await?;
+
+    audit::record(
+        &mut *tx,
+        audit::DATABASE,
+        None,
+        "merge",
+        None,
+        serde_json::to_value(&plan.report).ok().as_ref(),
+    )
+    .await?;
+
+    tx.commit()
         .await
-        .map_err(|e| format!("Error while deleting group_subjects: {}", e));
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
 
-    sqlx::query("DELETE FROM teacher_subjects")
-        .execute(&pool.db)
+    Ok(plan.report)
+}
+
+/// Funcion para revisar que los datos de un archivo se puedan importar
+/// # Argumentos
+/// * `data` - Datos leidos del archivo
+/// * `config` - Configuracion del horario con la que se revisan los dias y modulos
+/// Revisa que no haya ids repetidos, que cada relacion apunte a una fila del archivo y que
+/// el horario no tenga dos clases en la misma celda
+/// Retorna todos los problemas encontrados en un solo mensaje
+pub fn validate_schedule_data(data: &ScheduleData, config: &Config) -> Result<(), String> {
+    let mut errors: Vec<String> = Vec::new();
+
+    let subjects = unique_ids("subject", data.subjects.iter().map(|s| s.id), &mut errors);
+    let teachers = unique_ids("teacher", data.teachers.iter().map(|t| t.id), &mut errors);
+    let groups = unique_ids("group", data.groups.iter().map(|g| g.id), &mut errors);
+    let classrooms = unique_ids(
+        "classroom",
+        data.classrooms.iter().map(|c| c.id),
+        &mut errors,
+    );
+    unique_ids("student", data.students.iter().map(|s| s.id), &mut errors);
+    unique_ids("lesson", data.assignments.iter().map(|a| a.id), &mut errors);
+
+    if config.days.is_empty() || config.modules_per_day == 0 {
+        errors.push("The schedule configuration has no days or modules".to_string());
+    }
+
+    for s in data.subjects.iter().filter(|s| s.name.trim().is_empty()) {
+        errors.push(format!("Subject {:?} has no name", s.id));
+    }
+    for s in data
+        .subjects
+        .iter()
+        .filter(|s| s.required_modules.is_none())
+    {
+        errors.push(format!("Subject {} has no required modules", s.name));
+    }
+    for c in data.classrooms.iter().filter(|c| c.building_id.is_none()) {
+        errors.push(format!("Classroom {} has no building", c.building_number));
+    }
+    for t in data.teachers.iter().filter(|t| t.name.trim().is_empty()) {
+        errors.push(format!("Teacher {:?} has no name", t.id));
+    }
+
+    let mut links = BTreeSet::new();
+    for ts in &data.teacher_subjects {
+        if !teachers.contains(&ts.teacher_id) || !subjects.contains(&ts.subject_id) {
+            errors.push(format!(
+                "Teacher {} is linked to subject {}, but one of them is not in the file",
+                ts.teacher_id, ts.subject_id
+            ));
+        } else if !links.insert((ts.teacher_id, ts.subject_id)) {
+            errors.push(format!(
+                "Teacher {} is linked to subject {} twice",
+                ts.teacher_id, ts.subject_id
+            ));
+        }
+    }
+
+    let mut links = BTreeSet::new();
+    for gs in &data.group_subjects {
+        if !groups.contains(&gs.group_id) || !subjects.contains(&gs.subject_id) {
+            errors.push(format!(
+                "Group {} is linked to subject {}, but one of them is not in the file",
+                gs.group_id, gs.subject_id
+            ));
+        } else if !links.insert((gs.group_id, gs.subject_id)) {
+            errors.push(format!(
+                "Group {} is linked to subject {} twice",
+                gs.group_id, gs.subject_id
+            ));
+        }
+    }
+
+    for st in &data.students {
+        if let Some(group_id) = st.group_id.filter(|id| !groups.contains(id)) {
+            errors.push(format!(
+                "Student {} belongs to group {}, which is not in the file",
+                st.name, group_id
+            ));
+        }
+    }
+
+    let mut cells = BTreeSet::new();
+    for a in &data.assignments {
+        let cell = format!(
+            "group {} on {} module {}",
+            a.group_id,
+            a.day,
+            a.module_index + 1
+        );
+
+        if !groups.contains(&a.group_id) {
+            errors.push(format!("The lesson of {} has an unknown group", cell));
+        }
+        if !subjects.contains(&a.subject_id) {
+            errors.push(format!(
+                "The lesson of {} has an unknown subject {}",
+                cell, a.subject_id
+            ));
+        }
+        if !teachers.contains(&a.teacher_id) {
+            errors.push(format!(
+                "The lesson of {} has an unknown teacher {}",
+                cell, a.teacher_id
+            ));
+        }
+        if a.classroom_id != 0 && !classrooms.contains(&a.classroom_id) {
+            errors.push(format!(
+                "The lesson of {} has an unknown classroom {}",
+                cell, a.classroom_id
+            ));
+        }
+        if !config.days.contains(&a.day) {
+            errors.push(format!("The lesson of {} is not on a school day", cell));
+        }
+        if a.module_index < 0 || a.module_index as u32 >= config.modules_per_day {
+            errors.push(format!("The lesson of {} is out of the day", cell));
+        }
+        if !cells.insert((a.group_id, a.day.as_str(), a.module_index)) {
+            errors.push(format!("There are two lessons for {}", cell));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+// Junta los ids de una tabla y agrega un error por cada id repetido
+fn unique_ids(
+    entity: &str,
+    ids: impl Iterator<Item = Option<i16>>,
+    errors: &mut Vec<String>,
+) -> BTreeSet<i16> {
+    let mut seen = BTreeSet::new();
+    for id in ids.flatten() {
+        if !seen.insert(id) {
+            errors.push(format!("The {} id {} is repeated", entity, id));
+        }
+    }
+    seen
+}
+
+/// Funcion para guardar en la base de datos las tablas de un archivo
+/// # Argumentos
+/// * `tx` - Transaccion donde se hace la importacion
+/// * `data` - Datos leidos del archivo
+/// Las filas se insertan con el id que traen para que las relaciones no cambien;
+/// la configuracion y los datos de la escuela solo se sobrescriben si el archivo los trae
+async fn insert_schedule_data(tx: &mut Tx<'_>, data: &ScheduleData) -> Result<(), String> {
+    for s in &data.subjects {
+        sqlx::query(
+            "INSERT INTO subjects (id, name, shorten, color, spec, required_modules, priority)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )
+        .bind(s.id)
+        .bind(&s.name)
+        .bind(&s.shorten)
+        .bind(&s.color)
+        .bind(&s.spec)
+        .bind(s.required_modules)
+        .bind(s.priority)
+        .execute(&mut *tx)
         .await
-        .map_err(|e| format!("Error while deleting teacher_subjects: {}", e));
+        .map_err(|e| format!("Failed to import subject {}: {}", s.name, e))?;
+    }
+
+    for t in &data.teachers {
+        let preferred_days = serde_json::to_string(&t.preferred_days)
+            .map_err(|e| format!("Failed to serialize preferred_days: {}", e))?;
+        let preferred_modules = serde_json::to_string(&t.preferred_modules)
+            .map_err(|e| format!("Failed to serialize teacher preferred modules: {}", e))?;
+
+        sqlx::query(
+            "INSERT INTO teachers (
+                id, name, father_lastname, mother_lastname,
+                email, phone, degree, commisioned_hours,
+                active_hours, performance, preferred_days, preferred_modules
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        )
+        .bind(t.id)
+        .bind(&t.name)
+        .bind(&t.father_lastname)
+        .bind(&t.mother_lastname)
+        .bind(&t.email)
+        .bind(&t.phone)
+        .bind(&t.degree)
+        .bind(t.commisioned_hours)
+        .bind(t.active_hours)
+        .bind(t.performance)
+        .bind(preferred_days)
+        .bind(preferred_modules)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to import teacher {}: {}", t.name, e))?;
+    }
+
+    for ts in &data.teacher_subjects {
+        sqlx::query("INSERT INTO teacher_subjects (teacher_id, subject_id) VALUES (?1, ?2)")
+            .bind(ts.teacher_id)
+            .bind(ts.subject_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                format!(
+                    "Failed to link teacher {} to subject {}: {}",
+                    ts.teacher_id, ts.subject_id, e
+                )
+            })?;
+    }
 
-    sqlx::query("DELETE FROM subjects")
-        .execute(&pool.db)
+    for g in &data.groups {
+        sqlx::query(
+            r#"INSERT INTO groups (id, grade, "group", career, students, max_modules_per_day)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+        )
+        .bind(g.id)
+        .bind(g.grade)
+        .bind(&g.group)
+        .bind(&g.career)
+        .bind(g.students)
+        .bind(g.max_modules_per_day)
+        .execute(&mut *tx)
         .await
-        .map_err(|e| format!("Error while deleting subjects: {}", e));
+        .map_err(|e| format!("Failed to import group {}{}: {}", g.grade, g.group, e))?;
+    }
 
-    sqlx::query("DELETE FROM teachers")
-        .execute(&pool.db)
+    for gs in &data.group_subjects {
+        sqlx::query("INSERT INTO groups_subjects (group_id, subject_id) VALUES (?1, ?2)")
+            .bind(gs.group_id)
+            .bind(gs.subject_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                format!(
+                    "Failed to link group {} to subject {}: {}",
+                    gs.group_id, gs.subject_id, e
+                )
+            })?;
+    }
+
+    for st in &data.students {
+        sqlx::query(
+            "INSERT INTO students (id, name, father_lastname, mother_lastname, group_id)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )
+        .bind(st.id)
+        .bind(&st.name)
+        .bind(&st.father_lastname)
+        .bind(&st.mother_lastname)
+        .bind(st.group_id)
+        .execute(&mut *tx)
         .await
-        .map_err(|e| format!("Error while deleting teachers: {}", e));
+        .map_err(|e| format!("Failed to import student {}: {}", st.name, e))?;
+    }
+
+    for c in &data.classrooms {
+        let availability_json = serde_json::to_string(&c.availability)
+            .map_err(|e| format!("Failed to serialize availability: {}", e))?;
 
-    sqlx::query("DELETE FROM groups")
-        .execute(&pool.db)
+        sqlx::query(
+            "INSERT INTO classroom (id, building_id, building_number, building_type, capacity, availability)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )
+        .bind(c.id)
+        .bind(&c.building_id)
+        .bind(c.building_number)
+        .bind(&c.building_type)
+        .bind(c.capacity)
+        .bind(availability_json)
+        .execute(&mut *tx)
         .await
-        .map_err(|e| format!("Error while deleting groups: {}", e));
+        .map_err(|e| format!("Failed to import classroom {}: {}", c.building_number, e))?;
+    }
 
-    sqlx::query("DELETE FROM classrooms")
-        .execute(&pool.db)
+    for a in &data.assignments {
+        sqlx::query(
+            "INSERT INTO assignments (id, group_id, day, module_index, subject_id, teacher_id, classroom_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )
+        .bind(a.id)
+        .bind(a.group_id)
+        .bind(&a.day)
+        .bind(a.module_index)
+        .bind(a.subject_id)
+        .bind(a.teacher_id)
+        .bind((a.classroom_id != 0).then_some(a.classroom_id))
+        .execute(&mut *tx)
         .await
-        .map_err(|e| format!("Error while deleting classrooms: {}", e));
+        .map_err(|e| {
+            format!(
+                "Failed to import the lesson of group {} on {} module {}: {}",
+                a.group_id,
+                a.day,
+                a.module_index + 1,
+                e
+            )
+        })?;
+    }
 
-    sqlx::query("DELETE FROM assignments")
-        .execute(&pool.db)
+    if let Some(config) = &data.config {
+        let cfg_json = serde_json::to_string(config)
+            .map_err(|e| format!("Failed to serialize config file: {}", e))?;
+
+        sqlx::query(
+            "INSERT INTO config (key, value) VALUES ('schedule_config', ?1)
+             ON CONFLICT(key) DO UPDATE SET value = ?1",
+        )
+        .bind(cfg_json)
+        .execute(&mut *tx)
         .await
-        .map_err(|e| format!("Error while deleting assignments: {}", e));
+        .map_err(|e| format!("Failed to import the schedule configuration: {}", e))?;
+    }
+
+    if let Some(school) = &data.school {
+        sqlx::query(
+            "INSERT INTO school (id, name, logo_path) VALUES (1, ?1, ?2)
+             ON CONFLICT(id) DO UPDATE SET name = ?1, logo_path = ?2",
+        )
+        .bind(&school.name)
+        .bind(&school.logo_path)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to import school info: {}", e))?;
+    }
 
-    println!("Done!");
     Ok(())
 }
+
+// Reemplaza los datos de materias, profesores, grupos y aulas que ya existen
+// Las materias de los profesores y grupos se reemplazan por las de `data`
+async fn update_schedule_rows(tx: &mut Tx<'_>, data: &ScheduleData) -> Result<(), String> {
+    for s in &data.subjects {
+        sqlx::query(
+            "UPDATE subjects SET name = ?1, shorten = ?2, color = ?3, spec = ?4,
+                required_modules = ?5, priority = ?6
+             WHERE id = ?7",
+        )
+        .bind(&s.name)
+        .bind(&s.shorten)
+        .bind(&s.color)
+        .bind(&s.spec)
+        .bind(s.required_modules)
+        .bind(s.priority)
+        .bind(s.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to overwrite subject {}: {}", s.name, e))?;
+    }
+
+    for t in &data.teachers {
+        let preferred_days = serde_json::to_string(&t.preferred_days)
+            .map_err(|e| format!("Failed to serialize preferred_days: {}", e))?;
+        let preferred_modules = serde_json::to_string(&t.preferred_modules)
+            .map_err(|e| format!("Failed to serialize teacher preferred modules: {}", e))?;
+
+        sqlx::query(
+            "UPDATE teachers SET name = ?1, father_lastname = ?2, mother_lastname = ?3,
+                email = ?4, phone = ?5, degree = ?6, commisioned_hours = ?7,
+                active_hours = ?8, performance = ?9, preferred_days = ?10, preferred_modules = ?11
+             WHERE id = ?12",
+        )
+        .bind(&t.name)
+        .bind(&t.father_lastname)
+        .bind(&t.mother_lastname)
+        .bind(&t.email)
+        .bind(&t.phone)
+        .bind(&t.degree)
+        .bind(t.commisioned_hours)
+        .bind(t.active_hours)
+        .bind(t.performance)
+        .bind(preferred_days)
+        .bind(preferred_modules)
+        .bind(t.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to overwrite teacher {}: {}", t.name, e))?;
+
+        sqlx::query("DELETE FROM teacher_subjects WHERE teacher_id = ?1")
+            .bind(t.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to overwrite teacher {}: {}", t.name, e))?;
+    }
+
+    for ts in &data.teacher_subjects {
+        sqlx::query("INSERT INTO teacher_subjects (teacher_id, subject_id) VALUES (?1, ?2)")
+            .bind(ts.teacher_id)
+            .bind(ts.subject_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to link teacher subjects: {}", e))?;
+    }
+
+    for g in &data.groups {
+        sqlx::query(
+            r#"UPDATE groups SET grade = ?1, "group" = ?2, career = ?3, students = ?4,
+                max_modules_per_day = ?5
+               WHERE id = ?6"#,
+        )
+        .bind(g.grade)
+        .bind(&g.group)
+        .bind(&g.career)
+        .bind(g.students)
+        .bind(g.max_modules_per_day)
+        .bind(g.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to overwrite group {}{}: {}", g.grade, g.group, e))?;
+
+        sqlx::query("DELETE FROM groups_subjects WHERE group_id = ?1")
+            .bind(g.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to overwrite group {}{}: {}", g.grade, g.group, e))?;
+    }
+
+    for gs in &data.group_subjects {
+        sqlx::query("INSERT INTO groups_subjects (group_id, subject_id) VALUES (?1, ?2)")
+            .bind(gs.group_id)
+            .bind(gs.subject_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to link group subjects: {}", e))?;
+    }
+
+    for c in &data.classrooms {
+        let availability_json = serde_json::to_string(&c.availability)
+            .map_err(|e| format!("Failed to serialize availability: {}", e))?;
+
+        sqlx::query(
+            "UPDATE classroom SET building_id = ?1, building_number = ?2, building_type = ?3,
+                capacity = ?4, availability = ?5
+             WHERE id = ?6",
+        )
+        .bind(&c.building_id)
+        .bind(c.building_number)
+        .bind(&c.building_type)
+        .bind(c.capacity)
+        .bind(availability_json)
+        .bind(c.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to overwrite classroom {}: {}", c.building_number, e))?;
+    }
+
+    Ok(())
+}
+
+// WARNING
+/// Funcion para eliminar todos los datos registrados en el programa
+#[tauri::command]
+pub async fn delete_all_data(pool: tauri::State<'_, AppState>) -> Result<(), String> {
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    clear_tables(&mut tx).await?;
+    audit::record(&mut *tx, audit::DATABASE, None, "delete", None, None).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(())
+}
+
+/// Funcion que limpia todas las tablas de la escuela que se guardan en el archivo
+/// Tambien borra el horario en prueba, el historial para deshacer y los datos que el
+/// archivo no guarda (versiones, cambios por fecha, cierres, ausencias, periodos y
+/// festivos), que apuntan a los datos anteriores
+/// Retorna cuantas filas de esos datos se borraron
+async fn clear_tables(tx: &mut Tx<'_>) -> Result<u64, String> {
+    // Primero las filas que dependen de otras (clases de las versiones, datos guardados
+    // de los periodos), no se cuentan
+    for table in [
+        "schedule_version_assignments",
+        "term_assignments",
+        "term_students",
+        "term_groups_subjects",
+        "term_groups",
+    ] {
+        sqlx::query(&format!("DELETE FROM {}", table))
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Error while deleting {}: {}", table, e))?;
+    }
+
+    let mut discarded = 0;
+    for table in [
+        "schedule_overrides",
+        "absences",
+        "resource_closures",
+        "schedule_versions",
+        "terms",
+        "holidays",
+    ] {
+        discarded += sqlx::query(&format!("DELETE FROM {}", table))
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Error while deleting {}: {}", table, e))?
+            .rows_affected();
+    }
+
+    for table in [
+        "operation_changes",
+        "operation_log",
+        "staged_assignments",
+        "staged_schedule_base",
+        "assignments",
+        "groups_subjects",
+        "teacher_subjects",
+        "students",
+        "subjects",
+        "teachers",
+        "groups",
+        "classroom",
+    ] {
+        sqlx::query(&format!("DELETE FROM {}", table))
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Error while deleting {}: {}", table, e))?;
+    }
+
+    Ok(discarded)
+}
@@ -0,0 +1,426 @@
+/*
+    Historial para deshacer y rehacer cambios al horario.
+    Cada comando que modifica `assignments` guarda, dentro de su misma transaccion,
+    como quedaron las celdas antes y despues. Deshacer regresa las celdas a su estado
+    anterior y rehacer las vuelve a dejar como despues de la operacion.
+    Las operaciones por lotes se guardan como un solo paso.
+    Si una celda ya no tiene el valor que dejo la operacion (se cambio por fuera del historial,
+    por ejemplo al borrar un profesor) no se deshace ni rehace nada.
+*/
+
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Sqlite, Transaction};
+
+use crate::db::AppState;
+use crate::engine::{diff_schedules, CellChange, CellValue};
+
+use super::assignments::Assignment;
+use super::audit;
+
+// Operaciones que se conservan, las mas viejas se borran
+const MAX_OPERATIONS: i64 = 200;
+
+pub type Tx<'c> = Transaction<'c, Sqlite>;
+
+/// Operacion del historial
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Operation {
+    pub id: i64,
+    pub label: String,
+    pub created_at: i64,
+    pub undone: bool,
+    pub change_count: i64,
+}
+
+#[derive(FromRow)]
+struct ChangeRow {
+    group_id: i16,
+    day: String,
+    module_index: i16,
+    before_subject_id: Option<i16>,
+    before_teacher_id: Option<i16>,
+    before_classroom_id: Option<i16>,
+    after_subject_id: Option<i16>,
+    after_teacher_id: Option<i16>,
+    after_classroom_id: Option<i16>,
+}
+
+/// Funcion para deshacer la ultima operacion
+/// Retorna la operacion deshecha, o `None` si no hay nada que deshacer
+#[tauri::command]
+pub async fn undo(pool: tauri::State<'_, AppState>) -> Result<Option<Operation>, String> {
+    step(&pool, true).await
+}
+
+/// Funcion para rehacer la ultima operacion deshecha
+/// Retorna la operacion rehecha, o `None` si no hay nada que rehacer
+#[tauri::command]
+pub async fn redo(pool: tauri::State<'_, AppState>) -> Result<Option<Operation>, String> {
+    step(&pool, false).await
+}
+
+/// Funcion para obtener el historial, de la operacion mas reciente a la mas antigua
+#[tauri::command]
+pub async fn get_history(pool: tauri::State<'_, AppState>) -> Result<Vec<Operation>, String> {
+    sqlx::query_as::<_, Operation>(
+        "SELECT o.id, o.label, o.created_at, o.undone,
+            (SELECT COUNT(*) FROM operation_changes c WHERE c.operation_id = o.id) AS change_count
+        FROM operation_log o
+        ORDER BY o.id DESC",
+    )
+    .fetch(&pool.db)
+    .try_collect()
+    .await
+    .map_err(|e| format!("Failed to get history: {}", e))
+}
+
+// Deshace (`undo = true`) o rehace una operacion
+async fn step(pool: &AppState, undo: bool) -> Result<Option<Operation>, String> {
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    // Deshacer toma la ultima operacion vigente, rehacer la primera deshecha
+    let query = if undo {
+        "SELECT id, label FROM operation_log WHERE undone = 0 ORDER BY id DESC LIMIT 1"
+    } else {
+        "SELECT id, label FROM operation_log WHERE undone = 1 ORDER BY id ASC LIMIT 1"
+    };
+    let Some((id, label)) = sqlx::query_as::<_, (i64, String)>(query)
+        .fetch_optional(&mut tx)
+        .await
+        .map_err(|e| format!("Failed to read history: {}", e))?
+    else {
+        return Ok(None);
+    };
+
+    let rows = sqlx::query_as::<_, ChangeRow>(
+        "SELECT group_id, day, module_index,
+            before_subject_id, before_teacher_id, before_classroom_id,
+            after_subject_id, after_teacher_id, after_classroom_id
+        FROM operation_changes WHERE operation_id = ?1",
+    )
+    .bind(id)
+    .fetch_all(&mut tx)
+    .await
+    .map_err(|e| format!("Failed to read history: {}", e))?;
+
+    let mut changes = Vec::with_capacity(rows.len());
+    for row in &rows {
+        let before = cell_value(
+            row.before_subject_id,
+            row.before_teacher_id,
+            row.before_classroom_id,
+        );
+        let after = cell_value(
+            row.after_subject_id,
+            row.after_teacher_id,
+            row.after_classroom_id,
+        );
+        // Al deshacer la celda pasa de `after` a `before`
+        let (from, to) = if undo {
+            (after, before)
+        } else {
+            (before, after)
+        };
+
+        // La transaccion se descarta sin guardar nada
+        let current = read_cell(&mut tx, row.group_id, &row.day, row.module_index).await?;
+        if current != from {
+            return Err(format!(
+                "Could not {} \"{}\": the lesson of group {} on {} module {} changed since then",
+                if undo { "undo" } else { "redo" },
+                label,
+                row.group_id,
+                row.day,
+                row.module_index + 1
+            ));
+        }
+
+        write_cell(
+            &mut tx,
+            row.group_id,
+            &row.day,
+            row.module_index,
+            to.as_ref(),
+        )
+        .await
+        .map_err(|e| {
+            format!(
+                "Could not {} the change (a teacher, subject or classroom may have been deleted): {}",
+                if undo { "undo" } else { "redo" },
+                e
+            )
+        })?;
+        changes.push(CellChange {
+            group_id: row.group_id,
+            day: row.day.clone(),
+            module_index: row.module_index,
+            before: from,
+            after: to,
+        });
+    }
+
+    sqlx::query("UPDATE operation_log SET undone = ?1 WHERE id = ?2")
+        .bind(undo)
+        .bind(id)
+        .execute(&mut tx)
+        .await
+        .map_err(|e| format!("Failed to update history: {}", e))?;
+
+    let operation = sqlx::query_as::<_, Operation>(
+        "SELECT id, label, created_at, undone, ?2 AS change_count FROM operation_log WHERE id = ?1",
+    )
+    .bind(id)
+    .bind(rows.len() as i64)
+    .fetch_one(&mut tx)
+    .await
+    .map_err(|e| format!("Failed to read history: {}", e))?;
+
+    audit::record_schedule(
+        &mut *tx,
+        id,
+        if undo { "undo" } else { "redo" },
+        &operation.label,
+        &changes.iter().collect::<Vec<_>>(),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(Some(operation))
+}
+
+fn cell_value(
+    subject_id: Option<i16>,
+    teacher_id: Option<i16>,
+    classroom_id: Option<i16>,
+) -> Option<CellValue> {
+    Some(CellValue {
+        subject_id: subject_id?,
+        teacher_id: teacher_id?,
+        classroom_id: classroom_id.unwrap_or_default(),
+    })
+}
+
+/// Funcion para guardar una operacion en el historial
+/// Se llama dentro de la transaccion del comando que hizo el cambio
+/// Borra lo que se podia rehacer (una operacion nueva corta esa rama)
+/// Si ninguna celda cambio no se guarda nada
+pub async fn record(tx: &mut Tx<'_>, label: &str, changes: &[CellChange]) -> Result<(), String> {
+    let changes: Vec<&CellChange> = changes.iter().filter(|c| c.before != c.after).collect();
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    delete_operations(tx, "undone = 1").await?;
+
+    let id = sqlx::query("INSERT INTO operation_log (label, created_at) VALUES (?1, ?2)")
+        .bind(label)
+        .bind(chrono::Utc::now().timestamp())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to update history: {}", e))?
+        .last_insert_rowid();
+
+    for change in &changes {
+        let before = change.before.as_ref();
+        let after = change.after.as_ref();
+        sqlx::query(
+            "INSERT INTO operation_changes (operation_id, group_id, day, module_index,
+                before_subject_id, before_teacher_id, before_classroom_id,
+                after_subject_id, after_teacher_id, after_classroom_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        )
+        .bind(id)
+        .bind(change.group_id)
+        .bind(&change.day)
+        .bind(change.module_index)
+        .bind(before.map(|v| v.subject_id))
+        .bind(before.map(|v| v.teacher_id))
+        .bind(before.map(|v| v.classroom_id))
+        .bind(after.map(|v| v.subject_id))
+        .bind(after.map(|v| v.teacher_id))
+        .bind(after.map(|v| v.classroom_id))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to update history: {}", e))?;
+    }
+
+    audit::record_schedule(&mut *tx, id, "update", label, &changes).await?;
+
+    let keep = format!(
+        "id NOT IN (SELECT id FROM operation_log ORDER BY id DESC LIMIT {})",
+        MAX_OPERATIONS
+    );
+    delete_operations(tx, &keep).await
+}
+
+// Borra las operaciones que cumplan la condicion junto con sus cambios
+async fn delete_operations(tx: &mut Tx<'_>, condition: &str) -> Result<(), String> {
+    sqlx::query(&format!(
+        "DELETE FROM operation_changes WHERE operation_id IN (SELECT id FROM operation_log WHERE {})",
+        condition
+    ))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to update history: {}", e))?;
+
+    sqlx::query(&format!("DELETE FROM operation_log WHERE {}", condition))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to update history: {}", e))?;
+
+    Ok(())
+}
+
+/// Funcion para guardar en el historial el reemplazo completo del horario
+/// (generar, aplicar un horario en prueba, restaurar una version)
+/// `before` es el horario que habia antes; el de despues se lee de la transaccion
+pub async fn record_replace(
+    tx: &mut Tx<'_>,
+    label: &str,
+    before: &[Assignment],
+) -> Result<(), String> {
+    let after = read_schedule(tx).await?;
+    record(tx, label, &diff_schedules(before, &after)).await
+}
+
+/// Funcion que lee todas las asignaciones dentro de una transaccion
+/// Solo se llenan los campos de la celda (sin nombre ni color de la materia)
+pub async fn read_schedule(tx: &mut Tx<'_>) -> Result<Vec<Assignment>, String> {
+    sqlx::query_as::<_, Assignment>(
+        "SELECT id, group_id, day, module_index, subject_id, teacher_id,
+            COALESCE(classroom_id, 0) AS classroom_id,
+            '' AS subject_name, '' AS subject_shorten, '' AS subject_color
+        FROM assignments",
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| format!("An error occurred while getting the assignments: {}", e))
+}
+
+/// Funcion que lee una celda del horario
+pub async fn read_cell(
+    tx: &mut Tx<'_>,
+    group_id: i16,
+    day: &str,
+    module_index: i16,
+) -> Result<Option<CellValue>, String> {
+    let row: Option<(i16, i16, i16)> = sqlx::query_as(
+        "SELECT subject_id, teacher_id, COALESCE(classroom_id, 0) FROM assignments
+        WHERE group_id = ?1 AND day = ?2 AND module_index = ?3",
+    )
+    .bind(group_id)
+    .bind(day)
+    .bind(module_index)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Error getting assignment: {}", e))?;
+
+    Ok(row.map(|(subject_id, teacher_id, classroom_id)| CellValue {
+        subject_id,
+        teacher_id,
+        classroom_id,
+    }))
+}
+
+/// Funcion que escribe una celda del horario (`None` la deja vacia)
+pub async fn write_cell(
+    tx: &mut Tx<'_>,
+    group_id: i16,
+    day: &str,
+    module_index: i16,
+    value: Option<&CellValue>,
+) -> Result<(), String> {
+    match value {
+        None => sqlx::query(
+            "DELETE FROM assignments WHERE group_id = ?1 AND day = ?2 AND module_index = ?3",
+        )
+        .bind(group_id)
+        .bind(day)
+        .bind(module_index)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Error deleting assignment: {}", e))?,
+        Some(value) => sqlx::query(
+            "INSERT INTO assignments (group_id, day, module_index, subject_id, teacher_id, classroom_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT (group_id, day, module_index) DO UPDATE
+            SET subject_id = excluded.subject_id,
+                teacher_id = excluded.teacher_id,
+                classroom_id = excluded.classroom_id",
+        )
+        .bind(group_id)
+        .bind(day)
+        .bind(module_index)
+        .bind(value.subject_id)
+        .bind(value.teacher_id)
+        .bind(if value.classroom_id == 0 {
+            None
+        } else {
+            Some(value.classroom_id)
+        })
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Error saving assignment: {}", e))?,
+    };
+
+    Ok(())
+}
+
+/// Funcion que escribe una celda y regresa el cambio para guardarlo en el historial
+pub async fn change_cell(
+    tx: &mut Tx<'_>,
+    group_id: i16,
+    day: &str,
+    module_index: i16,
+    value: Option<CellValue>,
+) -> Result<CellChange, String> {
+    let before = read_cell(tx, group_id, day, module_index).await?;
+    write_cell(tx, group_id, day, module_index, value.as_ref()).await?;
+
+    Ok(CellChange {
+        group_id,
+        day: day.to_string(),
+        module_index,
+        before,
+        after: value,
+    })
+}
+
+/// Funcion que busca la celda (grupo, dia, modulo) de una asignacion por su id
+pub async fn cell_of(
+    tx: &mut Tx<'_>,
+    assignment_id: i32,
+) -> Result<Option<(i16, String, i16)>, String> {
+    sqlx::query_as("SELECT group_id, day, module_index FROM assignments WHERE id = ?1")
+        .bind(assignment_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| format!("Error getting assignment: {}", e))
+}
+
+/// Funcion que cambia solo el aula de una asignacion y guarda el cambio en el historial
+pub async fn set_classroom(
+    tx: &mut Tx<'_>,
+    assignment_id: i32,
+    classroom_id: i16,
+    label: &str,
+) -> Result<(), String> {
+    let Some((group_id, day, module_index)) = cell_of(tx, assignment_id).await? else {
+        return Err(format!("Assignment {} does not exist", assignment_id));
+    };
+    let Some(mut value) = read_cell(tx, group_id, &day, module_index).await? else {
+        return Err(format!("Assignment {} does not exist", assignment_id));
+    };
+
+    value.classroom_id = classroom_id;
+    let change = change_cell(tx, group_id, &day, module_index, Some(value)).await?;
+    record(tx, label, &[change]).await
+}
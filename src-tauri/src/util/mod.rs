@@ -1,6 +1,17 @@
+pub mod absences;
 pub mod ai;
 pub mod assignments;
+pub mod audit;
+pub mod availability;
+pub mod calendar;
+pub mod conflicts;
 pub mod constraints;
 pub mod file_handler;
 pub mod generate;
+pub mod history;
+pub mod overrides;
 pub mod settings;
+pub mod snapshot;
+pub mod staging;
+pub mod terms;
+pub mod versions;
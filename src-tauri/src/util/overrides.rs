@@ -0,0 +1,501 @@
+/*
+    Cambios por fecha.
+    El horario semanal no se modifica; encima de el se guardan cambios que solo aplican en
+    una fecha: una clase con otro profesor, materia o aula (ej. examen), una clase que no
+    se da, un grupo sin clases (ej. salida de campo) o un aula cerrada (ej. reparaciones).
+    Con ellos se arma el horario efectivo de cualquier fecha.
+*/
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::{AppState, DbPool};
+use crate::engine::{
+    apply_changes, effective_timetable, format_date, parse_date, school_day, DateChanges,
+    EffectiveDay, ResourceClosure, ScheduleOverride, Snapshot, CLOSE_CLASSROOM, CLOSE_GROUP,
+};
+use crate::util::snapshot::load_snapshot;
+
+use super::audit;
+use super::calendar::fetch_holidays_between;
+use super::history::Tx;
+use super::terms::ACTIVE_TERM;
+
+// Tipos de cambio por celda
+const OVERRIDE_KINDS: [&str; 4] = ["substitution", "cancelled", "exam", "change"];
+
+/// Cambio a una celda del horario en una fecha
+/// Sin materia o sin profesor la clase no se da ese dia
+/// Sin `kind` se usa "change", o "cancelled" si la clase no se da
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverrideEdit {
+    pub date: String,
+    pub group_id: i16,
+    pub module_index: i16,
+    pub subject_id: Option<i16>,
+    pub teacher_id: Option<i16>,
+    pub classroom_id: Option<i16>,
+    pub kind: Option<String>,
+    pub note: Option<String>,
+}
+
+/// Funcion para guardar un cambio a una clase en una fecha
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `edit` - Celda, fecha y lo que se da en ella ese dia
+/// Si la celda ya tenia un cambio en esa fecha se reemplaza
+/// Falla si el profesor o el aula ya estan ocupados ese dia en ese modulo
+#[tauri::command]
+pub async fn set_schedule_override(
+    pool: tauri::State<'_, AppState>,
+    edit: OverrideEdit,
+) -> Result<ScheduleOverride, String> {
+    let snapshot = load_snapshot(&pool.db).await?;
+    save_override(&pool.db, &snapshot, &edit, None).await
+}
+
+/// Funcion para obtener los cambios por celda y los cierres entre dos fechas
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `from` - Primera fecha ("AAAA-MM-DD")
+/// * `to` - Ultima fecha ("AAAA-MM-DD"), la misma si no se manda
+#[tauri::command]
+pub async fn get_date_changes(
+    pool: tauri::State<'_, AppState>,
+    from: String,
+    to: Option<String>,
+) -> Result<DateChanges, String> {
+    let from = format_date(parse_date(&from)?);
+    let to = match to {
+        Some(to) => format_date(parse_date(&to)?),
+        None => from.clone(),
+    };
+    fetch_date_changes(&pool.db, &from, &to).await
+}
+
+/// Funcion para quitar un cambio por fecha, la clase vuelve a ser la del horario
+#[tauri::command]
+pub async fn delete_schedule_override(
+    pool: tauri::State<'_, AppState>,
+    id: i64,
+) -> Result<(), String> {
+    audit::delete_recorded_in_active_term(
+        &pool.db,
+        "schedule_overrides",
+        audit::SCHEDULE_OVERRIDE,
+        id,
+    )
+    .await
+}
+
+/// Funcion para cerrar un grupo o un aula entre dos fechas
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `resource` - "group" (el grupo no tiene clases) o "classroom" (las clases se quedan sin aula)
+/// * `resource_id` - Id del grupo o del aula
+/// * `start_date` - Primer dia del cierre ("AAAA-MM-DD")
+/// * `end_date` - Ultimo dia del cierre, el mismo si no se manda
+/// * `start_module` / `end_module` - Modulos del cierre (empiezan en 0), todo el dia si no se mandan
+/// * `kind` - Motivo corto (ej. "trip", "repairs"), "closure" si no se manda
+/// * `note` - Nota opcional
+#[allow(clippy::too_many_arguments)]
+#[tauri::command(rename_all = "snake_case")]
+pub async fn create_resource_closure(
+    pool: tauri::State<'_, AppState>,
+    resource: String,
+    resource_id: i16,
+    start_date: String,
+    end_date: Option<String>,
+    start_module: Option<i16>,
+    end_module: Option<i16>,
+    kind: Option<String>,
+    note: Option<String>,
+) -> Result<ResourceClosure, String> {
+    let start = parse_date(&start_date)?;
+    let end = match end_date {
+        Some(end_date) => parse_date(&end_date)?,
+        None => start,
+    };
+    if end < start {
+        return Err("The closure ends before it starts".to_string());
+    }
+
+    let snapshot = load_snapshot(&pool.db).await?;
+    let exists = match resource.as_str() {
+        CLOSE_GROUP => snapshot.group(resource_id).is_some(),
+        CLOSE_CLASSROOM => snapshot.classroom(resource_id).is_some(),
+        other => return Err(format!("Can't close '{}', use group or classroom", other)),
+    };
+    if !exists {
+        return Err(format!("{} {} does not exist", resource, resource_id));
+    }
+
+    let last_module = snapshot.modules_per_day - 1;
+    let (first, last) = (start_module.unwrap_or(0), end_module.unwrap_or(last_module));
+    if first < 0 || last > last_module || first > last {
+        return Err(format!(
+            "Modules {} to {} are outside the day ({} modules per day)",
+            first + 1,
+            last + 1,
+            snapshot.modules_per_day
+        ));
+    }
+
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let id = sqlx::query(&format!(
+        "INSERT INTO resource_closures
+            (resource, resource_id, start_date, end_date, start_module, end_module, kind, note, created_at,
+             term_id)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, {})",
+        ACTIVE_TERM
+    ))
+    .bind(&resource)
+    .bind(resource_id)
+    .bind(format_date(start))
+    .bind(format_date(end))
+    .bind(start_module)
+    .bind(end_module)
+    .bind(
+        kind.as_deref()
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+            .unwrap_or("closure"),
+    )
+    .bind(note.as_deref().map(str::trim).filter(|n| !n.is_empty()))
+    .bind(chrono::Utc::now().timestamp())
+    .execute(&mut tx)
+    .await
+    .map_err(|e| format!("Failed to create closure: {}", e))?
+    .last_insert_rowid();
+
+    let new_value = audit::row_json(&mut tx, "resource_closures", id).await?;
+    audit::record(
+        &mut tx,
+        audit::RESOURCE_CLOSURE,
+        Some(id),
+        "create",
+        None,
+        new_value.as_ref(),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    sqlx::query_as::<_, ResourceClosure>(&format!("{} WHERE id = ?1", SELECT_CLOSURES))
+        .bind(id)
+        .fetch_one(&pool.db)
+        .await
+        .map_err(|e| format!("Failed to get closure: {}", e))
+}
+
+/// Funcion para quitar el cierre de un grupo o un aula
+#[tauri::command]
+pub async fn delete_resource_closure(
+    pool: tauri::State<'_, AppState>,
+    id: i64,
+) -> Result<(), String> {
+    audit::delete_recorded_in_active_term(
+        &pool.db,
+        "resource_closures",
+        audit::RESOURCE_CLOSURE,
+        id,
+    )
+    .await
+}
+
+/// Funcion para obtener el horario efectivo de una fecha
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `date` - Fecha a revisar ("AAAA-MM-DD")
+/// * `group_id` / `teacher_id` / `classroom_id` - Filtros opcionales
+/// Retorna las clases que se dan ese dia con los cambios y cierres aplicados y las que
+/// se cancelaron; si la fecha es festivo no hay clases
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_effective_timetable(
+    pool: tauri::State<'_, AppState>,
+    date: String,
+    group_id: Option<i16>,
+    teacher_id: Option<i16>,
+    classroom_id: Option<i16>,
+) -> Result<EffectiveDay, String> {
+    let date = parse_date(&date)?;
+    let date_text = format_date(date);
+
+    let snapshot = load_snapshot(&pool.db).await?;
+    let holidays = fetch_holidays_between(&pool.db, &date_text, &date_text).await?;
+    let changes = fetch_date_changes(&pool.db, &date_text, &date_text).await?;
+
+    let mut timetable = effective_timetable(&snapshot, date, &holidays, &changes);
+    for lessons in [&mut timetable.lessons, &mut timetable.cancelled] {
+        lessons.retain(|s| {
+            group_id.map_or(true, |g| s.lesson.group_id == g)
+                && teacher_id.map_or(true, |t| s.lesson.teacher_id == t)
+                && classroom_id.map_or(true, |c| s.lesson.classroom_id == c)
+        });
+    }
+
+    Ok(timetable)
+}
+
+/// Funcion que guarda (o reemplaza) el cambio de una celda en una fecha
+/// Revisa que la fecha tenga clases, que el modulo, la materia, el profesor y el aula
+/// existan, que el profesor de esa materia y que el grupo, el profesor y el aula esten
+/// libres y sin cierres ese dia; `absence_id` liga el cambio a una ausencia
+pub async fn save_override(
+    db: &DbPool,
+    snapshot: &Snapshot,
+    edit: &OverrideEdit,
+    absence_id: Option<i64>,
+) -> Result<ScheduleOverride, String> {
+    let date = parse_date(&edit.date)?;
+    let date_text = format_date(date);
+    if school_day(&snapshot.days, date).is_none() {
+        return Err(format!("There are no classes on {}", date_text));
+    }
+    if snapshot.group(edit.group_id).is_none() {
+        return Err(format!("Group {} does not exist", edit.group_id));
+    }
+    if edit.module_index < 0 || edit.module_index >= snapshot.modules_per_day {
+        return Err(format!(
+            "Module {} is outside the day ({} modules per day)",
+            edit.module_index + 1,
+            snapshot.modules_per_day
+        ));
+    }
+
+    let gives_class = edit.subject_id.is_some() && edit.teacher_id.is_some();
+    let kind = edit
+        .kind
+        .as_deref()
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+        .unwrap_or(if gives_class { "change" } else { "cancelled" });
+    if !OVERRIDE_KINDS.contains(&kind) {
+        return Err(format!(
+            "Unknown override kind '{}', use one of: {}",
+            kind,
+            OVERRIDE_KINDS.join(", ")
+        ));
+    }
+    let classroom_id = edit.classroom_id.filter(|&c| c != 0);
+
+    if let (Some(subject_id), Some(teacher_id)) = (edit.subject_id, edit.teacher_id) {
+        if snapshot.subject(subject_id).is_none() {
+            return Err(format!("Subject {} does not exist", subject_id));
+        }
+        if snapshot.teacher(teacher_id).is_none() {
+            return Err(format!("Teacher {} does not exist", teacher_id));
+        }
+        if !snapshot.is_qualified(teacher_id, subject_id) {
+            return Err(format!(
+                "Teacher {} is not registered for subject {}",
+                snapshot.teacher_label(teacher_id),
+                snapshot.subject_label(subject_id)
+            ));
+        }
+        if let Some(classroom_id) = classroom_id.filter(|&c| snapshot.classroom(c).is_none()) {
+            return Err(format!("Classroom {} does not exist", classroom_id));
+        }
+    }
+
+    // Los conflictos se leen en la misma transaccion que guarda el cambio
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    if gives_class {
+        let changes = fetch_date_changes_tx(&mut tx, &date_text, &date_text).await?;
+        // Un grupo cerrado no tiene clases y un aula cerrada no se puede usar
+        let mut resources = vec![(
+            CLOSE_GROUP,
+            edit.group_id,
+            snapshot.group_label(edit.group_id),
+        )];
+        if let Some(classroom_id) = classroom_id {
+            resources.push((
+                CLOSE_CLASSROOM,
+                classroom_id,
+                snapshot.classroom_label(classroom_id),
+            ));
+        }
+        for (resource, id, label) in resources {
+            if let Some(c) = changes.closure(resource, id, &date_text, edit.module_index) {
+                return Err(format!(
+                    "{} is closed on {} module {} ({})",
+                    label,
+                    date_text,
+                    edit.module_index + 1,
+                    c.kind
+                ));
+            }
+        }
+
+        let others = changes.without_cell(&date_text, edit.group_id, edit.module_index);
+        let (lessons, _) = apply_changes(snapshot, &snapshot.assignments, date, &others);
+        for lesson in lessons.iter().map(|l| &l.assignment) {
+            if lesson.module_index != edit.module_index || lesson.group_id == edit.group_id {
+                continue;
+            }
+            if Some(lesson.teacher_id) == edit.teacher_id {
+                return Err(format!(
+                    "{} already teaches {} on {} module {}",
+                    snapshot.teacher_label(lesson.teacher_id),
+                    snapshot.group_label(lesson.group_id),
+                    date_text,
+                    edit.module_index + 1
+                ));
+            }
+            if classroom_id.is_some_and(|c| c == lesson.classroom_id) {
+                return Err(format!(
+                    "{} is used by {} on {} module {}",
+                    snapshot.classroom_label(lesson.classroom_id),
+                    snapshot.group_label(lesson.group_id),
+                    date_text,
+                    edit.module_index + 1
+                ));
+            }
+        }
+    }
+
+    let old_value =
+        match existing_override(&mut tx, &date_text, edit.group_id, edit.module_index).await? {
+            Some(id) => audit::row_json(&mut tx, "schedule_overrides", id).await?,
+            None => None,
+        };
+
+    sqlx::query(&format!(
+        "INSERT INTO schedule_overrides
+            (date, group_id, module_index, subject_id, teacher_id, classroom_id, kind, absence_id,
+             note, created_at, term_id)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, {})
+        ON CONFLICT(term_id, date, group_id, module_index) DO UPDATE SET
+            subject_id = ?4, teacher_id = ?5, classroom_id = ?6, kind = ?7, absence_id = ?8,
+            note = ?9, created_at = ?10",
+        ACTIVE_TERM
+    ))
+    .bind(&date_text)
+    .bind(edit.group_id)
+    .bind(edit.module_index)
+    .bind(edit.subject_id.filter(|_| gives_class))
+    .bind(edit.teacher_id.filter(|_| gives_class))
+    .bind(classroom_id.filter(|_| gives_class))
+    .bind(kind)
+    .bind(absence_id)
+    .bind(
+        edit.note
+            .as_deref()
+            .map(str::trim)
+            .filter(|n| !n.is_empty()),
+    )
+    .bind(chrono::Utc::now().timestamp())
+    .execute(&mut tx)
+    .await
+    .map_err(|e| format!("Failed to save schedule override: {}", e))?;
+
+    let id = existing_override(&mut tx, &date_text, edit.group_id, edit.module_index)
+        .await?
+        .ok_or_else(|| "Failed to save schedule override".to_string())?;
+    let new_value = audit::row_json(&mut tx, "schedule_overrides", id).await?;
+    audit::record(
+        &mut tx,
+        audit::SCHEDULE_OVERRIDE,
+        Some(id),
+        if old_value.is_some() {
+            "update"
+        } else {
+            "create"
+        },
+        old_value.as_ref(),
+        new_value.as_ref(),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    sqlx::query_as::<_, ScheduleOverride>(&format!("{} WHERE id = ?1", SELECT_OVERRIDES))
+        .bind(id)
+        .fetch_one(db)
+        .await
+        .map_err(|e| format!("Failed to get schedule override: {}", e))
+}
+
+/// Funcion que retorna los cambios por celda y los cierres del periodo activo entre
+/// `from` y `to` (las dos incluidas)
+pub async fn fetch_date_changes(db: &DbPool, from: &str, to: &str) -> Result<DateChanges, String> {
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+    fetch_date_changes_tx(&mut tx, from, to).await
+}
+
+// Igual que `fetch_date_changes` dentro de una transaccion
+async fn fetch_date_changes_tx(
+    tx: &mut Tx<'_>,
+    from: &str,
+    to: &str,
+) -> Result<DateChanges, String> {
+    let overrides = sqlx::query_as::<_, ScheduleOverride>(&format!(
+        "{} WHERE term_id = {} AND date >= ?1 AND date <= ?2
+        ORDER BY date, group_id, module_index",
+        SELECT_OVERRIDES, ACTIVE_TERM
+    ))
+    .bind(from)
+    .bind(to)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to get schedule overrides: {}", e))?;
+
+    let closures = sqlx::query_as::<_, ResourceClosure>(&format!(
+        "{} WHERE term_id = {} AND end_date >= ?1 AND start_date <= ?2
+        ORDER BY start_date, id",
+        SELECT_CLOSURES, ACTIVE_TERM
+    ))
+    .bind(from)
+    .bind(to)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to get closures: {}", e))?;
+
+    Ok(DateChanges {
+        overrides,
+        closures,
+    })
+}
+
+const SELECT_OVERRIDES: &str = "SELECT id, date, group_id, module_index, subject_id, teacher_id,
+        classroom_id, kind, absence_id, note
+    FROM schedule_overrides";
+
+const SELECT_CLOSURES: &str = "SELECT id, resource, resource_id, start_date, end_date,
+        start_module, end_module, kind, note
+    FROM resource_closures";
+
+// Id del cambio guardado para una celda en una fecha
+async fn existing_override(
+    tx: &mut Tx<'_>,
+    date: &str,
+    group_id: i16,
+    module_index: i16,
+) -> Result<Option<i64>, String> {
+    sqlx::query_scalar(&format!(
+        "SELECT id FROM schedule_overrides
+        WHERE term_id = {} AND date = ?1 AND group_id = ?2 AND module_index = ?3",
+        ACTIVE_TERM
+    ))
+    .bind(date)
+    .bind(group_id)
+    .bind(module_index)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to get schedule override: {}", e))
+}
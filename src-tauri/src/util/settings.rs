@@ -1,7 +1,8 @@
 use crate::db::AppState;
+pub use crate::model::{Config, SchoolInfo};
+use crate::util::audit;
 use rfd::FileDialog;
-use serde::{Deserialize, Serialize};
-use sqlx::Row;
+use sqlx::{Executor, Row, Sqlite};
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
@@ -9,61 +10,24 @@ use tauri::api::dialog::FileDialogBuilder;
 use tauri::api::path::app_data_dir;
 use tauri::Manager;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct Config {
-    days: Vec<String>,
-    #[serde(rename = "modulesPerDay")]
-    pub modules_per_day: u32,
-    #[serde(rename = "moduleDuration")]
-    pub module_duration: u32,
-    #[serde(rename = "durationUnit")]
-    pub duration_unit: String,
-    #[serde(rename = "hasBreaks")]
-    pub has_breaks: bool,
-    #[serde(rename = "breakCount")]
-    pub break_count: u32,
-    #[serde(rename = "breakDuration")]
-    pub break_duration: u32,
-    #[serde(rename = "breakPositions")]
-    pub break_positions: Vec<u32>,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct SchoolInfo {
-    pub name: String,
-    pub logo_path: Option<String>,
-}
-
-impl Default for Config {
-    fn default() -> Self {
-        Config {
-            days: vec![
-                "Lunes".to_string(),
-                "Martes".to_string(),
-                "Miércoles".to_string(),
-                "Jueves".to_string(),
-                "Viernes".to_string(),
-            ],
-            modules_per_day: 9,
-            module_duration: 50,
-            duration_unit: "minutes".to_string(),
-            has_breaks: false,
-            break_count: 1,
-            break_duration: 30,
-            break_positions: vec![2],
-        }
-    }
-}
-
 #[tauri::command]
 pub async fn get_config(pool: tauri::State<'_, AppState>) -> Result<Config, String> {
+    load_config(&pool.db).await
+}
+
+/// Funcion para leer la configuracion del horario directamente del pool (o de una transaccion)
+/// Retorna la configuracion por defecto si no se ha guardado ninguna
+pub async fn load_config<'c, E>(db: E) -> Result<Config, String>
+where
+    E: Executor<'c, Database = Sqlite>,
+{
     let cfg = sqlx::query(
         "
         SELECT value FROM config
         WHERE key = 'schedule_config'
         ",
     )
-    .fetch_optional(&pool.db)
+    .fetch_optional(db)
     .await
     .map_err(|e| format!("Error consiguiendo configuracion del horario: {}", e))?;
 
@@ -85,6 +49,13 @@ pub async fn save_config(
 ) -> Result<(), String> {
     let cfg_json = serde_json::to_string(&config)
         .map_err(|e| format!("Failed to serialize config file: {}", e))?;
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let old_value = serde_json::to_value(load_config(&mut tx).await?).ok();
 
     sqlx::query(
         "
@@ -93,10 +64,24 @@ pub async fn save_config(
          ON CONFLICT(key) DO UPDATE SET value = ?1",
     )
     .bind(&cfg_json)
-    .execute(&pool.db)
+    .execute(&mut tx)
     .await
     .map_err(|e| format!("Failed saving configuration: {}", e))?;
 
+    audit::record(
+        &mut tx,
+        audit::CONFIG,
+        None,
+        "update",
+        old_value.as_ref(),
+        serde_json::to_value(&config).ok().as_ref(),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
     app.emit_all("config_updated", ())
         .map_err(|e| format!("Failed to emit event: {}", e))?;
 
@@ -126,13 +111,36 @@ pub async fn save_school_info(
     app: tauri::AppHandle,
 ) -> Result<(), String> {
     println!("Logo path: {}", logo_path);
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let old_value = audit::row_json(&mut tx, "school", 1).await?;
+
     sqlx::query("UPDATE school SET name = ?1, logo_path = ?2 WHERE id = 1")
         .bind(name)
         .bind(logo_path)
-        .execute(&pool.db)
+        .execute(&mut tx)
         .await
         .map_err(|e| format!("Failed saving school info: {}", e))?;
 
+    let new_value = audit::row_json(&mut tx, "school", 1).await?;
+    audit::record(
+        &mut tx,
+        audit::SCHOOL,
+        Some(1),
+        "update",
+        old_value.as_ref(),
+        new_value.as_ref(),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
     app.emit_all("school_info_updated", ())
         .map_err(|e| format!("Failed to emit event: {}", e))?;
 
@@ -0,0 +1,148 @@
+/*
+    Puente entre la base de datos y el motor.
+    Carga el `Snapshot` que usa el motor y guarda los horarios que genera, en el horario
+    actual (con historial) o en el area de prueba.
+*/
+
+use crate::db::DbPool;
+use crate::engine::Snapshot;
+use crate::model::{Assignment, Classroom, Group, GroupSubjects, Subject, Teacher};
+use crate::util::assignments::fetch_all_assignments;
+use crate::util::history;
+use crate::util::settings::load_config;
+
+/// Funcion para cargar todos los datos que necesita el motor de la base de datos
+/// # Argumentos
+/// * `db` - Pool de la base de datos
+/// Retorna el snapshot con la configuracion del horario (dias y modulos) ya aplicada
+/// Las filas se leen ordenadas por id para que la generacion con semilla sea reproducible
+pub async fn load_snapshot(db: &DbPool) -> Result<Snapshot, String> {
+    let subjects = sqlx::query_as::<_, Subject>("SELECT * FROM subjects ORDER BY id")
+        .fetch_all(db)
+        .await
+        .map_err(|e| format!("Failed to load subjects: {}", e))?;
+
+    let teachers = sqlx::query_as::<_, Teacher>("SELECT * FROM teachers ORDER BY id")
+        .fetch_all(db)
+        .await
+        .map_err(|e| format!("Failed to load teachers: {}", e))?;
+
+    let teacher_subjects: Vec<(i16, i16)> = sqlx::query_as(
+        "SELECT teacher_id, subject_id FROM teacher_subjects ORDER BY teacher_id, subject_id",
+    )
+    .fetch_all(db)
+    .await
+    .map_err(|e| format!("Failed to load teacher subjects: {}", e))?;
+
+    let groups = sqlx::query_as::<_, Group>("SELECT * FROM groups ORDER BY id")
+        .fetch_all(db)
+        .await
+        .map_err(|e| format!("Failed to load groups: {}", e))?;
+
+    let group_subjects = sqlx::query_as::<_, GroupSubjects>(
+        "SELECT group_id, subject_id FROM groups_subjects ORDER BY group_id, subject_id",
+    )
+    .fetch_all(db)
+    .await
+    .map_err(|e| format!("Failed to load group subjects: {}", e))?;
+
+    let classrooms = sqlx::query_as::<_, Classroom>("SELECT * FROM classroom ORDER BY id")
+        .fetch_all(db)
+        .await
+        .map_err(|e| format!("Failed to load classrooms: {}", e))?;
+
+    let assignments = fetch_all_assignments(db).await?;
+    let config = load_config(db).await?;
+
+    Ok(Snapshot {
+        subjects,
+        teachers,
+        teacher_subjects,
+        groups,
+        group_subjects,
+        classrooms,
+        assignments,
+        days: config.days,
+        modules_per_day: config.modules_per_day as i16,
+    })
+}
+
+/// Funcion para guardar un horario completo en la base de datos
+/// Reemplaza todas las asignaciones en una sola transaccion y lo guarda en el historial
+/// como un solo paso (`label`), asi se puede deshacer
+pub async fn save_schedule(
+    db: &DbPool,
+    schedule: &[Assignment],
+    label: &str,
+) -> Result<(), String> {
+    write_schedule(db, "assignments", schedule, Some(label)).await
+}
+
+/// Funcion para guardar un horario en el area de prueba (`staged_assignments`)
+/// No toca el horario actual, reemplaza lo que hubiera en prueba
+pub async fn stage_schedule(db: &DbPool, schedule: &[Assignment]) -> Result<(), String> {
+    write_schedule(db, "staged_assignments", schedule, None).await
+}
+
+// `table` siempre es una de las dos tablas de horario, nunca viene del usuario
+async fn write_schedule(
+    db: &DbPool,
+    table: &str,
+    schedule: &[Assignment],
+    history_label: Option<&str>,
+) -> Result<(), String> {
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let before = match history_label {
+        Some(_) => history::read_schedule(&mut tx).await?,
+        None => Vec::new(),
+    };
+
+    sqlx::query(&format!("DELETE FROM {}", table))
+        .execute(&mut tx)
+        .await
+        .map_err(|e| format!("Error clearing existing assignments: {}", e))?;
+
+    for assignment in schedule {
+        sqlx::query(&format!(
+            "INSERT INTO {} (group_id, day, module_index, subject_id, teacher_id, classroom_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT (group_id, day, module_index) DO UPDATE
+            SET subject_id = excluded.subject_id,
+                teacher_id = excluded.teacher_id,
+                classroom_id = excluded.classroom_id",
+            table
+        ))
+        .bind(assignment.group_id)
+        .bind(&assignment.day)
+        .bind(assignment.module_index)
+        .bind(assignment.subject_id)
+        .bind(assignment.teacher_id)
+        .bind(classroom_or_null(assignment.classroom_id))
+        .execute(&mut tx)
+        .await
+        .map_err(|e| format!("Error inserting assignment: {}", e))?;
+    }
+
+    if let Some(label) = history_label {
+        history::record_replace(&mut tx, label, &before).await?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(())
+}
+
+// En memoria el 0 significa "sin aula", en la base de datos es NULL
+fn classroom_or_null(classroom_id: i16) -> Option<i16> {
+    if classroom_id == 0 {
+        None
+    } else {
+        Some(classroom_id)
+    }
+}
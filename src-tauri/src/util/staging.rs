@@ -0,0 +1,230 @@
+/*
+    Generacion en modo de prueba.
+    El horario generado se guarda en `staged_assignments` en lugar de `assignments`,
+    se muestra la diferencia contra el horario actual y despues se aplica o se descarta.
+    Junto con el horario en prueba se guarda la huella del horario del que se partio; si el
+    horario actual cambia (ediciones, deshacer, borrados) ya no se aplica y hay que generarlo otra vez.
+*/
+
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::db::{AppState, DbPool};
+use crate::engine::{
+    self, diff_lessons, GenerateOptions, GenerationReport, GenerationScope, ScheduleDiff,
+};
+use crate::util::snapshot::{load_snapshot, stage_schedule};
+
+use super::assignments::{fetch_all_assignments, Assignment};
+use super::history::{self, Tx};
+
+/// Horario en prueba junto con su diferencia contra el horario actual
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StagedSchedule {
+    pub report: GenerationReport,
+    pub diff: ScheduleDiff,
+}
+
+/// Funcion para generar un horario sin tocar el actual
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `scope` - Si se manda, solo se regenera esa parte (igual que `regenerate_schedule`)
+/// * `options` - Semilla, limite de tiempo e intentos (opcional)
+/// Retorna el reporte y las clases agregadas, quitadas y cambiadas
+#[tauri::command]
+pub async fn preview_schedule(
+    pool: tauri::State<'_, AppState>,
+    scope: Option<GenerationScope>,
+    options: Option<GenerateOptions>,
+) -> Result<StagedSchedule, String> {
+    let options = options.unwrap_or_default();
+    let snapshot = load_snapshot(&pool.db).await?;
+
+    let (snapshot, report) = tokio::task::spawn_blocking(move || {
+        let report = match scope.filter(|s| !s.is_empty()) {
+            Some(scope) => engine::regenerate(&snapshot, &scope, &options).report,
+            None => engine::generate(&snapshot, &options),
+        };
+        (snapshot, report)
+    })
+    .await
+    .map_err(|e| format!("Schedule generation failed: {}", e))?;
+
+    stage_schedule(&pool.db, &report.assignments).await?;
+
+    sqlx::query(
+        "INSERT INTO staged_schedule_base (id, fingerprint, created_at) VALUES (1, ?1, ?2)
+        ON CONFLICT (id) DO UPDATE
+        SET fingerprint = excluded.fingerprint, created_at = excluded.created_at",
+    )
+    .bind(schedule_fingerprint(&snapshot.assignments))
+    .bind(chrono::Utc::now().timestamp())
+    .execute(&pool.db)
+    .await
+    .map_err(|e| format!("Failed to save the staged schedule base: {}", e))?;
+
+    let diff = diff_lessons(&snapshot, &snapshot.assignments, &report.assignments);
+    println!(
+        "Staged schedule: {} added, {} removed, {} changed",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.changed.len()
+    );
+
+    Ok(StagedSchedule { report, diff })
+}
+
+/// Funcion para consultar la diferencia del horario que esta en prueba
+/// Retorna `None` si no hay horario en prueba
+#[tauri::command]
+pub async fn get_staged_diff(
+    pool: tauri::State<'_, AppState>,
+) -> Result<Option<ScheduleDiff>, String> {
+    let staged = fetch_staged_assignments(&pool.db).await?;
+    if staged.is_empty() {
+        return Ok(None);
+    }
+
+    let snapshot = load_snapshot(&pool.db).await?;
+    Ok(Some(diff_lessons(
+        &snapshot,
+        &snapshot.assignments,
+        &staged,
+    )))
+}
+
+/// Funcion para pasar el horario en prueba al horario actual
+/// Reemplaza todas las asignaciones y vacia el area de prueba en una sola transaccion
+/// Falla si el horario actual cambio desde que se genero la prueba
+#[tauri::command]
+pub async fn apply_staged_schedule(
+    pool: tauri::State<'_, AppState>,
+) -> Result<Vec<Assignment>, String> {
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let (staged,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM staged_assignments")
+        .fetch_one(&mut tx)
+        .await
+        .map_err(|e| format!("Failed to read staged schedule: {}", e))?;
+
+    if staged == 0 {
+        return Err("There is no staged schedule to apply".to_string());
+    }
+
+    let before = history::read_schedule(&mut tx).await?;
+
+    let base: Option<(String,)> =
+        sqlx::query_as("SELECT fingerprint FROM staged_schedule_base WHERE id = 1")
+            .fetch_optional(&mut tx)
+            .await
+            .map_err(|e| format!("Failed to read staged schedule base: {}", e))?;
+    if base.map(|(f,)| f) != Some(schedule_fingerprint(&before)) {
+        return Err(
+            "The schedule changed after the preview was generated, generate the preview again"
+                .to_string(),
+        );
+    }
+
+    sqlx::query("DELETE FROM assignments")
+        .execute(&mut tx)
+        .await
+        .map_err(|e| format!("Error clearing existing assignments: {}", e))?;
+
+    sqlx::query(
+        "INSERT INTO assignments (group_id, day, module_index, subject_id, teacher_id, classroom_id)
+        SELECT group_id, day, module_index, subject_id, teacher_id, classroom_id
+        FROM staged_assignments",
+    )
+    .execute(&mut tx)
+    .await
+    .map_err(|e| format!("Error applying staged schedule: {}", e))?;
+
+    clear_staged(&mut tx).await?;
+
+    history::record_replace(&mut tx, "Aplicar horario en prueba", &before).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    println!("Staged schedule applied ({} assignments)", staged);
+    fetch_all_assignments(&pool.db).await
+}
+
+/// Funcion para descartar el horario en prueba
+#[tauri::command]
+pub async fn discard_staged_schedule(pool: tauri::State<'_, AppState>) -> Result<(), String> {
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    clear_staged(&mut tx).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))
+}
+
+// Vacia el area de prueba y su huella
+async fn clear_staged(tx: &mut Tx<'_>) -> Result<(), String> {
+    for table in ["staged_assignments", "staged_schedule_base"] {
+        sqlx::query(&format!("DELETE FROM {}", table))
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Error clearing staged schedule: {}", e))?;
+    }
+    Ok(())
+}
+
+// Huella del contenido del horario: numero de clases y CRC32 de las celdas ordenadas
+// No depende del id ni del orden de las filas
+fn schedule_fingerprint(schedule: &[Assignment]) -> String {
+    let mut cells: Vec<_> = schedule
+        .iter()
+        .map(|a| {
+            (
+                a.group_id,
+                a.day.as_str(),
+                a.module_index,
+                a.subject_id,
+                a.teacher_id,
+                a.classroom_id,
+            )
+        })
+        .collect();
+    cells.sort();
+
+    let mut hasher = crc32fast::Hasher::new();
+    for (group_id, day, module_index, subject_id, teacher_id, classroom_id) in cells {
+        hasher.update(
+            format!(
+                "{}|{}|{}|{}|{}|{}\n",
+                group_id, day, module_index, subject_id, teacher_id, classroom_id
+            )
+            .as_bytes(),
+        );
+    }
+    format!("{}:{:08x}", schedule.len(), hasher.finalize())
+}
+
+/// Funcion que retorna las asignaciones del horario en prueba
+pub async fn fetch_staged_assignments(db: &DbPool) -> Result<Vec<Assignment>, String> {
+    sqlx::query_as::<_, Assignment>(
+        r#"
+        SELECT a.id, a.group_id, a.day, a.module_index, a.teacher_id, a.classroom_id,
+               s.id as subject_id, s.name as subject_name, s.color as subject_color, s.shorten as subject_shorten
+        FROM staged_assignments a
+        JOIN subjects s ON a.subject_id = s.id
+        "#,
+    )
+    .fetch(db)
+    .try_collect()
+    .await
+    .map_err(|e| format!("An error occurred while getting the staged assignments: {}", e))
+}
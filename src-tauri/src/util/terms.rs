@@ -0,0 +1,421 @@
+/*
+    Periodos escolares con datos propios.
+    Los grupos, alumnos, horario y configuracion del periodo activo viven en las tablas
+    normales; al cambiar de periodo se guardan en las tablas `term_*` y se cargan los del
+    otro periodo. El cambio de periodo sube de grado a los grupos, da de baja el ultimo
+    grado y conserva profesores, materias y aulas (que son de toda la escuela).
+    Las versiones del horario, los cambios por fecha, los cierres y las ausencias llevan
+    el periodo en que se crearon (`term_id`) y solo se ven mientras ese periodo esta activo.
+*/
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::db::{AppState, DbPool};
+use crate::engine::{plan_rollover, RolloverOptions, Term};
+use crate::util::snapshot::load_snapshot;
+
+use super::audit;
+use super::calendar::{check_range, fetch_term};
+use super::history::Tx;
+
+/// Id del periodo activo dentro de una consulta, 0 si no se ha elegido ninguno
+pub const ACTIVE_TERM: &str = "COALESCE((SELECT id FROM terms WHERE active = 1), 0)";
+
+/// Resultado de pasar al siguiente periodo
+/// `promoted` son los grupos que subieron ("1A -> 2A"), `retired` los que egresaron,
+/// `created` los grupos nuevos de primer grado y `assignments` las clases copiadas
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RolloverReport {
+    pub term: Term,
+    pub promoted: Vec<String>,
+    pub retired: Vec<String>,
+    pub created: Vec<String>,
+    pub assignments: usize,
+}
+
+/// Funcion para obtener el periodo activo, `None` si no se ha elegido ninguno
+#[tauri::command]
+pub async fn get_active_term(pool: tauri::State<'_, AppState>) -> Result<Option<Term>, String> {
+    fetch_active_term(&pool.db).await
+}
+
+/// Funcion para cambiar el periodo activo
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `term_id` - Periodo a activar
+/// Los grupos, alumnos, horario y configuracion actuales se guardan en el periodo que
+/// estaba activo y se cargan los del periodo elegido. Si el periodo elegido nunca se ha
+/// guardado se queda con los datos actuales (asi se adopta la base de datos existente)
+/// El historial para deshacer se borra, sus cambios son de otro periodo
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_active_term(
+    pool: tauri::State<'_, AppState>,
+    term_id: i64,
+) -> Result<Term, String> {
+    let target = fetch_term(&pool.db, term_id).await?;
+    if target.active {
+        return Ok(target);
+    }
+    let current = fetch_active_term(&pool.db).await?;
+
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    if let Some(current) = &current {
+        archive_live(&mut tx, current.id).await?;
+    }
+    if target.archived_at.is_some() {
+        restore_live(&mut tx, target.id).await?;
+    } else if current.is_none() {
+        adopt_term_data(&mut tx, target.id).await?;
+    }
+    mark_active(&mut tx, target.id).await?;
+
+    audit::record(
+        &mut tx,
+        audit::TERM,
+        Some(target.id),
+        "activate",
+        current
+            .as_ref()
+            .map(|t| json!({ "id": t.id, "name": t.name }))
+            .as_ref(),
+        Some(&json!({ "id": target.id, "name": target.name })),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    println!("Active term: {}", target.name);
+    fetch_term(&pool.db, term_id).await
+}
+
+/// Funcion para crear el siguiente periodo a partir del periodo activo
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `name` - Nombre del nuevo periodo
+/// * `start_date` - Primer dia del nuevo periodo ("AAAA-MM-DD"), despues del fin del activo
+/// * `end_date` - Ultimo dia del nuevo periodo ("AAAA-MM-DD")
+/// * `options` - Ultimo grado, grupos nuevos de primer grado y si se copia el horario
+/// Los datos del periodo activo se guardan antes de cambiar nada; los grupos suben un grado,
+/// los del ultimo grado egresan (con sus alumnos) y el nuevo periodo queda activo
+#[tauri::command(rename_all = "snake_case")]
+pub async fn rollover_term(
+    pool: tauri::State<'_, AppState>,
+    name: String,
+    start_date: String,
+    end_date: String,
+    options: Option<RolloverOptions>,
+) -> Result<RolloverReport, String> {
+    let options = options.unwrap_or_default();
+    let (name, start, end) = check_range(&name, &start_date, &end_date)?;
+
+    let current = fetch_active_term(&pool.db)
+        .await?
+        .ok_or_else(|| "Choose the active term before creating the next one".to_string())?;
+    if start <= current.end_date {
+        return Err(format!(
+            "The new term must start after {} ends ({})",
+            current.name, current.end_date
+        ));
+    }
+
+    let snapshot = load_snapshot(&pool.db).await?;
+    let plan = plan_rollover(&snapshot, &options);
+
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    archive_live(&mut tx, current.id).await?;
+
+    let term_id = sqlx::query(
+        "INSERT INTO terms (name, start_date, end_date, previous_term_id, created_at)
+        VALUES (?1, ?2, ?3, ?4, ?5)",
+    )
+    .bind(name)
+    .bind(&start)
+    .bind(&end)
+    .bind(current.id)
+    .bind(chrono::Utc::now().timestamp())
+    .execute(&mut tx)
+    .await
+    .map_err(|e| format!("Failed to create term: {}", e))?
+    .last_insert_rowid();
+
+    clear_live(&mut tx).await?;
+
+    for group in &plan.groups {
+        sqlx::query(
+            r#"INSERT INTO groups (id, grade, "group", career, students, max_modules_per_day)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+        )
+        .bind(group.id)
+        .bind(group.grade)
+        .bind(&group.group)
+        .bind(&group.career)
+        .bind(group.students)
+        .bind(group.max_modules_per_day)
+        .execute(&mut tx)
+        .await
+        .map_err(|e| {
+            format!(
+                "Failed to create group {}{}: {}",
+                group.grade, group.group, e
+            )
+        })?;
+    }
+
+    for gs in &plan.group_subjects {
+        sqlx::query("INSERT INTO groups_subjects (group_id, subject_id) VALUES (?1, ?2)")
+            .bind(gs.group_id)
+            .bind(gs.subject_id)
+            .execute(&mut tx)
+            .await
+            .map_err(|e| format!("Failed to link group subjects: {}", e))?;
+    }
+
+    // Los alumnos siguen a su grupo; los de los grupos que egresan solo quedan en el
+    // periodo anterior
+    sqlx::query(
+        "INSERT INTO students (id, name, father_lastname, mother_lastname, group_id)
+        SELECT student_id, name, father_lastname, mother_lastname, group_id
+        FROM term_students
+        WHERE term_id = ?1 AND (group_id IS NULL OR group_id IN (SELECT id FROM groups))",
+    )
+    .bind(current.id)
+    .execute(&mut tx)
+    .await
+    .map_err(|e| format!("Failed to move students: {}", e))?;
+
+    for a in &plan.assignments {
+        sqlx::query(
+            "INSERT INTO assignments (group_id, day, module_index, subject_id, teacher_id, classroom_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )
+        .bind(a.group_id)
+        .bind(&a.day)
+        .bind(a.module_index)
+        .bind(a.subject_id)
+        .bind(a.teacher_id)
+        .bind(Some(a.classroom_id).filter(|&c| c != 0))
+        .execute(&mut tx)
+        .await
+        .map_err(|e| format!("Failed to copy timetable: {}", e))?;
+    }
+
+    mark_active(&mut tx, term_id).await?;
+
+    let report = RolloverReport {
+        term: fetch_term_tx(&mut tx, term_id).await?,
+        promoted: plan.promoted,
+        retired: plan.retired,
+        created: plan.created,
+        assignments: plan.assignments.len(),
+    };
+
+    audit::record(
+        &mut tx,
+        audit::TERM,
+        Some(term_id),
+        "rollover",
+        Some(&json!({ "id": current.id, "name": current.name })),
+        Some(&json!({
+            "id": term_id,
+            "name": report.term.name,
+            "promoted": report.promoted,
+            "retired": report.retired,
+            "created": report.created,
+        })),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    println!(
+        "Rollover {} -> {}: {} promoted, {} retired, {} created",
+        current.name,
+        report.term.name,
+        report.promoted.len(),
+        report.retired.len(),
+        report.created.len()
+    );
+
+    Ok(report)
+}
+
+/// Funcion que retorna el periodo activo
+pub async fn fetch_active_term(db: &DbPool) -> Result<Option<Term>, String> {
+    sqlx::query_as::<_, Term>(
+        "SELECT id, name, start_date, end_date, active, previous_term_id, archived_at
+        FROM terms WHERE active = 1",
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(|e| format!("Failed to get active term: {}", e))
+}
+
+async fn fetch_term_tx(tx: &mut Tx<'_>, id: i64) -> Result<Term, String> {
+    sqlx::query_as::<_, Term>(
+        "SELECT id, name, start_date, end_date, active, previous_term_id, archived_at
+        FROM terms WHERE id = ?1",
+    )
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to get term: {}", e))
+}
+
+// Guarda los grupos, alumnos, horario y configuracion actuales en un periodo
+async fn archive_live(tx: &mut Tx<'_>, term_id: i64) -> Result<(), String> {
+    for table in [
+        "term_groups",
+        "term_groups_subjects",
+        "term_students",
+        "term_assignments",
+    ] {
+        sqlx::query(&format!("DELETE FROM {} WHERE term_id = ?1", table))
+            .bind(term_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to clear saved term data: {}", e))?;
+    }
+
+    let copies = [
+        r#"INSERT INTO term_groups
+            (term_id, group_id, grade, "group", career, students, max_modules_per_day)
+        SELECT ?1, id, grade, "group", career, students, max_modules_per_day FROM groups"#,
+        "INSERT INTO term_groups_subjects (term_id, group_id, subject_id)
+        SELECT ?1, group_id, subject_id FROM groups_subjects",
+        "INSERT INTO term_students
+            (term_id, student_id, name, father_lastname, mother_lastname, group_id)
+        SELECT ?1, id, name, father_lastname, mother_lastname, group_id FROM students",
+        "INSERT INTO term_assignments
+            (term_id, group_id, day, module_index, subject_id, teacher_id, classroom_id)
+        SELECT ?1, group_id, day, module_index, subject_id, teacher_id, classroom_id
+        FROM assignments",
+    ];
+    for query in copies {
+        sqlx::query(query)
+            .bind(term_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to save term data: {}", e))?;
+    }
+
+    sqlx::query(
+        "UPDATE terms SET archived_at = ?2,
+            schedule_config = (SELECT value FROM config WHERE key = 'schedule_config')
+        WHERE id = ?1",
+    )
+    .bind(term_id)
+    .bind(chrono::Utc::now().timestamp())
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save term data: {}", e))?;
+
+    Ok(())
+}
+
+// Reemplaza los datos actuales por los guardados en un periodo
+// Las clases cuya materia o profesor ya no existen no se cargan y las aulas borradas
+// se quedan vacias, igual que al restaurar una version del horario
+async fn restore_live(tx: &mut Tx<'_>, term_id: i64) -> Result<(), String> {
+    clear_live(tx).await?;
+
+    let copies = [
+        r#"INSERT INTO groups (id, grade, "group", career, students, max_modules_per_day)
+        SELECT group_id, grade, "group", career, students, max_modules_per_day
+        FROM term_groups WHERE term_id = ?1"#,
+        "INSERT INTO groups_subjects (group_id, subject_id)
+        SELECT group_id, subject_id FROM term_groups_subjects
+        WHERE term_id = ?1 AND subject_id IN (SELECT id FROM subjects)",
+        "INSERT INTO students (id, name, father_lastname, mother_lastname, group_id)
+        SELECT student_id, name, father_lastname, mother_lastname, group_id
+        FROM term_students WHERE term_id = ?1",
+        "INSERT INTO assignments (group_id, day, module_index, subject_id, teacher_id, classroom_id)
+        SELECT group_id, day, module_index, subject_id, teacher_id,
+               (SELECT c.id FROM classroom c WHERE c.id = t.classroom_id)
+        FROM term_assignments t
+        WHERE term_id = ?1
+          AND subject_id IN (SELECT id FROM subjects)
+          AND teacher_id IN (SELECT id FROM teachers)",
+        "INSERT INTO config (key, value)
+        SELECT 'schedule_config', schedule_config FROM terms
+        WHERE id = ?1 AND schedule_config IS NOT NULL
+        ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+    ];
+    for query in copies {
+        sqlx::query(query)
+            .bind(term_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to load term data: {}", e))?;
+    }
+
+    Ok(())
+}
+
+// Borra los grupos, alumnos y horario actuales junto con el horario en prueba y el
+// historial para deshacer, que ya no corresponden a los grupos nuevos
+async fn clear_live(tx: &mut Tx<'_>) -> Result<(), String> {
+    for table in [
+        "operation_changes",
+        "operation_log",
+        "staged_assignments",
+        "staged_schedule_base",
+        "assignments",
+        "students",
+        "groups_subjects",
+        "groups",
+    ] {
+        sqlx::query(&format!("DELETE FROM {}", table))
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to clear {}: {}", table, e))?;
+    }
+
+    Ok(())
+}
+
+// Pasa al periodo las versiones, cambios por fecha, cierres y ausencias que se crearon
+// antes de elegir un periodo, junto con los datos que adopta
+async fn adopt_term_data(tx: &mut Tx<'_>, term_id: i64) -> Result<(), String> {
+    for table in [
+        "schedule_versions",
+        "schedule_overrides",
+        "resource_closures",
+        "absences",
+    ] {
+        sqlx::query(&format!(
+            "UPDATE {} SET term_id = ?1 WHERE term_id = 0",
+            table
+        ))
+        .bind(term_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to move {} to the term: {}", table, e))?;
+    }
+
+    Ok(())
+}
+
+async fn mark_active(tx: &mut Tx<'_>, term_id: i64) -> Result<(), String> {
+    sqlx::query("UPDATE terms SET active = (id = ?1)")
+        .bind(term_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to change the active term: {}", e))?;
+
+    Ok(())
+}
@@ -0,0 +1,330 @@
+/*
+    Versiones del horario.
+    Cada version es una copia completa de `assignments` con un nombre ("Borrador 1",
+    "Publicado Agosto"...), asi se puede volver a un horario anterior despues de generar
+    o de editar a mano.
+*/
+
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::db::{AppState, DbPool};
+use crate::engine::{compare_schedules, format_change_sheet, ScheduleComparison};
+use crate::util::snapshot::load_snapshot;
+
+use super::assignments::{fetch_all_assignments, Assignment};
+use super::history;
+use super::terms::ACTIVE_TERM;
+
+/// Version guardada del horario
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ScheduleVersion {
+    pub id: i64,
+    pub name: String,
+    pub note: Option<String>,
+    pub created_at: i64,
+    pub assignment_count: i64,
+}
+
+/// Resultado de restaurar una version
+/// `skipped` son las clases que ya no se pueden restaurar porque su grupo,
+/// materia o profesor se borro despues de guardar la version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoredVersion {
+    pub version: ScheduleVersion,
+    pub assignments: Vec<Assignment>,
+    pub skipped: i64,
+}
+
+/// Funcion para guardar el horario actual como una version nueva
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `name` - Nombre de la version
+/// * `note` - Nota opcional (ej. por que se guardo)
+/// Retorna la version creada
+#[tauri::command]
+pub async fn create_schedule_version(
+    pool: tauri::State<'_, AppState>,
+    name: String,
+    note: Option<String>,
+) -> Result<ScheduleVersion, String> {
+    let id = snapshot_current_schedule(&pool.db, &name, note.as_deref()).await?;
+    fetch_version(&pool.db, id).await
+}
+
+/// Funcion para obtener todas las versiones guardadas, de la mas reciente a la mas antigua
+#[tauri::command]
+pub async fn get_schedule_versions(
+    pool: tauri::State<'_, AppState>,
+) -> Result<Vec<ScheduleVersion>, String> {
+    sqlx::query_as::<_, ScheduleVersion>(&format!(
+        "{} WHERE v.term_id = {} ORDER BY v.created_at DESC, v.id DESC",
+        SELECT_VERSIONS, ACTIVE_TERM
+    ))
+    .fetch(&pool.db)
+    .try_collect()
+    .await
+    .map_err(|e| format!("Failed to get schedule versions: {}", e))
+}
+
+/// Funcion para reemplazar el horario actual por una version guardada
+/// Solo se pueden restaurar las versiones del periodo activo
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `version_id` - Id de la version a restaurar
+#[tauri::command(rename_all = "snake_case")]
+pub async fn restore_schedule_version(
+    pool: tauri::State<'_, AppState>,
+    version_id: i64,
+) -> Result<RestoredVersion, String> {
+    let version = fetch_version(&pool.db, version_id).await?;
+
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let before = history::read_schedule(&mut tx).await?;
+
+    sqlx::query("DELETE FROM assignments")
+        .execute(&mut tx)
+        .await
+        .map_err(|e| format!("Error clearing existing assignments: {}", e))?;
+
+    // Solo se restauran las clases cuyo grupo, materia y profesor siguen existiendo
+    // Si el aula ya no existe la clase se queda sin aula
+    let restored = sqlx::query(
+        "INSERT INTO assignments (group_id, day, module_index, subject_id, teacher_id, classroom_id)
+        SELECT v.group_id, v.day, v.module_index, v.subject_id, v.teacher_id,
+               (SELECT c.id FROM classroom c WHERE c.id = v.classroom_id)
+        FROM schedule_version_assignments v
+        WHERE v.version_id = ?1
+          AND v.group_id IN (SELECT id FROM groups)
+          AND v.subject_id IN (SELECT id FROM subjects)
+          AND v.teacher_id IN (SELECT id FROM teachers)",
+    )
+    .bind(version_id)
+    .execute(&mut tx)
+    .await
+    .map_err(|e| format!("Error restoring schedule version: {}", e))?
+    .rows_affected() as i64;
+
+    history::record_replace(
+        &mut tx,
+        &format!("Restaurar version {}", version.name),
+        &before,
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    let skipped = version.assignment_count - restored;
+    println!(
+        "Restored version '{}' ({} assignments, {} skipped)",
+        version.name, restored, skipped
+    );
+
+    Ok(RestoredVersion {
+        version,
+        assignments: fetch_all_assignments(&pool.db).await?,
+        skipped,
+    })
+}
+
+/// Funcion para borrar una version guardada
+#[tauri::command(rename_all = "snake_case")]
+pub async fn delete_schedule_version(
+    pool: tauri::State<'_, AppState>,
+    version_id: i64,
+) -> Result<(), String> {
+    let mut tx = pool
+        .db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    sqlx::query(&format!(
+        "DELETE FROM schedule_version_assignments
+        WHERE version_id IN (SELECT id FROM schedule_versions WHERE id = ?1 AND term_id = {})",
+        ACTIVE_TERM
+    ))
+    .bind(version_id)
+    .execute(&mut tx)
+    .await
+    .map_err(|e| format!("Error deleting version assignments: {}", e))?;
+
+    let deleted = sqlx::query(&format!(
+        "DELETE FROM schedule_versions WHERE id = ?1 AND term_id = {}",
+        ACTIVE_TERM
+    ))
+    .bind(version_id)
+    .execute(&mut tx)
+    .await
+    .map_err(|e| format!("Error deleting schedule version: {}", e))?
+    .rows_affected();
+
+    if deleted == 0 {
+        return Err(format!("Schedule version {} does not exist", version_id));
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(())
+}
+
+/// Funcion para comparar dos versiones del horario por profesor y por grupo
+/// # Argumentos
+/// * `pool` - Conexion a la base de datos
+/// * `from_version_id` - Version de antes, `None` para el horario actual
+/// * `to_version_id` - Version de despues, `None` para el horario actual
+/// Retorna las clases movidas, con otro profesor o con otra aula, agrupadas por profesor y grupo
+#[tauri::command(rename_all = "snake_case")]
+pub async fn compare_schedule_versions(
+    pool: tauri::State<'_, AppState>,
+    from_version_id: Option<i64>,
+    to_version_id: Option<i64>,
+) -> Result<ScheduleComparison, String> {
+    let (comparison, _) = compare_versions(&pool.db, from_version_id, to_version_id).await?;
+    Ok(comparison)
+}
+
+/// Funcion para obtener la hoja de cambios imprimible entre dos versiones
+/// Mismos argumentos que `compare_schedule_versions`, retorna el texto listo para imprimir
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_change_sheet(
+    pool: tauri::State<'_, AppState>,
+    from_version_id: Option<i64>,
+    to_version_id: Option<i64>,
+) -> Result<String, String> {
+    let (comparison, title) = compare_versions(&pool.db, from_version_id, to_version_id).await?;
+    Ok(format_change_sheet(&comparison, &title))
+}
+
+// Compara dos versiones (o el horario actual) y arma el titulo de la hoja de cambios
+async fn compare_versions(
+    db: &DbPool,
+    from_version_id: Option<i64>,
+    to_version_id: Option<i64>,
+) -> Result<(ScheduleComparison, String), String> {
+    let snapshot = load_snapshot(db).await?;
+
+    let load = |version_id: Option<i64>| {
+        let current = snapshot.assignments.clone();
+        async move {
+            match version_id {
+                Some(id) => {
+                    let version = fetch_version(db, id).await?;
+                    Ok::<_, String>((fetch_version_assignments(db, id).await?, version.name))
+                }
+                None => Ok((current, "Horario actual".to_string())),
+            }
+        }
+    };
+
+    let (before, from_name) = load(from_version_id).await?;
+    let (after, to_name) = load(to_version_id).await?;
+
+    let comparison = compare_schedules(&snapshot, &before, &after);
+    let title = format!("Cambios de horario: {} -> {}", from_name, to_name);
+
+    Ok((comparison, title))
+}
+
+const SELECT_VERSIONS: &str = "SELECT v.id, v.name, v.note, v.created_at,
+        (SELECT COUNT(*) FROM schedule_version_assignments a WHERE a.version_id = v.id) AS assignment_count
+    FROM schedule_versions v";
+
+/// Funcion que guarda una copia del horario actual y retorna el id de la version
+pub async fn snapshot_current_schedule(
+    db: &DbPool,
+    name: &str,
+    note: Option<&str>,
+) -> Result<i64, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("The version needs a name".to_string());
+    }
+
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction! {}", e))?;
+
+    let id = sqlx::query(&format!(
+        "INSERT INTO schedule_versions (name, note, created_at, term_id) VALUES (?1, ?2, ?3, {})",
+        ACTIVE_TERM
+    ))
+    .bind(name)
+    .bind(note)
+    .bind(chrono::Utc::now().timestamp())
+    .execute(&mut tx)
+    .await
+    .map_err(|e| format!("Error creating schedule version: {}", e))?
+    .last_insert_rowid();
+
+    sqlx::query(
+        "INSERT INTO schedule_version_assignments
+            (version_id, group_id, day, module_index, subject_id, teacher_id, classroom_id)
+        SELECT ?1, group_id, day, module_index, subject_id, teacher_id, classroom_id
+        FROM assignments",
+    )
+    .bind(id)
+    .execute(&mut tx)
+    .await
+    .map_err(|e| format!("Error copying assignments to version: {}", e))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(id)
+}
+
+/// Funcion que retorna los datos de una version del periodo activo
+pub async fn fetch_version(db: &DbPool, version_id: i64) -> Result<ScheduleVersion, String> {
+    sqlx::query_as::<_, ScheduleVersion>(&format!(
+        "{} WHERE v.id = ?1 AND v.term_id = {}",
+        SELECT_VERSIONS, ACTIVE_TERM
+    ))
+    .bind(version_id)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| format!("Failed to get schedule version: {}", e))?
+    .ok_or_else(|| format!("Schedule version {} does not exist", version_id))
+}
+
+/// Funcion que retorna las asignaciones guardadas en una version
+/// Las materias que ya no existen se regresan sin nombre ni color
+pub async fn fetch_version_assignments(
+    db: &DbPool,
+    version_id: i64,
+) -> Result<Vec<Assignment>, String> {
+    sqlx::query_as::<_, Assignment>(
+        r#"
+        SELECT NULL as id, a.group_id, a.day, a.module_index, a.teacher_id,
+               COALESCE(a.classroom_id, 0) as classroom_id, a.subject_id,
+               COALESCE(s.name, '') as subject_name, COALESCE(s.color, '') as subject_color,
+               COALESCE(s.shorten, '') as subject_shorten
+        FROM schedule_version_assignments a
+        LEFT JOIN subjects s ON a.subject_id = s.id
+        WHERE a.version_id = ?1
+        ORDER BY a.group_id, a.day, a.module_index
+        "#,
+    )
+    .bind(version_id)
+    .fetch(db)
+    .try_collect()
+    .await
+    .map_err(|e| {
+        format!(
+            "An error occurred while getting the version assignments: {}",
+            e
+        )
+    })
+}
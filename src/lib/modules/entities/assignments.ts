@@ -118,6 +118,12 @@ export async function deleteAssignment(assign_id: unknown): Promise<void> {
   }
 }
 
+// Resultado de guardar una asignacion (ver `SaveResult` en el backend)
+interface SaveResult {
+  saved: boolean;
+  violations: { kind: string; severity: "hard" | "soft"; message: string }[];
+}
+
 export async function saveAssignment(
   groupId: number,
   day: string,
@@ -146,13 +152,39 @@ export async function saveAssignment(
       });
       return;
     }
-    await invoke("save_assignment", {
-      group_id: groupId,
-      day,
-      module_index: moduleIndex,
-      subject_id: subjectId,
-      teacher_id: teacherId
-    });
+    const save = (force: boolean) =>
+      invoke<SaveResult>("save_assignment", {
+        group_id: groupId,
+        day,
+        module_index: moduleIndex,
+        subject_id: subjectId,
+        teacher_id: teacherId,
+        force
+      });
+
+    let result = await save(false);
+
+    // Si solo rompe restricciones blandas se puede guardar de todos modos
+    if (
+      !result.saved &&
+      result.violations.length > 0 &&
+      result.violations.every((v) => v.severity === "soft") &&
+      confirm(
+        `El cambio rompe estas restricciones:\n${result.violations.map((v) => v.message).join("\n")}\n¿Guardar de todos modos?`
+      )
+    ) {
+      result = await save(true);
+    }
+
+    // El backend no guarda si el cambio rompe alguna restriccion
+    if (!result.saved) {
+      addNotification({
+        message: `No se pudo asignar: ${result.violations.map((v) => v.message).join(", ")}`,
+        type: "error",
+        timeout: 2500
+      });
+      return;
+    }
 
     // Update local store
     const key = `${groupId}-${day}-${moduleIndex}`;
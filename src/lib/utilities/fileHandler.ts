@@ -4,19 +4,122 @@ import { loadGroups } from "$lib/modules/entities/groupsStore";
 import { loadSubjects, loadSubjectsWithTeachers } from "$lib/modules/entities/subjectsStore";
 import { loadTeachers } from "$lib/modules/entities/teachersStore";
 import { invoke } from "@tauri-apps/api"; 
+import PasswordModal from "$lib/components/buttons/PasswordModal.svelte";
 
-export async function exportFile(): Promise<void> {
-  await invoke("export_file");
+// Con contraseña el archivo se cifra y se necesita la misma para importarlo
+export async function exportFile(password?: string): Promise<void> {
+  await invoke("export_file", { password });
 }
 
-export async function importFile(): Promise<void> {
-  await invoke("import_file");
+export interface ImportSummary {
+  subjects: number;
+  teachers: number;
+  teacher_subjects: number;
+  groups: number;
+  group_subjects: number;
+  students: number;
+  classrooms: number;
+  assignments: number;
+  config: boolean;
+  school: boolean;
+  // Versiones, cambios por fecha, cierres, ausencias, periodos y festivos que se borraron
+  discarded: number;
+  backup: string | null;
+}
+
+// Error de import_file; `kind` indica si falta la contraseña o no es la correcta
+export interface ImportError {
+  kind: "password_required" | "wrong_password" | "other";
+  message: string;
+}
+
+// Abre el selector de archivos; retorna null si el usuario cancela
+export async function pickRosterFile(): Promise<string | null> {
+  return await invoke<string | null>("pick_roster_file");
+}
+
+export async function importFile(path: string, password?: string): Promise<ImportSummary> {
+  const summary = await invoke<ImportSummary>("import_file", { path, password });
   await loadTeachers();
   await loadSubjects();
   await loadSubjectsWithTeachers();
   await loadGroups();
   await loadClassrooms();
   await loadAssignments();
+  return summary;
+}
+
+interface PasswordRequest {
+  title: string;
+  message: string;
+  confirm?: boolean;
+  allowEmpty?: boolean;
+  confirmText?: string;
+}
+
+// Muestra el modal de contraseña y espera la respuesta; null si el usuario cancela
+function askPassword(request: PasswordRequest): Promise<string | null> {
+  return new Promise((resolve) => {
+    const modal = new PasswordModal({
+      target: document.body,
+      props: {
+        ...request,
+        isOpen: true,
+        onConfirm: (password: string) => {
+          modal.$destroy();
+          resolve(password);
+        },
+        onCancel: () => {
+          modal.$destroy();
+          resolve(null);
+        },
+      },
+    });
+  });
+}
+
+// Pide una contraseña opcional (dos veces) antes de exportar; retorna false si el usuario cancela
+export async function exportFileAskingPassword(): Promise<boolean> {
+  const password = await askPassword({
+    title: "Cifrar archivo",
+    message: "Contraseña para cifrar el archivo (déjala vacía para no cifrarlo)",
+    confirm: true,
+    allowEmpty: true,
+    confirmText: "Exportar",
+  });
+  if (password === null) return false;
+  await exportFile(password || undefined);
+  return true;
+}
+
+function isImportError(error: unknown): error is ImportError {
+  return typeof error === "object" && error !== null && "kind" in error && "message" in error;
+}
+
+// Elige un archivo y lo importa; si esta cifrado pide la contraseña hasta que sea correcta
+// sin volver a abrir el selector; retorna null si el usuario cancela
+export async function importFileAskingPassword(): Promise<ImportSummary | null> {
+  const path = await pickRosterFile();
+  if (path === null) return null;
+
+  let password: string | undefined;
+  for (;;) {
+    try {
+      return await importFile(path, password);
+    } catch (error) {
+      if (!isImportError(error)) throw error;
+      if (error.kind === "other") throw error.message;
+      const answer = await askPassword({
+        title: "Archivo cifrado",
+        message:
+          error.kind === "wrong_password"
+            ? "Contraseña incorrecta, vuelve a intentarlo"
+            : "El archivo está cifrado, escribe la contraseña",
+      });
+      if (answer === null) return null;
+      password = answer;
+    }
+  }
 }
 
 export async function deleteAll(): Promise<void> {